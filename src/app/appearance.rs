@@ -0,0 +1,120 @@
+use eframe::egui::{self, FontData, FontDefinitions, FontFamily};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub const APPEARANCE_STORAGE_KEY: &str = "appearance";
+
+/// egui's body text size, which `font_size` is relative to.
+const DEFAULT_FONT_SIZE: f32 = 12.5;
+
+const CUSTOM_FONT: &str = "custom_monospace";
+const FALLBACK_FONT: &str = "unicode_fallback";
+
+/// System fonts with broad CJK coverage, the first one found is used as a
+/// fallback behind egui's bundled fonts, which only cover Latin and emoji.
+const FALLBACK_FONTS: &[&str] = &[
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+    "/Library/Fonts/Arial Unicode.ttf",
+    "C:\\Windows\\Fonts\\msyh.ttc",
+    "C:\\Windows\\Fonts\\YuGothR.ttc",
+];
+
+/// How large the UI is drawn, kept between launches. egui's defaults are
+/// tiny on some HiDPI setups that report a scale factor of 1.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Appearance {
+    /// Multiplies the scale factor the system reports.
+    pub ui_scale: f32,
+    /// Size of body text in points; the other text styles follow it.
+    pub font_size: f32,
+    /// Font file used for the curl box, previews and other monospace text.
+    pub monospace_font: Option<String>,
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Self {
+            ui_scale: 1.0,
+            font_size: DEFAULT_FONT_SIZE,
+            monospace_font: None,
+        }
+    }
+}
+
+impl Appearance {
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        storage
+            .and_then(|storage| eframe::get_value(storage, APPEARANCE_STORAGE_KEY))
+            .unwrap_or_default()
+    }
+
+    pub fn apply(&self, ctx: &egui::Context) {
+        let native = ctx
+            .input(|input| input.viewport().native_pixels_per_point)
+            .unwrap_or(1.0);
+        ctx.set_pixels_per_point(native * self.ui_scale);
+
+        let factor = self.font_size / DEFAULT_FONT_SIZE;
+        let mut style = (*ctx.style()).clone();
+        style.text_styles = egui::Style::default()
+            .text_styles
+            .into_iter()
+            .map(|(text_style, mut font)| {
+                font.size *= factor;
+                (text_style, font)
+            })
+            .collect();
+        ctx.set_style(style);
+    }
+
+    /// Installs the custom monospace font, if any, in front of egui's own,
+    /// and a system font with CJK coverage behind them. On error the
+    /// default fonts are kept.
+    pub fn apply_fonts(&self, ctx: &egui::Context) -> Result<(), String> {
+        let mut fonts = FontDefinitions::default();
+
+        if let Some(path) = FALLBACK_FONTS.iter().find(|path| Path::new(path).is_file()) {
+            match std::fs::read(path) {
+                Ok(bytes) => {
+                    println!("Using {} as fallback font", path);
+                    fonts
+                        .font_data
+                        .insert(FALLBACK_FONT.to_string(), FontData::from_owned(bytes));
+                    for family in [FontFamily::Proportional, FontFamily::Monospace] {
+                        fonts
+                            .families
+                            .entry(family)
+                            .or_default()
+                            .push(FALLBACK_FONT.to_string());
+                    }
+                }
+                Err(e) => println!("Could not read fallback font {}: {}", path, e),
+            }
+        }
+
+        let mut result = Ok(());
+        if let Some(path) = &self.monospace_font {
+            match std::fs::read(path) {
+                Ok(bytes) => {
+                    fonts
+                        .font_data
+                        .insert(CUSTOM_FONT.to_string(), FontData::from_owned(bytes));
+                    fonts
+                        .families
+                        .entry(FontFamily::Monospace)
+                        .or_default()
+                        .insert(0, CUSTOM_FONT.to_string());
+                }
+                Err(e) => result = Err(format!("Could not read font {}: {}", path, e)),
+            }
+        }
+
+        ctx.set_fonts(fonts);
+        result
+    }
+}
@@ -0,0 +1,53 @@
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+pub const GEOMETRY_STORAGE_KEY: &str = "window_geometry";
+
+/// Position and size of the main window, restored on the next launch.
+/// Kept in points, like every viewport command takes them.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    /// Top left corner of the window frame, if the platform reports it.
+    pub position: Option<[f32; 2]>,
+    pub size: [f32; 2],
+    #[serde(default)]
+    pub maximized: bool,
+}
+
+impl WindowGeometry {
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Option<Self> {
+        storage.and_then(|storage| eframe::get_value(storage, GEOMETRY_STORAGE_KEY))
+    }
+
+    /// Current geometry of the viewport `ctx` draws. `size` replaces the
+    /// reported size, e.g. the full size while the window is compact.
+    pub fn capture(ctx: &egui::Context, size: Option<egui::Vec2>) -> Option<Self> {
+        ctx.input(|input| {
+            let viewport = input.viewport();
+            // Minimized windows report a meaningless geometry
+            if viewport.minimized == Some(true) {
+                return None;
+            }
+            let size = size.or(viewport.inner_rect.map(|rect| rect.size()))?;
+            Some(Self {
+                position: viewport.outer_rect.map(|rect| rect.min.into()),
+                size: size.into(),
+                maximized: viewport.maximized == Some(true),
+            })
+        })
+    }
+
+    /// Moves and resizes the window, never below `min_size`.
+    pub fn restore(&self, ctx: &egui::Context, min_size: egui::Vec2) {
+        let size = egui::Vec2::from(self.size).max(min_size);
+        ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(size));
+        if let Some(position) = self.position {
+            // Keeps the title bar reachable if a monitor was unplugged since
+            let position = egui::Pos2::from(position).max(egui::Pos2::ZERO);
+            ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(position));
+        }
+        if self.maximized {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Maximized(true));
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use crate::upload::{FileStatus, FilterOptions, Operation, UploadStatus};
+use crate::utils::git_info::GitInfo;
+use crate::utils::time_format::TimeFormat;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+pub const HISTORY_STORAGE_KEY: &str = "history";
+
+/// Older runs are dropped once this many are stored.
+const MAX_RUNS: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileOutcome {
+    Uploaded,
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunFile {
+    pub relative_path: String,
+    pub size: u64,
+    pub outcome: FileOutcome,
+}
+
+/// What an upload run covered and how each file ended up.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub started_at: SystemTime,
+    pub duration: Duration,
+    pub folder_path: Option<String>,
+    pub selected_sections: Vec<String>,
+    pub filter_options: FilterOptions,
+    #[serde(default)]
+    pub git: Option<GitInfo>,
+    /// Project the run uploaded to, to refuse repeating it into another one.
+    #[serde(default)]
+    pub organization_id: Option<String>,
+    #[serde(default)]
+    pub project_id: Option<String>,
+    pub files: Vec<RunFile>,
+}
+
+impl RunRecord {
+    /// Builds a record from the final upload statuses, keeping the last
+    /// outcome of each file.
+    pub fn from_statuses(
+        statuses: &[FileStatus],
+        duration: Duration,
+        folder_path: Option<String>,
+        selected_sections: Vec<String>,
+        filter_options: FilterOptions,
+    ) -> Self {
+        let mut files: Vec<RunFile> = Vec::new();
+        for status in statuses {
+            if status.operation != Operation::Upload {
+                continue;
+            }
+            let Some(relative_path) = &status.relative_path else {
+                continue;
+            };
+            let outcome = match status.status {
+                UploadStatus::Success => FileOutcome::Uploaded,
+                UploadStatus::Skipped(_) => FileOutcome::Skipped,
+                UploadStatus::Error(_) => FileOutcome::Failed,
+                _ => continue,
+            };
+            let file = RunFile {
+                relative_path: relative_path.clone(),
+                size: status.size.unwrap_or(0),
+                outcome,
+            };
+            match files
+                .iter_mut()
+                .find(|existing| existing.relative_path == file.relative_path)
+            {
+                Some(existing) => *existing = file,
+                None => files.push(file),
+            }
+        }
+
+        Self {
+            started_at: SystemTime::now()
+                .checked_sub(duration)
+                .unwrap_or_else(SystemTime::now),
+            duration,
+            folder_path,
+            selected_sections,
+            filter_options,
+            git: None,
+            organization_id: None,
+            project_id: None,
+            files,
+        }
+    }
+
+    /// When the run happened and which folder it covered.
+    pub fn label(&self) -> String {
+        let folder = self
+            .folder_path
+            .as_deref()
+            .and_then(|path| Path::new(path).file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "no folder".to_string());
+        match &self.git {
+            Some(git) => format!("{} - {}", TimeFormat::ago(self.started_at), git.label()),
+            None => format!("{} - {}", TimeFormat::ago(self.started_at), folder),
+        }
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|file| file.size).sum()
+    }
+
+    pub fn count(&self, outcome: FileOutcome) -> usize {
+        self.files.iter().filter(|f| f.outcome == outcome).count()
+    }
+
+    /// Share of attempted files that failed, skipped ones left out.
+    pub fn failure_rate(&self) -> f32 {
+        let failed = self.count(FileOutcome::Failed);
+        let attempted = failed + self.count(FileOutcome::Uploaded);
+        if attempted == 0 {
+            return 0.0;
+        }
+        failed as f32 / attempted as f32
+    }
+}
+
+/// What changed from an earlier run to a later one.
+pub struct RunComparison {
+    /// Files only in the later run.
+    pub added: Vec<String>,
+    /// Files only in the earlier run.
+    pub removed: Vec<String>,
+    /// Files that failed in the later run but not in the earlier one.
+    pub now_failing: Vec<String>,
+    /// Files that failed in the earlier run and no longer do.
+    pub fixed: Vec<String>,
+    pub size_delta: i64,
+    /// Later duration minus earlier duration, in seconds.
+    pub duration_delta: f64,
+}
+
+impl RunComparison {
+    pub fn between(earlier: &RunRecord, later: &RunRecord) -> Self {
+        let earlier_files: HashMap<&str, FileOutcome> = earlier
+            .files
+            .iter()
+            .map(|file| (file.relative_path.as_str(), file.outcome))
+            .collect();
+        let later_files: HashMap<&str, FileOutcome> = later
+            .files
+            .iter()
+            .map(|file| (file.relative_path.as_str(), file.outcome))
+            .collect();
+
+        let mut comparison = Self {
+            added: Vec::new(),
+            removed: Vec::new(),
+            now_failing: Vec::new(),
+            fixed: Vec::new(),
+            size_delta: later.total_size() as i64 - earlier.total_size() as i64,
+            duration_delta: later.duration.as_secs_f64() - earlier.duration.as_secs_f64(),
+        };
+
+        for file in &later.files {
+            let path = file.relative_path.clone();
+            match (earlier_files.get(path.as_str()), file.outcome) {
+                (None, _) => comparison.added.push(path),
+                (Some(FileOutcome::Failed), FileOutcome::Failed) => {}
+                (Some(FileOutcome::Failed), _) => comparison.fixed.push(path),
+                (Some(_), FileOutcome::Failed) => comparison.now_failing.push(path),
+                _ => {}
+            }
+        }
+        comparison.removed = earlier
+            .files
+            .iter()
+            .filter(|file| !later_files.contains_key(file.relative_path.as_str()))
+            .map(|file| file.relative_path.clone())
+            .collect();
+
+        comparison
+    }
+}
+
+/// Past upload runs, oldest first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunHistory {
+    pub runs: Vec<RunRecord>,
+}
+
+impl RunHistory {
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        storage
+            .and_then(|storage| eframe::get_value(storage, HISTORY_STORAGE_KEY))
+            .unwrap_or_default()
+    }
+
+    pub fn push(&mut self, record: RunRecord) {
+        self.runs.push(record);
+        if self.runs.len() > MAX_RUNS {
+            self.runs.drain(..self.runs.len() - MAX_RUNS);
+        }
+    }
+
+    pub fn average_duration(&self) -> Option<Duration> {
+        if self.runs.is_empty() {
+            return None;
+        }
+        let total: Duration = self.runs.iter().map(|run| run.duration).sum();
+        Some(total / self.runs.len() as u32)
+    }
+
+    /// Files that failed in the most runs, with the number of those runs.
+    pub fn most_failing(&self, limit: usize) -> Vec<(&str, usize)> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for run in &self.runs {
+            for file in &run.files {
+                if file.outcome == FileOutcome::Failed {
+                    *counts.entry(file.relative_path.as_str()).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let mut counts: Vec<(&str, usize)> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        counts.truncate(limit);
+        counts
+    }
+}
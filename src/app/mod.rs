@@ -1,341 +1,3056 @@
+mod appearance;
+mod geometry;
+mod history;
+mod passes;
+mod profile;
+mod progress;
+mod sections;
+mod session;
+mod settings;
 mod state;
+mod trim;
 mod ui;
+mod window;
 
-use crate::upload::{FileProcessor, FileStatus, UploadStatus, UploadedFile};
+use crate::upload::{
+    ClaudeClient, ConflictResolution, ContentAnalysis, ContentMatchCache, DiscoveredFile,
+    DiscoveryKey, DiscoveryResult, DiscoveryScan, DryRun, ErrorKind, FileList, FileProcessor,
+    FileStatus, FilesApiClient, FilterOptions, Operation, PathFilter, Permissions, PlanPolicy,
+    Preset, ProjectBackend, RateLimiter, RemoteDoc, ReportExclusion, ReportFile, RunControl,
+    SectionTag, SelectionReport, SensitiveFile, SensitivePath, StatusBatch, SyncInfo, SyncNote,
+    UploadError, UploadPlan, UploadStatus, UploadedFile, API_KEY_ENV,
+};
 use crate::utils::claude_keep::ClaudeKeepConfig;
+use crate::utils::claude_keep_template::ClaudeKeepTemplate;
 use crate::utils::curl_parser::CurlParser;
+use crate::utils::git_info::GitInfo;
+use crate::utils::ignore_import::IgnoreImport;
+use crate::utils::session_store::{SessionStore, StoredSession};
+use crate::utils::single_instance::HandOff;
+use crate::utils::temp_workspace::TempWorkspace;
+use appearance::{Appearance, APPEARANCE_STORAGE_KEY};
 use eframe::{egui, App};
+use geometry::{WindowGeometry, GEOMETRY_STORAGE_KEY};
+use history::{FileOutcome, RunHistory, RunRecord, HISTORY_STORAGE_KEY};
+use passes::{PassPlan, PassPlans, PASSES_STORAGE_KEY};
+use profile::{Profile, Profiles, PROFILES_STORAGE_KEY};
+use progress::{Phase, RunProgress};
 use reqwest::header::HeaderMap;
-pub use state::{ActionProgress, UploadState};
-use std::path::Path;
+use sections::SectionIndex;
+use session::{SessionHealth, SessionUnlock};
+pub use settings::Settings;
+pub use state::UploadState;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::mpsc as std_mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use trim::{TrimProposal, TrimStrategy};
+use window::ProfileWindow;
+
+pub const APP_TITLE: &str = "Claude.ai File Uploader";
+
+const BUSY_REPAINT_INTERVAL: Duration = Duration::from_millis(100);
+const SESSION_REPAINT_INTERVAL: Duration = Duration::from_secs(30);
+const BRANCH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// Files named in the suggested chat prompt before the rest is summed up.
+const CHAT_PROMPT_FILES: usize = 20;
+const COMPACT_SIZE: egui::Vec2 = egui::vec2(420.0, 64.0);
+const MIN_WINDOW_SIZE: egui::Vec2 = egui::vec2(400.0, 500.0);
+/// How long closing the window waits for a cancelled run to wind down.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
 
 #[derive(Default)]
 pub struct ClaudeUploader {
     curl_text: String,
+    /// Hint shown when the pasted curl is not the docs request.
+    curl_warning: Option<String>,
+    session_health: SessionHealth,
     folder_path: Option<String>,
     state: UploadState,
     curl_parser: CurlParser,
+    settings: Settings,
+    profiles: Profiles,
+    profile_name: String,
+    history: RunHistory,
+    /// Saved splits of folders too large for their project.
+    pass_plans: PassPlans,
+    /// Whether uploads need an approved plan, locked with the approval key.
+    plan_policy: PlanPolicy,
+    rate_limiter: RateLimiter,
+    /// Session of each profile other than the active one, with the
+    /// organization and project picked for it, by profile key.
+    sessions: HashMap<String, StoredSession>,
+    /// Where the main window keeps sessions between launches.
+    session_store: Option<SessionStore>,
+    /// Passphrase prompt, shown while sessions wait for one.
+    session_unlock: Option<SessionUnlock>,
+    /// Key for profiles that upload through the Files API, taken from
+    /// `ANTHROPIC_API_KEY` when set. Kept in memory only.
+    api_key: String,
+    /// Scratch space for intermediate files, `None` if it couldn't be created.
+    /// There is one per process, shared by all windows.
+    workspace: Option<Arc<TempWorkspace>>,
+    /// Content filter results, kept across rediscoveries.
+    content_matches: ContentMatchCache,
+    /// Last title sent to the window, to only send changes.
+    window_title: String,
+    /// Profile an extra window was opened for, `None` for the main window.
+    window_profile: Option<String>,
+    /// Profiles opened in windows of their own, only from the main window.
+    windows: Vec<ProfileWindow>,
+    windows_opened: usize,
+    /// When closing was requested during a run. The window closes once the
+    /// run wound down or the grace period is over.
+    shutdown_started: Option<Instant>,
+    /// Requests of later launches, which exit after handing them over.
+    hand_offs: Option<std_mpsc::Receiver<HandOff>>,
+    /// Show only a slim progress strip that stays on top.
+    compact: bool,
+    /// Window size to go back to when leaving compact mode.
+    expanded_size: Option<egui::Vec2>,
+    appearance: Appearance,
+    /// Appearance last applied to the context, to only apply changes.
+    applied_appearance: Option<Appearance>,
+    /// UI scale while its slider is dragged, applied on release.
+    ui_scale_draft: Option<f32>,
+    /// Geometry from the last launch, applied on the first frame.
+    restore_geometry: Option<WindowGeometry>,
+    /// Geometry of the main window as of the last frame, saved on exit.
+    geometry: Option<WindowGeometry>,
+    /// Answers every client's requests from recorded exchanges.
+    #[cfg(test)]
+    fixtures: Option<crate::upload::Fixtures>,
 }
 
 impl ClaudeUploader {
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         println!("Initializing Claude.ai File Uploader");
+        let mut uploader =
+            Self::with_stores(Profiles::load(cc.storage), RunHistory::load(cc.storage));
+        uploader.appearance = Appearance::load(cc.storage);
+        uploader.pass_plans = PassPlans::load(cc.storage);
+        uploader.plan_policy = PlanPolicy::load();
+        uploader.workspace = TempWorkspace::create()
+            .map_err(|e| println!("Failed to create a temp workspace: {}", e))
+            .ok()
+            .map(Arc::new);
+        uploader.restore_geometry = WindowGeometry::load(cc.storage);
+
+        if let Some(index) = uploader.profiles.active {
+            uploader.activate_profile(index);
+        }
+        match SessionStore::open() {
+            Ok(mut store) => {
+                let sessions = store.load().unwrap_or_else(|e| {
+                    println!("Could not restore sessions: {}", e);
+                    HashMap::new()
+                });
+                uploader.session_store = Some(store);
+                uploader.restore_sessions(sessions);
+            }
+            Err(e) => {
+                println!("No keychain for sessions ({}), asking for a passphrase", e);
+                uploader.session_unlock = Some(SessionUnlock {
+                    existing: SessionStore::has_locked_sessions(),
+                    ..SessionUnlock::default()
+                });
+            }
+        }
+        uploader
+    }
+
+    fn with_stores(profiles: Profiles, history: RunHistory) -> Self {
         Self {
             curl_text: String::new(),
+            curl_warning: None,
+            session_health: SessionHealth::default(),
             folder_path: None,
             state: UploadState::default(),
             curl_parser: CurlParser::new(),
+            settings: Settings::default(),
+            profiles,
+            profile_name: String::new(),
+            history,
+            pass_plans: PassPlans::default(),
+            plan_policy: PlanPolicy::default(),
+            rate_limiter: RateLimiter::default(),
+            sessions: HashMap::new(),
+            session_store: None,
+            session_unlock: None,
+            api_key: std::env::var(API_KEY_ENV).unwrap_or_default(),
+            workspace: None,
+            content_matches: ContentMatchCache::default(),
+            window_title: APP_TITLE.to_string(),
+            window_profile: None,
+            windows: Vec::new(),
+            windows_opened: 0,
+            shutdown_started: None,
+            hand_offs: None,
+            compact: false,
+            expanded_size: None,
+            appearance: Appearance::default(),
+            applied_appearance: None,
+            ui_scale_draft: None,
+            restore_geometry: None,
+            geometry: None,
+            #[cfg(test)]
+            fixtures: None,
+        }
+    }
+
+    pub fn with_hand_offs(mut self, hand_offs: std_mpsc::Receiver<HandOff>) -> Self {
+        self.hand_offs = Some(hand_offs);
+        self
+    }
+
+    /// Brings the window to the front for a later launch, opening its
+    /// folder unless that would pull the folder from under a run.
+    fn handle_hand_offs(&mut self, ctx: &egui::Context) {
+        let Some(hand_offs) = &self.hand_offs else {
+            return;
+        };
+        let requests: Vec<HandOff> = hand_offs.try_iter().collect();
+        for request in requests {
+            println!("A later launch handed over: {:?}", request);
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+            ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+            let HandOff::OpenFolder(folder) = request else {
+                continue;
+            };
+            if self.has_running_work() {
+                self.state.error_message = Some(format!(
+                    "Not opening {}, a run is in progress",
+                    folder.display()
+                ));
+            } else {
+                self.open_folder(folder);
+            }
+        }
+    }
+
+    pub fn is_compact(&self) -> bool {
+        self.compact
+    }
+
+    /// Shrinks the window to a progress strip kept above other windows, or
+    /// brings back the full window at its previous size.
+    pub fn set_compact(&mut self, ctx: &egui::Context, compact: bool) {
+        if compact == self.compact {
+            return;
+        }
+        self.compact = compact;
+        if compact {
+            self.expanded_size = ctx.input(|input| input.viewport().inner_rect.map(|r| r.size()));
+            ctx.send_viewport_cmd(egui::ViewportCommand::MinInnerSize(COMPACT_SIZE));
+            ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(COMPACT_SIZE));
+            ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(
+                egui::WindowLevel::AlwaysOnTop,
+            ));
+        } else {
+            let size = self
+                .expanded_size
+                .take()
+                .unwrap_or(egui::vec2(600.0, 600.0));
+            ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(
+                egui::WindowLevel::Normal,
+            ));
+            ctx.send_viewport_cmd(egui::ViewportCommand::MinInnerSize(MIN_WINDOW_SIZE));
+            ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(size.max(MIN_WINDOW_SIZE)));
+        }
+    }
+
+    /// Opens the active profile in a window of its own, starting from a copy
+    /// of the settings and sessions. Profiles, run history and pass plans are
+    /// shared with this window, see [`Self::lend_stores`].
+    pub fn open_profile_window(&mut self) {
+        let Some(index) = self.profiles.active else {
+            return;
+        };
+        let name = self.profiles.profiles[index].name.clone();
+        println!("Opening profile '{}' in a new window", name);
+
+        let mut uploader = Self::with_stores(Profiles::default(), RunHistory::default());
+        uploader.settings = self.settings.clone();
+        uploader.appearance = self.appearance.clone();
+        uploader.plan_policy = self.plan_policy.clone();
+        uploader.workspace = self.workspace.clone();
+        uploader.api_key = self.api_key.clone();
+        uploader.sessions = self.sessions.clone();
+        if !self.curl_text.is_empty() {
+            uploader
+                .sessions
+                .insert(self.profile_key(), self.current_session());
+        }
+        uploader.window_profile = Some(name);
+        self.lend_stores(&mut uploader, |uploader| uploader.activate_profile(index));
+
+        self.windows_opened += 1;
+        let id = egui::ViewportId::from_hash_of(("profile_window", self.windows_opened));
+        self.windows.push(ProfileWindow::new(id, uploader));
+    }
+
+    /// Runs `f` on a profile window's uploader with this window's profiles,
+    /// run history and pass plans, the copies that get saved. They are moved
+    /// over rather than cloned, and each window keeps its own active profile.
+    fn lend_stores<R>(&mut self, window: &mut Self, f: impl FnOnce(&mut Self) -> R) -> R {
+        self.swap_stores(window);
+        let result = f(window);
+        self.swap_stores(window);
+        result
+    }
+
+    fn swap_stores(&mut self, other: &mut Self) {
+        std::mem::swap(&mut self.profiles, &mut other.profiles);
+        std::mem::swap(&mut self.profiles.active, &mut other.profiles.active);
+        std::mem::swap(&mut self.history, &mut other.history);
+        std::mem::swap(&mut self.pass_plans, &mut other.pass_plans);
+    }
+
+    pub fn is_profile_window(&self) -> bool {
+        self.window_profile.is_some()
+    }
+
+    fn frame(&mut self, ctx: &egui::Context) {
+        if self.applied_appearance.as_ref() != Some(&self.appearance) {
+            let font_changed = self.applied_appearance.as_ref().map(|a| &a.monospace_font)
+                != Some(&self.appearance.monospace_font);
+            if font_changed {
+                if let Err(e) = self.appearance.apply_fonts(ctx) {
+                    println!("{}", e);
+                    self.state.error_message = Some(e);
+                }
+            }
+            self.appearance.apply(ctx);
+            self.applied_appearance = Some(self.appearance.clone());
+        }
+        if let Some(geometry) = self.restore_geometry.take() {
+            geometry.restore(ctx, MIN_WINDOW_SIZE);
+        }
+        if !self.is_profile_window() {
+            let size = self.expanded_size.filter(|_| self.compact);
+            if let Some(geometry) = WindowGeometry::capture(ctx, size) {
+                self.geometry = Some(geometry);
+            }
+        }
+        self.update_state(ctx);
+        if !self.is_profile_window() {
+            self.handle_hand_offs(ctx);
+            self.handle_close_request(ctx);
+        }
+        self.update_window_title(ctx);
+        self.render(ctx);
+        let mut windows = std::mem::take(&mut self.windows);
+        windows.retain_mut(|window| window.show(ctx, self));
+        self.windows = windows;
+    }
+
+    pub fn open_folder(&mut self, path: PathBuf) {
+        self.folder_path = Some(path.display().to_string());
+
+        // Load .claudekeep configuration
+        self.state.keep_config = ClaudeKeepConfig::from_file(&path);
+        self.state.presets = Preset::detect(&path);
+        self.state.selected_sections.clear();
+        self.state.use_file_list = false;
+        self.restart_discovery();
+    }
+
+    pub fn activate_profile(&mut self, index: usize) {
+        let previous_key = self.profile_key();
+        self.profiles.active = Some(index);
+        let Some(profile) = self.profiles.active().cloned() else {
+            self.profiles.active = None;
+            return;
+        };
+
+        println!("Switching to profile '{}'", profile.name);
+        if self.profile_key() != previous_key {
+            self.switch_session(previous_key);
+        }
+        self.profile_name = profile.name;
+        if let Some(folder_path) = profile.folder_path {
+            self.open_folder(PathBuf::from(folder_path));
+        }
+    }
+
+    /// Puts the current session away under `previous_key` and brings back the
+    /// active profile's, so one organization's headers never end up in
+    /// requests made for another profile.
+    fn switch_session(&mut self, previous_key: String) {
+        if !self.curl_text.is_empty() {
+            self.sessions.insert(previous_key, self.current_session());
+        }
+        let session = self
+            .sessions
+            .remove(&self.profile_key())
+            .unwrap_or_default();
+
+        self.curl_parser = CurlParser::new();
+        self.apply_session(session);
+        self.state.remote_docs = None;
+        self.state.selected_docs.clear();
+        self.state.opened_doc = None;
+        self.state.awaiting_reauth = false;
+    }
+
+    /// Brings back the sessions of the last launch, including the
+    /// organization and project picked for the active profile's. Sessions
+    /// pasted since are kept.
+    fn restore_sessions(&mut self, stored_sessions: HashMap<String, StoredSession>) {
+        let key = self.profile_key();
+        let restored = stored_sessions
+            .get(&key)
+            .filter(|session| self.curl_text.is_empty() || session.curl_text == self.curl_text)
+            .cloned();
+        for (key, session) in stored_sessions {
+            self.sessions.entry(key).or_insert(session);
+        }
+        self.sessions.remove(&key);
+        if let Some(session) = restored {
+            println!("Restored the session of '{}'", key);
+            self.apply_session(session);
+        }
+    }
+
+    /// The active profile's session with the organization and project
+    /// picked for it.
+    fn current_session(&self) -> StoredSession {
+        StoredSession {
+            curl_text: self.curl_text.clone(),
+            organization_override: self.curl_parser.organization_override.clone(),
+            project_override: self.curl_parser.project_override.clone(),
+        }
+    }
+
+    fn apply_session(&mut self, session: StoredSession) {
+        self.curl_text = session.curl_text;
+        self.curl_changed();
+        self.curl_parser.organization_override = session.organization_override;
+        self.curl_parser.project_override = session.project_override;
+    }
+
+    /// Opens the passphrase protected sessions on systems without a keychain.
+    pub fn unlock_sessions(&mut self) {
+        let Some(unlock) = &mut self.session_unlock else {
+            return;
+        };
+        let passphrase = std::mem::take(&mut unlock.passphrase);
+        let opened = SessionStore::unlock(&passphrase)
+            .and_then(|mut store| store.load().map(|sessions| (store, sessions)));
+        match opened {
+            Ok((store, sessions)) => {
+                println!("Unlocked {} saved sessions", sessions.len());
+                self.session_unlock = None;
+                self.session_store = Some(store);
+                self.restore_sessions(sessions);
+            }
+            Err(e) => unlock.error = Some(e),
+        }
+    }
+
+    /// Goes on without keeping sessions between launches.
+    pub fn skip_session_unlock(&mut self) {
+        println!("Sessions will not be kept between launches");
+        self.session_unlock = None;
+    }
+
+    /// Writes the sessions of all profiles to the session store.
+    fn persist_sessions(&mut self) {
+        if self.session_store.is_none() {
+            return;
+        }
+        let mut sessions = self.sessions.clone();
+        if !self.curl_text.is_empty() {
+            sessions.insert(self.profile_key(), self.current_session());
+        }
+        if let Some(store) = &mut self.session_store {
+            if let Err(e) = store.save(&sessions) {
+                println!("Failed to save sessions: {}", e);
+            }
+        }
+    }
+
+    /// Drops the active profile's session, here and on disk.
+    pub fn forget_session(&mut self) {
+        println!("Forgetting the session of '{}'", self.profile_key());
+        self.curl_text.clear();
+        self.sessions.remove(&self.profile_key());
+        self.curl_parser = CurlParser::new();
+        self.curl_changed();
+        self.state.remote_docs = None;
+        self.state.selected_docs.clear();
+        self.persist_sessions();
+    }
+
+    /// Whether the active profile uploads through the Files API.
+    pub fn uses_api_key(&self) -> bool {
+        self.profiles
+            .active()
+            .is_some_and(|profile| profile.use_api_key)
+    }
+
+    /// Whether there is an API key or a pasted session to upload with.
+    pub fn has_credentials(&self) -> bool {
+        if self.uses_api_key() {
+            !self.api_key.trim().is_empty()
+        } else {
+            !self.curl_text.is_empty()
+        }
+    }
+
+    pub fn remove_profile(&mut self) {
+        self.sessions.remove(&self.profile_key());
+        self.profiles.remove_active();
+    }
+
+    pub fn save_profile(&mut self) {
+        let name = self.profile_name.trim().to_string();
+        if name.is_empty() {
+            return;
+        }
+        // The organization the session was copied from, not one switched to
+        let mut parser = CurlParser::new();
+        let organization_id = parser
+            .parse(&self.curl_text)
+            .ok()
+            .and_then(|()| parser.organization_id);
+        self.profiles
+            .save_as(&name, self.folder_path.clone(), organization_id);
+    }
+
+    /// Filter rules in effect: the active profile's own, or the global ones.
+    fn filter_options(&self) -> &FilterOptions {
+        self.profiles
+            .active()
+            .and_then(|profile| profile.filter_options.as_ref())
+            .unwrap_or(&self.state.filter_options)
+    }
+
+    pub fn filter_options_mut(&mut self) -> &mut FilterOptions {
+        let global = &mut self.state.filter_options;
+        match self.profiles.active_mut() {
+            Some(Profile {
+                filter_options: Some(options),
+                ..
+            }) => options,
+            _ => global,
+        }
+    }
+
+    /// Attaches a copy of the current filter rules to the active profile, or
+    /// makes it use the global ones again.
+    pub fn set_profile_filters(&mut self, enabled: bool) {
+        let options = self.filter_options().clone();
+        if let Some(profile) = self.profiles.active_mut() {
+            profile.filter_options = enabled.then_some(options);
+        }
+    }
+
+    /// Merges the patterns of an ignore file in the selected folder into the
+    /// ignore rules in effect.
+    pub fn import_ignore_file(&mut self, file_name: &str, root_relative: bool) {
+        let Some(folder_path) = &self.folder_path else {
+            return;
+        };
+
+        let path = Path::new(folder_path).join(file_name);
+        self.state.import_message = Some(match IgnoreImport::read(&path, root_relative) {
+            Ok(imported) => {
+                let added = IgnoreImport::merge(
+                    &mut self.filter_options_mut().ignore_rules,
+                    &imported.patterns,
+                );
+                let mut message = format!("Imported {} new patterns from {}", added, file_name);
+                if imported.skipped > 0 {
+                    message.push_str(&format!(
+                        ", {} negated patterns were left out",
+                        imported.skipped
+                    ));
+                }
+                message
+            }
+            Err(e) => format!("Could not read {}: {}", file_name, e),
+        });
+    }
+
+    /// Name under which per-profile data such as the content cache is stored.
+    fn profile_key(&self) -> String {
+        self.profiles
+            .active()
+            .map(|profile| profile.name.clone())
+            .unwrap_or_else(|| "default".to_string())
+    }
+
+    /// What the current session may modify. Inspection mode overrides the
+    /// active profile.
+    pub fn permissions(&self) -> Permissions {
+        if self.settings.inspection_mode {
+            return Permissions::READ_ONLY;
+        }
+        self.profiles
+            .active()
+            .map(Profile::permissions)
+            .unwrap_or_default()
+    }
+
+    pub fn reset_upload_state(&mut self) {
+        println!("Resetting application state");
+        self.curl_text.clear();
+        self.curl_warning = None;
+        self.session_health = SessionHealth::default();
+        self.folder_path = None;
+        self.state.clear();
+        self.curl_parser = CurlParser::new();
+    }
+
+    /// Replaces the uploaded docs with fresh copies. The new docs are uploaded
+    /// first and the old ones are only deleted once every upload succeeded, so
+    /// a failed reupload never leaves the project empty.
+    pub fn delete_and_reupload(&mut self) {
+        if self.state.uploaded_files.is_empty() {
+            println!("No files to delete. Uploaded files list is empty.");
+            self.state.error_message = Some("No files to delete".to_string());
+            return;
+        }
+
+        println!("Starting delete and reupload process...");
+
+        let files_to_upload = self
+            .state
+            .discovery
+            .as_ref()
+            .map(|discovery| discovery.files.clone())
+            .unwrap_or_default();
+        if files_to_upload.is_empty() {
+            self.state.error_message = Some("No files to reupload".to_string());
+            return;
+        }
+
+        self.state.clear_statuses();
+        let replaced_files = std::mem::take(&mut self.state.uploaded_files);
+
+        self.state.progress = RunProgress::plan(&[Phase::Upload, Phase::Verify, Phase::Delete]);
+        self.state.replacing_docs = true;
+        self.upload_files(files_to_upload);
+        self.state.replacing_docs = false;
+        if self.state.is_uploading {
+            self.state.replaced_files = Some(replaced_files);
+        } else {
+            self.state.uploaded_files = replaced_files;
+            self.state.progress = RunProgress::default();
+        }
+    }
+
+    /// Replaces the project docs tagged with `section` by fresh uploads of its
+    /// local files, going through the same verify and roll back steps as a
+    /// full reupload. Docs of other sections are left alone.
+    pub fn resync_section(&mut self, section: &str) {
+        let Some(config) = &self.state.keep_config else {
+            return;
+        };
+        let case_insensitive = self.filter_options().case_insensitive;
+        let files: Vec<DiscoveredFile> = self
+            .state
+            .discovery
+            .iter()
+            .flat_map(|discovery| discovery.files.iter())
+            .filter(|file| {
+                config.should_include_file(
+                    Path::new(&file.relative_path),
+                    &[section.to_string()],
+                    case_insensitive,
+                )
+            })
+            .cloned()
+            .collect();
+        if files.is_empty() {
+            self.state.error_message =
+                Some(format!("No selected files belong to section '{}'", section));
+            return;
+        }
+        let replaced_files: Vec<UploadedFile> = self
+            .state
+            .remote_docs
+            .iter()
+            .flatten()
+            .filter(|doc| SectionTag::parse(&doc.file_name) == Some(section))
+            .map(|doc| UploadedFile {
+                name: doc.file_name.clone(),
+                uuid: doc.uuid.clone(),
+            })
+            .collect();
+        println!(
+            "Re-syncing section '{}': {} files replace {} docs",
+            section,
+            files.len(),
+            replaced_files.len()
+        );
+
+        self.state.clear_statuses();
+        self.state.uploaded_files.clear();
+        self.state.resync_section = Some(section.to_string());
+        self.state.replacing_docs = true;
+        if replaced_files.is_empty() {
+            self.upload_files(files);
+        } else {
+            self.state.progress = RunProgress::plan(&[Phase::Upload, Phase::Verify, Phase::Delete]);
+            self.upload_files(files);
+            if self.state.is_uploading {
+                self.state.replaced_files = Some(replaced_files);
+            } else {
+                self.state.progress = RunProgress::default();
+            }
+        }
+        self.state.resync_section = None;
+        self.state.replacing_docs = false;
+    }
+
+    pub fn pass_plan(&self) -> Option<&PassPlan> {
+        self.pass_plans.for_folder(self.folder_path.as_deref()?)
+    }
+
+    /// Splits the selected files into passes that each fit the project,
+    /// counting the docs the next pass replaces as free.
+    pub fn plan_passes(&mut self) {
+        let (Some(folder_path), Some(discovery)) = (&self.folder_path, &self.state.discovery)
+        else {
+            return;
+        };
+        let replaced = self
+            .pass_plan()
+            .map(|plan| plan.active_docs.len())
+            .unwrap_or(0);
+        let capacity = (self.remaining_capacity() + replaced).min(self.settings.project_doc_limit);
+        if capacity == 0 {
+            self.state.error_message =
+                Some("The project has no room left for another pass".to_string());
+            return;
+        }
+        let plan = PassPlan::split(folder_path, &discovery.files, capacity);
+        println!(
+            "Planned {} passes of up to {} files for {}",
+            plan.passes.len(),
+            capacity,
+            folder_path
+        );
+        self.pass_plans.replace(plan);
+    }
+
+    pub fn forget_pass_plan(&mut self) {
+        if let Some(folder_path) = &self.folder_path {
+            self.pass_plans.remove(folder_path);
+        }
+    }
+
+    /// Uploads the files of pass `index`, then verifies them and deletes the
+    /// docs of the pass run before, like a reupload.
+    pub fn run_pass(&mut self, index: usize) {
+        let Some(plan) = self.pass_plan() else {
+            return;
+        };
+        let files = self
+            .state
+            .discovery
+            .as_ref()
+            .map(|discovery| plan.files_of(index, &discovery.files))
+            .unwrap_or_default();
+        if files.is_empty() {
+            self.state.error_message =
+                Some("None of this pass's files are in the folder anymore".to_string());
+            return;
+        }
+        let replaced_files = plan.active_docs.clone();
+        println!(
+            "Running {}: {} files replace {} docs",
+            plan.passes[index].name,
+            files.len(),
+            replaced_files.len()
+        );
+
+        self.state.clear_statuses();
+        self.state.uploaded_files.clear();
+        self.state.running_pass = Some(index);
+        self.state.replacing_docs = true;
+        if replaced_files.is_empty() {
+            self.upload_files(files);
+        } else {
+            self.state.progress = RunProgress::plan(&[Phase::Upload, Phase::Verify, Phase::Delete]);
+            self.upload_files(files);
+            if self.state.is_uploading {
+                self.state.replaced_files = Some(replaced_files);
+            } else {
+                self.state.progress = RunProgress::default();
+            }
+        }
+        self.state.replacing_docs = false;
+        if !self.state.is_uploading {
+            self.state.running_pass = None;
+        }
+    }
+
+    /// Makes a finished pass the plan's active one. A rolled back pass never
+    /// gets here, its predecessor's docs are still the ones in the project.
+    fn finish_pass(&mut self) {
+        if self.state.is_uploading
+            || self.state.is_verifying
+            || self.state.is_deleting
+            || self.state.uploaded_files_receiver.is_some()
+            || self.state.replaced_files.is_some()
+        {
+            return;
+        }
+        let Some(index) = self.state.running_pass.take() else {
+            return;
+        };
+        let uploaded = self.state.uploaded_files.clone();
+        let Some(plan) = self
+            .folder_path
+            .as_deref()
+            .and_then(|folder_path| self.pass_plans.for_folder_mut(folder_path))
+        else {
+            return;
+        };
+        println!("Pass {} finished with {} docs", index + 1, uploaded.len());
+        plan.active = Some(index);
+        plan.active_docs = uploaded;
+    }
+
+    /// Moves a reupload on once its current phase has finished: verify the
+    /// new docs after uploading, then delete the replaced ones. Any failure
+    /// rolls the reupload back instead.
+    fn advance_reupload(&mut self) {
+        // Wait for the uploaded files as well, they arrive after the last status
+        if self.state.is_uploading
+            || self.state.is_verifying
+            || self.state.is_deleting
+            || self.state.uploaded_files_receiver.is_some()
+        {
+            return;
+        }
+        let Some(replaced_files) = self.state.replaced_files.take() else {
+            return;
+        };
+
+        let Some(phase) = self.state.progress.last_started().cloned() else {
+            return;
+        };
+        let clean = phase.failed == 0 && !self.state.last_run_stopped;
+
+        match phase.phase {
+            Phase::Upload if clean => {
+                self.state.replaced_files = Some(replaced_files);
+                self.verify_uploads();
+            }
+            Phase::Verify if clean => {
+                println!(
+                    "Reupload verified, deleting {} replaced docs",
+                    replaced_files.len()
+                );
+                self.delete_docs(replaced_files);
+            }
+            _ => self.roll_back_reupload(replaced_files),
+        }
+    }
+
+    /// Drops the partial set of new docs and keeps the old ones.
+    fn roll_back_reupload(&mut self, replaced_files: Vec<UploadedFile>) {
+        let new_files = std::mem::replace(&mut self.state.uploaded_files, replaced_files);
+        println!(
+            "Reupload incomplete, removing {} new docs and keeping the previous ones",
+            new_files.len()
+        );
+        self.state.error_message = Some(
+            "Reupload did not complete, the previous docs were kept and the new ones removed"
+                .to_string(),
+        );
+
+        self.state.running_pass = None;
+        self.state.progress.drop_pending();
+        if !new_files.is_empty() {
+            self.delete_docs(new_files);
+        }
+    }
+
+    /// Confirms that every new doc of a reupload is listed in the project
+    /// before anything is deleted.
+    fn verify_uploads(&mut self) {
+        self.state.is_verifying = true;
+
+        let (sender, receiver) = StatusBatch::channel();
+        self.state.status_receiver = Some(receiver);
+
+        let new_files = self.state.uploaded_files.clone();
+        self.state
+            .progress
+            .start_phase(Phase::Verify, new_files.len());
+
+        if new_files.is_empty() {
+            return;
+        }
+        let client = self.client();
+        println!("Verifying {} uploaded docs", new_files.len());
+
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            let listed = rt.block_on(client.list_docs());
+
+            for file in &new_files {
+                let status = match &listed {
+                    Ok(docs) if docs.iter().any(|doc| doc.uuid == file.uuid) => {
+                        UploadStatus::Success
+                    }
+                    Ok(_) => UploadStatus::Error(UploadError::new(
+                        ErrorKind::Other,
+                        "Doc is missing from the project after upload".to_string(),
+                    )),
+                    Err(error) => UploadStatus::Error(error.clone()),
+                };
+                let _ = sender.send(FileStatus::for_operation(file, Operation::Verify, status));
+            }
+        });
+    }
+
+    fn delete_docs(&mut self, files_to_delete: Vec<UploadedFile>) {
+        self.state.is_deleting = true;
+
+        let (sender, receiver) = StatusBatch::channel();
+        self.state.status_receiver = Some(receiver);
+
+        self.state
+            .progress
+            .start_phase(Phase::Delete, files_to_delete.len());
+        if files_to_delete.is_empty() {
+            return;
+        }
+
+        let pacing = self.settings.pacing_policy();
+        self.rate_limiter.configure(pacing);
+        let client = self.client();
+        println!(
+            "Starting deletion of {} files with {} parallel requests",
+            files_to_delete.len(),
+            pacing.delete_concurrency
+        );
+
+        let queue = Arc::new(Mutex::new(VecDeque::from(files_to_delete)));
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            rt.block_on(async {
+                let workers: Vec<_> = (0..pacing.delete_concurrency)
+                    .map(|_| {
+                        let client = client.clone();
+                        let queue = Arc::clone(&queue);
+                        let sender = sender.clone();
+                        tokio::spawn(async move {
+                            loop {
+                                let next = queue.lock().unwrap().pop_front();
+                                let Some(file) = next else {
+                                    break;
+                                };
+                                let _ = sender.send(FileStatus::for_operation(
+                                    &file,
+                                    Operation::Delete,
+                                    UploadStatus::Processing,
+                                ));
+                                let status = Self::delete_file(&client, &file).await;
+                                let _ = sender.send(status);
+                            }
+                        })
+                    })
+                    .collect();
+
+                for worker in workers {
+                    let _ = worker.await;
+                }
+            });
+        });
+    }
+
+    /// Headers parsed from a curl command with the active profile's
+    /// overrides applied.
+    fn request_headers(&self, parser: &CurlParser) -> HeaderMap {
+        let mut headers = parser.headers.clone().unwrap();
+        if let Some(profile) = self.profiles.active() {
+            CurlParser::override_headers(&mut headers, &profile.header_overrides);
+        }
+        headers
+    }
+
+    /// Client for the organization and project of the last parsed curl command.
+    fn client(&self) -> ClaudeClient {
+        let client = ClaudeClient::new(
+            self.curl_parser.organization_id.clone().unwrap(),
+            self.curl_parser.project_id.clone().unwrap(),
+            self.request_headers(&self.curl_parser),
+        )
+        .with_project_url(
+            self.profiles
+                .active()
+                .map(|profile| profile.project_url.as_str())
+                .unwrap_or_default(),
+        )
+        .with_permissions(self.permissions())
+        .with_expected_organization(self.profile_organization().map(str::to_string))
+        .with_rate_limiter(self.rate_limiter.clone());
+        #[cfg(test)]
+        let client = match &self.fixtures {
+            Some(fixtures) => client.with_fixtures(fixtures.clone()),
+            None => client,
+        };
+        client
+    }
+
+    /// Organization the active profile was created for.
+    fn profile_organization(&self) -> Option<&str> {
+        self.profiles
+            .active()
+            .and_then(|profile| profile.organization_id.as_deref())
+    }
+
+    /// Fetches the account's organizations for the organization picker.
+    pub fn list_organizations(&mut self) {
+        if let Err(e) = self.curl_parser.parse(&self.curl_text) {
+            self.state.error_message = Some(format!("Error parsing curl command: {}", e));
+            return;
+        }
+        self.state.error_message = None;
+
+        let client = self.client();
+        let (sender, receiver) = std_mpsc::channel();
+        self.state.organizations_receiver = Some(receiver);
+
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            let result = rt.block_on(client.list_organizations());
+            let _ = sender.send(result);
+        });
+    }
+
+    /// Sends requests to `organization_id` with the pasted session. Its
+    /// projects are listed right away, since the project of the curl
+    /// belongs to the previous organization.
+    pub fn select_organization(&mut self, organization_id: &str) {
+        if self.curl_parser.organization_id.as_deref() == Some(organization_id) {
+            return;
+        }
+        if let Some(profile_organization) = self.profile_organization() {
+            if profile_organization != organization_id {
+                self.state.error_message = Some(
+                    "This profile belongs to another organization, \
+                     save a new profile to work in this one"
+                        .to_string(),
+                );
+                return;
+            }
+        }
+        println!("Switching to organization {}", organization_id);
+        self.curl_parser.organization_override = Some(organization_id.to_string());
+        self.curl_parser.project_override = None;
+        if let Err(e) = self.curl_parser.parse(&self.curl_text) {
+            self.state.error_message = Some(format!("Error parsing curl command: {}", e));
+            return;
+        }
+        self.state.remote_docs = None;
+        self.state.selected_docs.clear();
+        self.state.opened_doc = None;
+        self.state.confirm_delete_docs = false;
+        self.state.projects = None;
+        self.list_projects();
+    }
+
+    /// Fetches the organization's projects for the project picker.
+    pub fn list_projects(&mut self) {
+        if let Err(e) = self.curl_parser.parse(&self.curl_text) {
+            self.state.error_message = Some(format!("Error parsing curl command: {}", e));
+            return;
+        }
+        self.state.error_message = None;
+
+        let client = self.client();
+        let (sender, receiver) = std_mpsc::channel();
+        self.state.projects_receiver = Some(receiver);
+
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            let result = rt.block_on(client.list_projects());
+            let _ = sender.send(result);
+        });
+    }
+
+    /// Sends requests to `project_id` instead of the project of the pasted
+    /// curl, which stays valid for every project of its organization.
+    pub fn select_project(&mut self, project_id: &str) {
+        if self.curl_parser.project_id.as_deref() == Some(project_id) {
+            return;
+        }
+        println!("Switching to project {}", project_id);
+        self.curl_parser.project_override = Some(project_id.to_string());
+        if let Err(e) = self.curl_parser.parse(&self.curl_text) {
+            self.state.error_message = Some(format!("Error parsing curl command: {}", e));
+            return;
+        }
+        self.state.remote_docs = None;
+        self.state.selected_docs.clear();
+        self.state.opened_doc = None;
+        self.state.confirm_delete_docs = false;
+    }
+
+    pub fn list_remote_docs(&mut self) {
+        if let Err(e) = self.curl_parser.parse(&self.curl_text) {
+            self.state.error_message = Some(format!("Error parsing curl command: {}", e));
+            return;
+        }
+        self.state.error_message = None;
+
+        let client = self.client();
+        let (sender, receiver) = std_mpsc::channel();
+        self.state.remote_docs_receiver = Some(receiver);
+
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            let result = rt.block_on(client.list_docs());
+            let _ = sender.send(result);
+        });
+    }
+
+    /// Shows a remote doc, fetching its content unless the listing had it.
+    pub fn open_remote_doc(&mut self, doc: &RemoteDoc) {
+        if doc.content.is_some() {
+            self.state.opened_doc = Some(doc.clone());
+            return;
+        }
+
+        let client = self.client();
+        let uuid = doc.uuid.clone();
+        let (sender, receiver) = std_mpsc::channel();
+        self.state.opened_doc = None;
+        self.state.opened_doc_receiver = Some(receiver);
+
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            let result = rt.block_on(client.get_doc(&uuid));
+            let _ = sender.send(result);
+        });
+    }
+
+    /// Deletes the docs selected in the listing.
+    pub fn delete_selected_docs(&mut self) {
+        let Some(docs) = &self.state.remote_docs else {
+            return;
+        };
+        let files: Vec<UploadedFile> = docs
+            .iter()
+            .filter(|doc| self.state.selected_docs.contains(&doc.uuid))
+            .map(|doc| UploadedFile {
+                name: doc.file_name.clone(),
+                uuid: doc.uuid.clone(),
+            })
+            .collect();
+        if files.is_empty() {
+            return;
+        }
+
+        self.state.progress = RunProgress::default();
+        self.state.clear_statuses();
+        self.state.selected_docs.clear();
+        self.delete_docs(files);
+    }
+
+    /// Writes the listed docs into `target_dir`, one file per doc. With
+    /// `selected_only` only the docs selected in the listing are written.
+    pub fn download_remote_docs(&mut self, target_dir: &Path, selected_only: bool) {
+        let Some(docs) = &self.state.remote_docs else {
+            return;
+        };
+
+        let mut written = 0;
+        let mut failures = Vec::new();
+        let docs = docs
+            .iter()
+            .filter(|doc| !selected_only || self.state.selected_docs.contains(&doc.uuid));
+        for doc in docs {
+            // Doc names come from the server, never let them escape the target folder
+            let Some(file_name) = Path::new(&doc.file_name).file_name() else {
+                failures.push(doc.file_name.clone());
+                continue;
+            };
+
+            let content = doc.content.as_deref().unwrap_or_default();
+            let target = target_dir.join(file_name);
+            let result = match &self.workspace {
+                Some(workspace) => workspace.write_then_move(&target, content),
+                None => std::fs::write(&target, content),
+            };
+            match result {
+                Ok(()) => written += 1,
+                Err(e) => {
+                    println!("Failed to write {}: {}", doc.file_name, e);
+                    failures.push(doc.file_name.clone());
+                }
+            }
+        }
+
+        println!("Downloaded {} docs to {}", written, target_dir.display());
+        if !failures.is_empty() {
+            self.state.error_message = Some(format!(
+                "Could not download {} docs: {}",
+                failures.len(),
+                failures.join(", ")
+            ));
+        }
+    }
+
+    async fn delete_file(client: &ClaudeClient, file: &UploadedFile) -> FileStatus {
+        println!(
+            "Attempting to delete file '{}' with ID: {}",
+            file.name, file.uuid
+        );
+
+        match client.delete_doc(&file.uuid).await {
+            Ok(()) => {
+                println!(
+                    "Successfully deleted file '{}' with ID: {}",
+                    file.name, file.uuid
+                );
+                FileStatus::for_operation(file, Operation::Delete, UploadStatus::Success)
+            }
+            Err(error) => {
+                println!(
+                    "Error deleting file '{}' with ID {}: {}",
+                    file.name, file.uuid, error
+                );
+                FileStatus::for_operation(file, Operation::Delete, UploadStatus::Error(error))
+            }
+        }
+    }
+
+    pub fn exceeds_doc_limit(&self) -> bool {
+        self.state
+            .discovery
+            .as_ref()
+            .map(|discovery| discovery.files.len() > self.settings.project_doc_limit)
+            .unwrap_or(false)
+    }
+
+    /// Replaces the discovered files with the paths in the file list text.
+    /// Filter rules and sections do not apply to an explicit list.
+    pub fn use_file_list(&mut self) {
+        let Some(folder_path) = self.folder_path.clone() else {
+            self.state.error_message =
+                Some("Select the folder the listed paths are relative to".to_string());
+            return;
+        };
+
+        let list = FileList::resolve(Path::new(&folder_path), &self.state.file_list_text);
+        let mut message = format!("Using {} listed files", list.files.len());
+        if !list.missing.is_empty() {
+            message.push_str(&format!(
+                ", {} paths are not files in the folder: {}",
+                list.missing.len(),
+                list.missing[..list.missing.len().min(5)].join(", ")
+            ));
+        }
+        println!("{}", message);
+        self.state.file_list_message = Some(message);
+        self.state.approved_plan = None;
+
+        self.select_listed_files(&folder_path, list.files);
+    }
+
+    /// Makes `files` the selection instead of discovering them. Sensitive
+    /// files are held back like in a discovery.
+    fn select_listed_files(&mut self, folder_path: &str, listed: Vec<DiscoveredFile>) {
+        let confirmed = &self.filter_options().confirmed_sensitive;
+        let mut files = Vec::new();
+        let mut sensitive = Vec::new();
+        for file in listed {
+            match SensitivePath::blocking_pattern(&file.relative_path, confirmed) {
+                Some(pattern) => sensitive.push(SensitiveFile {
+                    relative_path: file.relative_path,
+                    pattern,
+                }),
+                None => files.push(file),
+            }
+        }
+
+        self.state.use_file_list = true;
+        self.state.discovery_scan = None;
+        self.state.content_analysis =
+            Some(ContentAnalysis::start(files.clone(), self.profile_key()));
+        self.state.discovery = Some(DiscoveryResult {
+            files,
+            git: GitInfo::read(Path::new(folder_path)),
+            vendored: Vec::new(),
+            sensitive,
+        });
+        self.state.discovery_key = self.discovery_key();
+    }
+
+    /// Lets a file on the sensitive deny-list be uploaded after the user
+    /// confirmed it. The confirmation is logged and kept with the filter
+    /// rules, so it is never implied by any other setting.
+    pub fn confirm_sensitive(&mut self, relative_path: &str) {
+        self.state.confirm_sensitive = None;
+        let Some(pattern) = SensitivePath::pattern(relative_path) else {
+            return;
+        };
+        println!(
+            "Confirmed uploading sensitive file {} (matches {})",
+            relative_path, pattern
+        );
+        self.filter_options_mut()
+            .confirmed_sensitive
+            .push(relative_path.to_string());
+
+        // Discovery picks the change up by itself, a list has to be redone
+        if !self.state.use_file_list {
+            return;
+        }
+        let (Some(folder_path), Some(discovery)) =
+            (self.folder_path.clone(), self.state.discovery.as_ref())
+        else {
+            return;
+        };
+        let root = Path::new(&folder_path);
+        let mut files = discovery.files.clone();
+        let path = root.join(relative_path);
+        if let Ok(metadata) = std::fs::metadata(&path) {
+            files.push(DiscoveredFile::from_metadata(root, &path, &metadata));
+        }
+        for file in &discovery.sensitive {
+            if file.relative_path == relative_path {
+                continue;
+            }
+            let path = root.join(&file.relative_path);
+            if let Ok(metadata) = std::fs::metadata(&path) {
+                files.push(DiscoveredFile::from_metadata(root, &path, &metadata));
+            }
+        }
+        self.select_listed_files(&folder_path, files);
+    }
+
+    /// Goes back to discovering files with the filter rules and sections.
+    pub fn stop_using_file_list(&mut self) {
+        self.state.use_file_list = false;
+        self.state.file_list_message = None;
+        self.state.approved_plan = None;
+        self.restart_discovery();
+    }
+
+    /// Freezes the discovered files and their current content into a plan
+    /// for the pasted project, signed with the approval key.
+    pub fn freeze_plan(&mut self, path: &Path) {
+        let Some(discovery) = &self.state.discovery else {
+            return;
+        };
+        let mut parser = CurlParser::new();
+        if parser.parse(&self.curl_text).is_err() {
+            self.state.error_message =
+                Some("Paste the curl request of the target project first".to_string());
+            return;
+        }
+
+        let result = UploadPlan::freeze(
+            self.sync_info().folder_name,
+            self.git_info(),
+            parser.organization_id,
+            parser.project_id,
+            &discovery.files,
+        )
+        .and_then(|mut plan| plan.save(path, &self.state.plan_key).map(|_| plan));
+        match result {
+            Ok(plan) => println!("Froze {} files into {:?}", plan.files.len(), path),
+            Err(error) => self.state.error_message = Some(error),
+        }
+    }
+
+    /// Loads a signed plan and makes its files the selection, like an
+    /// explicit file list.
+    pub fn load_plan(&mut self, path: &Path) {
+        let Some(folder_path) = self.folder_path.clone() else {
+            self.state.error_message = Some("Select the folder the plan was made from".to_string());
+            return;
+        };
+        let plan = match UploadPlan::load(path, &self.state.plan_key) {
+            Ok(plan) => plan,
+            Err(error) => {
+                self.state.error_message = Some(error);
+                return;
+            }
+        };
+
+        let list = plan.resolve(Path::new(&folder_path));
+        let mut message = format!("Executing approved plan with {} files", plan.files.len());
+        if !list.missing.is_empty() {
+            message.push_str(&format!(", {} are missing", list.missing.len()));
+        }
+        println!("{}", message);
+        self.state.file_list_message = Some(message);
+        self.state.approved_plan = Some(plan);
+
+        self.select_listed_files(&folder_path, list.files);
+    }
+
+    /// Requires approved plans from now on, locked with the approval key.
+    pub fn require_approved_plans(&mut self) {
+        let mut policy = self.plan_policy.clone();
+        match policy
+            .require(&self.state.plan_key)
+            .and_then(|()| policy.save())
+        {
+            Ok(()) => {
+                println!("Uploads now require an approved plan");
+                self.plan_policy = policy;
+            }
+            Err(e) => self.state.error_message = Some(e),
+        }
+    }
+
+    pub fn lift_plan_requirement(&mut self) {
+        let mut policy = self.plan_policy.clone();
+        match policy
+            .lift(&self.state.plan_key)
+            .and_then(|()| policy.save())
+        {
+            Ok(()) => {
+                println!("Uploads no longer require an approved plan");
+                self.plan_policy = policy;
+            }
+            Err(e) => self.state.error_message = Some(e),
+        }
+    }
+
+    /// Uploads need an approved plan when required, and a loaded plan must
+    /// be for the pasted project.
+    fn check_plan(&self) -> Result<(), String> {
+        match &self.state.approved_plan {
+            None if self.plan_policy.is_required() => {
+                Err("Load an approved plan before uploading".to_string())
+            }
+            Some(plan)
+                if plan.organization_id != self.curl_parser.organization_id
+                    || plan.project_id != self.curl_parser.project_id =>
+            {
+                Err("The approved plan is for another project".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Writes the relative paths of the discovered files to `path`, one per
+    /// line in sorted order.
+    pub fn export_file_list(&mut self, path: &Path) {
+        let Some(discovery) = &self.state.discovery else {
+            return;
+        };
+        let mut paths: Vec<&str> = discovery
+            .files
+            .iter()
+            .map(|file| file.relative_path.as_str())
+            .collect();
+        paths.sort_unstable();
+
+        let mut list = paths.join("\n");
+        list.push('\n');
+        match std::fs::write(path, list) {
+            Ok(()) => println!("Exported {} paths to {:?}", paths.len(), path),
+            Err(e) => self.state.error_message = Some(format!("Could not write file list: {}", e)),
+        }
+    }
+
+    /// Writes an HTML summary of the discovered files for review, with the
+    /// outcome of each file if they were uploaded in this session.
+    pub fn export_report(&mut self, path: &Path) {
+        let Some(discovery) = &self.state.discovery else {
+            return;
+        };
+        let analysis = self.state.content_analysis.as_ref();
+        let evicted: HashMap<&str, FileOutcome> = self
+            .state
+            .evicted_statuses
+            .files
+            .iter()
+            .map(|file| (file.relative_path.as_str(), file.outcome))
+            .collect();
+        let mut outcomes: HashMap<&str, &FileStatus> = HashMap::new();
+        for status in &self.state.file_statuses {
+            if status.operation == Operation::Preview {
+                continue;
+            }
+            if let (Some(relative_path), true) = (&status.relative_path, status.status.is_final()) {
+                outcomes.insert(relative_path, status);
+            }
+        }
+
+        let files = discovery
+            .files
+            .iter()
+            .map(|file| {
+                let status = outcomes.get(file.relative_path.as_str());
+                let outcome = status.map(|status| match (&status.status, &status.warning) {
+                    (UploadStatus::Success, Some(warning)) => format!("Uploaded, {}", warning),
+                    (UploadStatus::Success, None) => "Uploaded".to_string(),
+                    (UploadStatus::Skipped(reason), _) => format!("Skipped: {}", reason),
+                    (UploadStatus::Error(error), _) => format!("Failed: {}", error),
+                    _ => String::new(),
+                });
+                // Uploads dropped from the details list only kept their outcome
+                let outcome = outcome.or_else(|| {
+                    evicted
+                        .get(file.relative_path.as_str())
+                        .map(|outcome| match outcome {
+                            FileOutcome::Uploaded => "Uploaded".to_string(),
+                            FileOutcome::Skipped => "Skipped".to_string(),
+                            FileOutcome::Failed => "Failed".to_string(),
+                        })
+                });
+                ReportFile {
+                    relative_path: file.relative_path.clone(),
+                    size: file.size,
+                    tokens: analysis
+                        .and_then(|analysis| analysis.summaries.get(&file.path))
+                        .map(|summary| summary.tokens),
+                    outcome,
+                    failed: status.is_some_and(|s| matches!(s.status, UploadStatus::Error(_))),
+                }
+            })
+            .collect();
+        let exclusions = discovery
+            .vendored
+            .iter()
+            .map(|file| ReportExclusion {
+                relative_path: file.relative_path.clone(),
+                reason: file.reason.to_string(),
+            })
+            .collect();
+
+        let report = SelectionReport {
+            info: self.sync_info(),
+            files,
+            exclusions,
+        };
+        match std::fs::write(path, report.to_html(SystemTime::now())) {
+            Ok(()) => println!("Exported selection report to {:?}", path),
+            Err(e) => self.state.error_message = Some(format!("Could not write report: {}", e)),
+        }
+    }
+
+    /// Whether the discovered files are more than a run may upload.
+    pub fn exceeds_run_cap(&self) -> bool {
+        let cap = self.settings.run_cap();
+        self.state
+            .discovery
+            .as_ref()
+            .map(|discovery| {
+                let bytes = discovery.files.iter().map(|file| file.size).sum();
+                !cap.allows(discovery.files.len(), bytes)
+            })
+            .unwrap_or(false)
+    }
+
+    /// Proposes which discovered files to leave out so the run fits the cap.
+    pub fn propose_trim(&mut self, strategy: TrimStrategy) {
+        let Some(discovery) = &self.state.discovery else {
+            return;
+        };
+        let priorities = self.section_priorities(&discovery.files);
+        let proposal = TrimProposal::propose(
+            &discovery.files,
+            self.settings.run_cap(),
+            strategy,
+            &priorities,
+        );
+        self.state.trim = Some(proposal);
+    }
+
+    /// Position of each file's first `.claudekeep` section in the file.
+    fn section_priorities(&self, files: &[DiscoveredFile]) -> HashMap<String, usize> {
+        let Some(config) = &self.state.keep_config else {
+            return HashMap::new();
+        };
+        let case_insensitive = self.filter_options().case_insensitive;
+        files
+            .iter()
+            .filter_map(|file| {
+                let position = config.sections.iter().position(|section| {
+                    config.should_include_file(
+                        Path::new(&file.relative_path),
+                        std::slice::from_ref(section),
+                        case_insensitive,
+                    )
+                })?;
+                Some((file.relative_path.clone(), position))
+            })
+            .collect()
+    }
+
+    /// Uploads the discovered files without the ones the trim leaves out.
+    pub fn start_trimmed_upload(&mut self) {
+        let Some(trim) = self.state.trim.take() else {
+            return;
+        };
+        let dropped: Vec<&String> = trim
+            .proposed
+            .iter()
+            .filter(|path| trim.drops(path))
+            .collect();
+        println!(
+            "Trimming {} files from the run ({})",
+            dropped.len(),
+            trim.strategy.label()
+        );
+        for path in &dropped {
+            println!("  left out: {}", path);
+        }
+        self.start_upload_with(None, Some(&trim));
+    }
+
+    /// Uploads the discovered files, optionally only the first `max_files`.
+    pub fn start_upload(&mut self, max_files: Option<usize>) {
+        self.start_upload_with(max_files, None);
+    }
+
+    /// Uploads only the files that are new or changed according to the
+    /// project manifest, and deletes the docs of files removed since the
+    /// last sync, whether or not manifest sync is enabled in the settings.
+    pub fn start_sync(&mut self) {
+        println!("Syncing changes...");
+        self.state.syncing_changes = true;
+        self.start_upload(None);
+        self.state.syncing_changes = false;
+    }
+
+    fn start_upload_with(&mut self, max_files: Option<usize>, trim: Option<&TrimProposal>) {
+        println!("Starting upload process...");
+        self.state.clear_statuses();
+        self.state.uploaded_files.clear();
+
+        let Some(folder_path) = &self.folder_path else {
+            println!("No folder selected for upload");
+            self.state.error_message = Some("No folder selected".to_string());
+            return;
+        };
+        println!("Processing folder: {}", folder_path);
+
+        let mut files = match &self.state.discovery {
+            Some(discovery) => discovery.files.clone(),
+            None => {
+                self.state.error_message =
+                    Some("Still scanning the selected folder, please wait".to_string());
+                return;
+            }
+        };
+
+        if let Some(trim) = trim {
+            files.retain(|file| !trim.drops(&file.relative_path));
+        }
+        if let Some(max_files) = max_files {
+            files.truncate(max_files);
+        }
+
+        self.upload_files(files);
+    }
+
+    /// Uploads an explicit list of files. Statuses and uploaded files from
+    /// earlier runs are kept so a retry only replaces its own entries.
+    pub fn upload_files(&mut self, files: Vec<DiscoveredFile>) {
+        self.upload_files_with(files, HashMap::new());
+    }
+
+    fn upload_files_with(
+        &mut self,
+        files: Vec<DiscoveredFile>,
+        conflict_resolutions: HashMap<String, ConflictResolution>,
+    ) {
+        self.state.is_uploading = true;
+        self.state.error_message = None;
+
+        if self.uses_api_key() {
+            if self.api_key.trim().is_empty() {
+                self.state.error_message = Some("Enter an API key first".to_string());
+                self.state.is_uploading = false;
+                return;
+            }
+        } else if let Err(e) = self.curl_parser.parse(&self.curl_text) {
+            let error_msg = format!("Error parsing curl command: {}", e);
+            println!("Error: {}", error_msg);
+            self.state.error_message = Some(error_msg);
+            self.state.is_uploading = false;
+            return;
+        }
+        if let Err(error) = self.check_plan() {
+            self.state.error_message = Some(error);
+            self.state.is_uploading = false;
+            return;
+        }
+
+        self.state.file_statuses.retain(|status| {
+            status.operation != Operation::Preview
+                && status
+                    .path
+                    .as_ref()
+                    .map(|path| !files.iter().any(|file| &file.path == path))
+                    .unwrap_or(true)
+        });
+
+        let control = RunControl::new();
+        self.state.run_control = Some(control.clone());
+        self.rate_limiter.configure(self.settings.pacing_policy());
+
+        if self.uses_api_key() {
+            let client = FilesApiClient::new(self.api_key.clone())
+                .with_permissions(self.permissions())
+                .with_rate_limiter(self.rate_limiter.clone());
+            let processor = self.configure_processor(
+                FileProcessor::new(client, control),
+                conflict_resolutions,
+                &files,
+            );
+            self.spawn_upload(processor, files);
+        } else {
+            let processor = self.configure_processor(
+                FileProcessor::new(self.client(), control),
+                conflict_resolutions,
+                &files,
+            );
+            self.spawn_upload(processor, files);
+        }
+    }
+
+    fn configure_processor<B: ProjectBackend>(
+        &self,
+        processor: FileProcessor<B>,
+        conflict_resolutions: HashMap<String, ConflictResolution>,
+        files: &[DiscoveredFile],
+    ) -> FileProcessor<B> {
+        processor
+            .with_manifest_sync(self.manifest_sync_enabled())
+            .with_prune_removed(
+                self.folder_path
+                    .as_ref()
+                    .filter(|_| self.state.syncing_changes && self.permissions().delete)
+                    .map(PathBuf::from),
+            )
+            .with_conflict_resolutions(conflict_resolutions)
+            .with_sync_info(self.settings.upload_sync_info.then(|| self.sync_info()))
+            .with_git(self.git_info())
+            .with_min_content(self.filter_options().min_content)
+            .with_reupload_modified(self.settings.reupload_modified_files)
+            .with_approved_plan(self.state.approved_plan.clone())
+            .with_section_tags(self.section_tags(files))
+            .with_retry_policy(self.settings.retry_policy())
+            .with_confirmed_sensitive(self.filter_options().confirmed_sensitive.clone())
+    }
+
+    /// Whether the run skips files the project manifest lists as unchanged.
+    /// Never for runs that replace docs: the replaced docs of skipped files
+    /// would be deleted without a new upload standing in for them.
+    fn manifest_sync_enabled(&self) -> bool {
+        (self.settings.sync_with_manifest || self.state.syncing_changes)
+            && !self.state.replacing_docs
+    }
+
+    /// The first selected `.claudekeep` section of each file, or of all
+    /// sections when none is selected, if docs are tagged with it.
+    fn section_tags(&self, files: &[DiscoveredFile]) -> HashMap<String, String> {
+        if let Some(section) = &self.state.resync_section {
+            return files
+                .iter()
+                .map(|file| (file.relative_path.clone(), section.clone()))
+                .collect();
+        }
+        let Some(config) = self
+            .state
+            .keep_config
+            .as_ref()
+            .filter(|_| self.settings.tag_docs_with_section)
+        else {
+            return HashMap::new();
+        };
+        let sections = if self.state.selected_sections.is_empty() {
+            &config.sections
+        } else {
+            &self.state.selected_sections
+        };
+        let case_insensitive = self.filter_options().case_insensitive;
+
+        files
+            .iter()
+            .filter_map(|file| {
+                let section = sections.iter().find(|section| {
+                    config.should_include_file(
+                        Path::new(&file.relative_path),
+                        std::slice::from_ref(*section),
+                        case_insensitive,
+                    )
+                })?;
+                Some((file.relative_path.clone(), section.clone()))
+            })
+            .collect()
+    }
+
+    fn spawn_upload<B: ProjectBackend + 'static>(
+        &mut self,
+        mut processor: FileProcessor<B>,
+        files: Vec<DiscoveredFile>,
+    ) {
+        let preflight = self.settings.preflight_policy();
+
+        let (status_sender, status_receiver) = StatusBatch::channel();
+        let (files_sender, files_receiver) = std_mpsc::channel();
+        self.state.status_receiver = Some(status_receiver);
+        self.state.uploaded_files_receiver = Some(files_receiver);
+
+        let total_files = files.len();
+        println!("Found {} supported files to upload", total_files);
+
+        if !self.state.progress.is_planned(Phase::Upload) {
+            self.state.progress = RunProgress::default();
+        }
+        self.state.progress.start_phase(Phase::Upload, total_files);
+        if files.is_empty() {
+            self.state.status_receiver = None;
+            self.state.uploaded_files_receiver = None;
+            return;
+        }
+
+        let status_sender = status_sender.clone();
+
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            rt.block_on(async {
+                if let Err(error) = processor.check_session(preflight, &status_sender).await {
+                    println!("Session check failed: {}", error);
+                    processor.fail_all(&files, &error, &status_sender);
+                    let _ = files_sender.send(Vec::new());
+                    return;
+                }
+
+                let uploaded_files = processor.process_files(&files, &status_sender).await;
+                println!(
+                    "Upload process completed. Uploaded files: {:?}",
+                    uploaded_files
+                );
+
+                let _ = files_sender.send(uploaded_files);
+                let _ =
+                    status_sender.send(FileStatus::new(String::from(""), UploadStatus::Success));
+            });
+        });
+    }
+
+    /// Adds the finished upload to the run history.
+    fn record_run(&mut self) {
+        let mut record = RunRecord::from_statuses(
+            &self.state.file_statuses,
+            self.state.progress.elapsed().unwrap_or_default(),
+            self.folder_path.clone(),
+            self.state.selected_sections.clone(),
+            self.filter_options().clone(),
+        );
+        // Uploads dropped from the details list still belong to the run
+        for file in &self.state.evicted_statuses.files {
+            if !record
+                .files
+                .iter()
+                .any(|existing| existing.relative_path == file.relative_path)
+            {
+                record.files.push(file.clone());
+            }
+        }
+        if record.files.is_empty() {
+            return;
+        }
+        record.git = self.git_info();
+        record.organization_id = self.curl_parser.organization_id.clone();
+        record.project_id = self.curl_parser.project_id.clone();
+        self.history.push(record);
+    }
+
+    /// Rediscovers the folder when its checked out branch no longer matches
+    /// the discovered one, so the file set and profile suggestion follow it.
+    fn check_branch(&mut self, ctx: &egui::Context) {
+        let Some(discovered) = self.git_info() else {
+            return;
+        };
+        let Some(folder_path) = self.folder_path.clone() else {
+            return;
+        };
+        ctx.request_repaint_after(BRANCH_CHECK_INTERVAL);
+        if self.is_busy()
+            || self
+                .state
+                .branch_checked_at
+                .is_some_and(|checked| checked.elapsed() < BRANCH_CHECK_INTERVAL)
+        {
+            return;
+        }
+        self.state.branch_checked_at = Some(Instant::now());
+
+        let branch = GitInfo::current_branch(Path::new(&folder_path));
+        if branch != discovered.branch {
+            println!(
+                "Branch changed from {:?} to {:?}, rescanning",
+                discovered.branch, branch
+            );
+            self.restart_discovery();
+        }
+    }
+
+    /// A profile bound to the discovered branch, other than the active one,
+    /// with the branch name.
+    pub fn branch_suggestion(&self) -> Option<(usize, String)> {
+        if self.is_profile_window() {
+            return None;
+        }
+        let branch = self.git_info()?.branch?;
+        if self.state.dismissed_branch.as_ref() == Some(&branch) {
+            return None;
+        }
+        let index = self.profiles.for_branch(&branch)?;
+        (self.profiles.active != Some(index)).then_some((index, branch))
+    }
+
+    /// Git HEAD of the folder as of the last discovery.
+    fn git_info(&self) -> Option<GitInfo> {
+        self.state
+            .discovery
+            .as_ref()
+            .and_then(|discovery| discovery.git.clone())
+    }
+
+    /// Chat page of the project the session points at, on claude.ai.
+    pub fn project_chat_url(&self) -> Option<String> {
+        if self.uses_api_key() {
+            return None;
+        }
+        let project_id = self.curl_parser.project_id.as_ref()?;
+        Some(format!("https://claude.ai/project/{}", project_id))
+    }
+
+    /// Suggested first message for a chat about the files the last run
+    /// uploaded.
+    pub fn chat_prompt(&self) -> String {
+        let folder_name = self
+            .folder_path
+            .as_ref()
+            .and_then(|path| Path::new(path).file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "my project".to_string());
+        let files = &self.state.uploaded_files;
+
+        let mut prompt = format!(
+            "I just synced {} files from {} to this project's knowledge:\n",
+            files.len(),
+            folder_name
+        );
+        for file in files.iter().take(CHAT_PROMPT_FILES) {
+            prompt.push_str(&format!("- {}\n", file.name));
+        }
+        if files.len() > CHAT_PROMPT_FILES {
+            prompt.push_str(&format!("- and {} more\n", files.len() - CHAT_PROMPT_FILES));
+        }
+        prompt.push_str(
+            "\nGive me a short overview of what these files cover and point out \
+             anything that looks inconsistent or out of date.",
+        );
+        prompt
+    }
+
+    /// What the SYNC_INFO.md doc says about the current selection; the file
+    /// counts are filled in once the run is done.
+    fn sync_info(&self) -> SyncInfo {
+        let folder_path = PathBuf::from(self.folder_path.clone().unwrap_or_default());
+        let sections = self
+            .state
+            .keep_config
+            .as_ref()
+            .map(|config| config.sections.clone())
+            .unwrap_or_default();
+        let selected = &self.state.selected_sections;
+
+        SyncInfo {
+            folder_name: folder_path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            git: self.git_info(),
+            selected_sections: selected.clone(),
+            // Nothing selected uploads every section
+            excluded_sections: sections
+                .into_iter()
+                .filter(|section| !selected.is_empty() && !selected.contains(section))
+                .collect(),
+            files: 0,
+            uploaded: 0,
+        }
+    }
+
+    /// Replaces the sync note in the project description after a run that
+    /// uploaded anything, when enabled in the settings.
+    fn note_sync_in_description(&mut self) {
+        let uploaded = self.state.file_statuses.iter().any(|status| {
+            status.operation == Operation::Upload && matches!(status.status, UploadStatus::Success)
+        });
+        if !self.settings.note_sync_in_description
+            || !uploaded
+            || !self.permissions().upload
+            || self.uses_api_key()
+        {
+            return;
+        }
+        let Some(folder_path) = self.folder_path.clone() else {
+            return;
+        };
+
+        let folder_path = PathBuf::from(folder_path);
+        let folder_name = folder_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let note = SyncNote::line(&folder_name, self.git_info().as_ref(), SystemTime::now());
+        let client = self.client();
+        let (sender, receiver) = std_mpsc::channel();
+        self.state.description_receiver = Some(receiver);
+
+        std::thread::spawn(move || {
+            println!("Updating project description: {}", note);
+
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            let result = rt.block_on(async {
+                let description = client.get_project_description().await?;
+                client
+                    .update_project_description(&SyncNote::apply(&description, &note))
+                    .await
+            });
+            let _ = sender.send(result);
+        });
+    }
+
+    /// Restores the folder, sections and filter rules of a recorded run and
+    /// uploads again once discovery has caught up. The session is the one
+    /// currently pasted, so it has to target the same project.
+    pub fn repeat_run(&mut self, index: usize) {
+        let Some(run) = self.history.runs.get(index).cloned() else {
+            return;
+        };
+        let Some(folder_path) = run.folder_path.filter(|path| Path::new(path).is_dir()) else {
+            self.state.error_message = Some("The folder of that run no longer exists".to_string());
+            return;
+        };
+
+        if let Err(e) = self.curl_parser.parse(&self.curl_text) {
+            self.state.error_message = Some(format!("Error parsing curl command: {}", e));
+            return;
+        }
+        if run.project_id.is_some()
+            && (run.organization_id != self.curl_parser.organization_id
+                || run.project_id != self.curl_parser.project_id)
+        {
+            self.state.error_message = Some(
+                "The run uploaded to another project, paste a curl request for that project first"
+                    .to_string(),
+            );
+            return;
+        }
+
+        println!("Repeating run from {}", folder_path);
+        self.open_folder(PathBuf::from(folder_path));
+        if let Some(config) = &self.state.keep_config {
+            self.state.selected_sections = run
+                .selected_sections
+                .into_iter()
+                .filter(|section| config.sections.contains(section))
+                .collect();
+        }
+        *self.filter_options_mut() = run.filter_options;
+        self.state.repeat_pending = true;
+    }
+
+    /// Starts a repeated run once the discovery for its configuration is done.
+    fn start_pending_repeat(&mut self) {
+        if !self.state.repeat_pending
+            || self.state.discovery.is_none()
+            || self.state.discovery_key != self.discovery_key()
+        {
+            return;
+        }
+        self.state.repeat_pending = false;
+
+        if !self.permissions().upload {
+            self.state.error_message = Some("Uploading is disabled for this profile".to_string());
+            return;
+        }
+        if self.exceeds_doc_limit() {
+            self.state.error_message = Some(
+                "The repeated run exceeds the project document limit, start it by hand".to_string(),
+            );
+            return;
+        }
+
+        self.state.progress = RunProgress::default();
+        self.start_upload(None);
+    }
+
+    /// Number of failed files per error kind, for the grouped failure summary.
+    /// Failed deletes and verifications are only listed in the details, they
+    /// cannot be retried.
+    pub fn failure_groups(&self) -> BTreeMap<ErrorKind, usize> {
+        let mut groups = BTreeMap::new();
+        for status in &self.state.file_statuses {
+            if status.operation != Operation::Upload {
+                continue;
+            }
+            if let UploadStatus::Error(error) = &status.status {
+                *groups.entry(error.kind).or_insert(0) += 1;
+            }
+        }
+        groups
+    }
+
+    fn failed_files(&self, kind: ErrorKind) -> Vec<DiscoveredFile> {
+        let root = self.folder_path.clone().unwrap_or_default();
+        self.state
+            .file_statuses
+            .iter()
+            .filter(
+                |status| matches!(&status.status, UploadStatus::Error(error) if error.kind == kind),
+            )
+            .filter_map(|status| status.path.as_ref())
+            .filter_map(|path| {
+                self.state
+                    .discovery
+                    .as_ref()
+                    .and_then(|discovery| discovery.files.iter().find(|file| &file.path == path))
+                    .cloned()
+                    .or_else(|| DiscoveredFile::from_path(Path::new(&root), path))
+            })
+            .collect()
+    }
+
+    pub fn retry_failed(&mut self, kind: ErrorKind) {
+        let files = self.failed_files(kind);
+        if files.is_empty() {
+            return;
+        }
+
+        println!(
+            "Retrying {} files that failed with: {}",
+            files.len(),
+            kind.label()
+        );
+        self.upload_files(files);
+    }
+
+    /// Re-runs the conflicting files with the chosen resolution applied to each.
+    pub fn resolve_conflicts(&mut self, resolution: ConflictResolution) {
+        let files = self.failed_files(ErrorKind::Conflict);
+        if files.is_empty() {
+            return;
         }
+
+        println!("Resolving {} conflicts: {:?}", files.len(), resolution);
+        let resolutions = files
+            .iter()
+            .map(|file| (file.relative_path.clone(), resolution))
+            .collect();
+        self.upload_files_with(files, resolutions);
     }
 
-    pub fn reset_upload_state(&mut self) {
-        println!("Resetting application state");
-        self.curl_text.clear();
-        self.folder_path = None;
-        self.state.clear();
-        self.curl_parser = CurlParser::new();
+    pub fn skip_failed(&mut self, kind: ErrorKind) {
+        for status in &mut self.state.file_statuses {
+            let message = match &status.status {
+                UploadStatus::Error(error) if error.kind == kind => error.message.clone(),
+                _ => continue,
+            };
+            status.status = UploadStatus::Skipped(format!("Skipped after failure: {}", message));
+        }
     }
 
-    pub fn delete_and_reupload(&mut self) {
-        if self.state.uploaded_files.is_empty() {
-            println!("No files to delete. Uploaded files list is empty.");
-            self.state.error_message = Some("No files to delete".to_string());
+    /// Validates the freshly pasted curl and hands its headers to the paused run.
+    pub fn resume_with_new_session(&mut self) {
+        // The session serves a picked organization and project as well
+        let mut parser = CurlParser {
+            project_override: self.curl_parser.project_override.clone(),
+            organization_override: self.curl_parser.organization_override.clone(),
+            ..CurlParser::new()
+        };
+        if let Err(e) = parser.parse(&self.state.reauth_curl_text) {
+            self.state.error_message = Some(format!("Error parsing curl command: {}", e));
             return;
         }
 
-        println!("Starting delete and reupload process...");
+        if parser.organization_id != self.curl_parser.organization_id
+            || parser.project_id != self.curl_parser.project_id
+        {
+            self.state.error_message = Some(
+                "The new curl command targets a different organization or project".to_string(),
+            );
+            return;
+        }
 
-        self.state.is_deleting = true;
+        let Some(control) = &self.state.run_control else {
+            return;
+        };
+        control.provide_headers(self.request_headers(&parser));
+
+        println!("Session refreshed, resuming run");
+        self.curl_text = std::mem::take(&mut self.state.reauth_curl_text);
+        self.curl_changed();
+        self.session_health.mark(true);
+        self.curl_parser = parser;
+        self.state.awaiting_reauth = false;
         self.state.error_message = None;
-        self.state.file_statuses.clear();
+    }
+
+    /// Re-evaluates what can be told about the pasted curl before it is used.
+    pub fn curl_changed(&mut self) {
+        self.curl_warning = CurlParser::endpoint_warning(&self.curl_text);
+        // A new curl names its own organization and project
+        self.curl_parser.organization_override = None;
+        self.curl_parser.project_override = None;
+        self.state.organizations = None;
+        self.state.projects = None;
 
-        let files_to_delete = self.state.uploaded_files.clone();
-        let folder_path = self.folder_path.clone();
-        let keep_config = self.state.keep_config.clone();
-        let selected_sections = self.state.selected_sections.clone();
+        let mut parser = CurlParser::new();
+        self.session_health = match parser.parse(&self.curl_text) {
+            Ok(()) => parser
+                .headers
+                .as_ref()
+                .map(SessionHealth::from_headers)
+                .unwrap_or_default(),
+            Err(_) => SessionHealth::default(),
+        };
+    }
 
+    /// Probes the pasted session with a read-only request.
+    pub fn check_session_health(&mut self) {
         if let Err(e) = self.curl_parser.parse(&self.curl_text) {
-            let error_msg = format!("Error parsing curl command: {}", e);
-            println!("Error: {}", error_msg);
-            self.state.error_message = Some(error_msg);
-            self.state.is_deleting = false;
+            self.state.error_message = Some(format!("Error parsing curl command: {}", e));
             return;
         }
 
+        let client = self.client();
         let (sender, receiver) = std_mpsc::channel();
-        self.state.status_receiver = Some(receiver);
-        let sender = sender.clone();
-
-        self.state.progress = ActionProgress::Deleting {
-            total: files_to_delete.len(),
-            current: 0,
-            successful: 0,
-            failed: 0,
-        };
-
-        let org_id = self.curl_parser.organization_id.clone().unwrap();
-        let proj_id = self.curl_parser.project_id.clone().unwrap();
-        let headers = self.curl_parser.headers.clone().unwrap();
-
-        println!("Starting deletion of {} files", files_to_delete.len());
+        self.session_health.check_receiver = Some(receiver);
 
         std::thread::spawn(move || {
             let rt = tokio::runtime::Runtime::new().unwrap();
-            rt.block_on(async {
-                for file in files_to_delete {
-                    let status = Self::delete_file(&org_id, &proj_id, &file, &headers).await;
-                    let _ = sender.send(status);
-                }
-
-                if let Some(folder_path) = folder_path {
-                    let processor = FileProcessor::new(
-                        folder_path.clone(),
-                        org_id.clone(),
-                        proj_id.clone(),
-                        headers.clone(),
-                        keep_config,
-                        selected_sections,
-                    );
-
-                    let uploaded_files = processor.process_files(&sender).await;
-                    println!("Reupload completed. Uploaded files: {:?}", uploaded_files);
-                }
-            });
+            let result = rt.block_on(client.check_access());
+            let _ = sender.send(result);
         });
     }
 
-    async fn delete_file(
-        org_id: &str,
-        project_id: &str,
-        file: &UploadedFile,
-        headers: &HeaderMap,
-    ) -> FileStatus {
-        println!(
-            "Attempting to delete file '{}' with ID: {}",
-            file.name, file.uuid
-        );
+    pub fn stop_run(&mut self) {
+        if let Some(control) = &self.state.run_control {
+            println!("Stopping run on user request");
+            control.stop();
+        }
+    }
 
-        let client = reqwest::Client::new();
-        let url = format!(
-            "https://claude.ai/api/organizations/{}/projects/{}/docs/{}",
-            org_id, project_id, file.uuid
-        );
+    /// Stops the run right away, abandoning the file being uploaded. The
+    /// files not uploaded yet are reported as cancelled.
+    pub fn cancel_run(&mut self) {
+        if let Some(control) = &self.state.run_control {
+            println!("Cancelling run on user request");
+            control.cancel();
+            self.state.progress.cancel();
+        }
+    }
 
-        let response = client.delete(&url).headers(headers.clone()).send().await;
-
-        match response {
-            Ok(res) => {
-                let status = res.status();
-                if status.is_success() {
-                    println!(
-                        "Successfully deleted file '{}' with ID: {}",
-                        file.name, file.uuid
-                    );
-                    FileStatus {
-                        name: file.name.clone(),
-                        status: UploadStatus::Success,
-                    }
-                } else {
-                    let error_msg = format!("Failed to delete with status: {}", status);
-                    println!(
-                        "Error deleting file '{}' with ID {}: {}",
-                        file.name, file.uuid, error_msg
-                    );
-                    FileStatus {
-                        name: file.name.clone(),
-                        status: UploadStatus::Error(error_msg),
-                    }
+    /// Whether closing now would abandon requests in flight, here or in a
+    /// profile window.
+    fn has_running_work(&self) -> bool {
+        self.state.is_uploading
+            || self.state.is_verifying
+            || self.state.is_deleting
+            || self.state.uploaded_files_receiver.is_some()
+            || self.windows.iter().any(ProfileWindow::is_running)
+    }
+
+    /// Keeps the window open while a run winds down: the run is cancelled,
+    /// the project manifest of what was uploaded still gets saved, and the
+    /// window closes once the worker is done or the grace period is over.
+    /// Closing a second time doesn't wait any longer.
+    fn handle_close_request(&mut self, ctx: &egui::Context) {
+        let close_requested = ctx.input(|input| input.viewport().close_requested());
+        match self.shutdown_started {
+            None if close_requested && self.has_running_work() => {
+                println!("Close requested during a run, cancelling it first");
+                ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+                self.cancel_run();
+                self.cancel_discovery();
+                for window in &mut self.windows {
+                    window.cancel_run();
                 }
+                self.shutdown_started = Some(Instant::now());
             }
-            Err(e) => {
-                let error_msg = format!("Failed to send delete request: {}", e);
-                println!(
-                    "Error deleting file '{}' with ID {}: {}",
-                    file.name, file.uuid, error_msg
-                );
-                FileStatus {
-                    name: file.name.clone(),
-                    status: UploadStatus::Error(error_msg),
+            None => {}
+            Some(_) if close_requested => self.abandon_run(),
+            Some(started) => {
+                if self.has_running_work() && started.elapsed() < SHUTDOWN_GRACE {
+                    ctx.request_repaint_after(BUSY_REPAINT_INTERVAL);
+                    return;
                 }
+                self.abandon_run();
+                println!("Closing");
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
             }
         }
     }
 
-    pub fn start_upload(&mut self) {
-        println!("Starting upload process...");
-        self.state.is_uploading = true;
-        self.state.error_message = None;
-        self.state.file_statuses.clear();
-        self.state.uploaded_files.clear();
+    /// Records what a run still going at exit got done, so the history saved
+    /// on close has it, and stops listening to it.
+    fn abandon_run(&mut self) {
+        if !self.has_running_work() {
+            return;
+        }
+        println!("Run did not wind down in time, closing anyway");
+        if self.state.is_uploading {
+            self.record_run();
+        }
+        self.state.is_uploading = false;
+        self.state.is_verifying = false;
+        self.state.is_deleting = false;
+        self.state.status_receiver = None;
+        self.state.uploaded_files_receiver = None;
+        self.state.run_control = None;
+        let mut windows = std::mem::take(&mut self.windows);
+        for window in &mut windows {
+            window.abandon_run(self);
+        }
+    }
 
-        if let Err(e) = self.curl_parser.parse(&self.curl_text) {
-            let error_msg = format!("Error parsing curl command: {}", e);
-            println!("Error: {}", error_msg);
-            self.state.error_message = Some(error_msg);
-            self.state.is_uploading = false;
+    fn discovery_key(&self) -> Option<DiscoveryKey> {
+        self.folder_path.as_ref().map(|folder_path| DiscoveryKey {
+            folder_path: folder_path.clone(),
+            selected_sections: self.state.selected_sections.clone(),
+            filter_options: self.filter_options().clone(),
+        })
+    }
+
+    /// Starts a background scan whenever the folder, section selection or
+    /// filter options no longer match the cached discovery result.
+    fn refresh_discovery(&mut self) {
+        if self.state.use_file_list {
+            return;
+        }
+        let key = self.discovery_key();
+        if key == self.state.discovery_key {
             return;
         }
 
-        if let Some(folder_path) = &self.folder_path {
-            println!("Processing folder: {}", folder_path);
-            let keep_config = self.state.keep_config.clone();
-            let selected_sections = self.state.selected_sections.clone();
-
-            let processor = FileProcessor::new(
-                folder_path.clone(),
-                self.curl_parser.organization_id.clone().unwrap(),
-                self.curl_parser.project_id.clone().unwrap(),
-                self.curl_parser.headers.clone().unwrap(),
-                keep_config,
-                selected_sections,
-            );
+        self.state.discovery = None;
+        self.state.discovery_scan = None;
+        self.state.content_analysis = None;
+        self.state.trim = None;
+        self.state.discovery_key = key.clone();
 
-            let (status_sender, status_receiver) = std_mpsc::channel();
-            let (files_sender, files_receiver) = std_mpsc::channel();
-            self.state.status_receiver = Some(status_receiver);
-            self.state.uploaded_files_receiver = Some(files_receiver);
+        let Some(key) = key else {
+            return;
+        };
 
-            let total_files = processor.count_supported_files();
-            println!("Found {} supported files to upload", total_files);
+        let path_filter = PathFilter::new(
+            PathBuf::from(&key.folder_path),
+            self.state.keep_config.clone(),
+            key.selected_sections,
+            key.filter_options,
+        )
+        .with_content_cache(self.content_matches.clone());
 
-            self.state.progress = ActionProgress::Uploading {
-                total: total_files,
-                current: 0,
-                successful: 0,
-                failed: 0,
-                skipped: 0,
-            };
+        self.state.discovery_scan = Some(DiscoveryScan::start(path_filter));
+    }
 
-            let status_sender = status_sender.clone();
-
-            std::thread::spawn(move || {
-                let rt = tokio::runtime::Runtime::new().unwrap();
-                rt.block_on(async {
-                    let uploaded_files = processor.process_files(&status_sender).await;
-                    println!(
-                        "Upload process completed. Uploaded files: {:?}",
-                        uploaded_files
-                    );
-
-                    let _ = files_sender.send(uploaded_files);
-                    let _ = status_sender.send(FileStatus {
-                        name: String::from(""),
-                        status: UploadStatus::Success,
-                    });
-                });
-            });
-        } else {
-            println!("No folder selected for upload");
-            self.state.error_message = Some("No folder selected".to_string());
-            self.state.is_uploading = false;
+    /// Scans the files of all `.claudekeep` sections whenever the folder or
+    /// filter options change, so each section's impact can be shown.
+    fn refresh_section_index(&mut self) {
+        let key = self.state.keep_config.as_ref().and_then(|config| {
+            self.discovery_key().map(|key| DiscoveryKey {
+                selected_sections: config.sections.clone(),
+                ..key
+            })
+        });
+        if key == self.state.section_key {
+            return;
+        }
+
+        self.state.section_index = None;
+        self.state.section_scan = None;
+        self.state.section_key = key.clone();
+
+        let Some(key) = key else {
+            return;
+        };
+
+        let path_filter = PathFilter::new(
+            PathBuf::from(&key.folder_path),
+            self.state.keep_config.clone(),
+            key.selected_sections,
+            key.filter_options,
+        )
+        .with_content_cache(self.content_matches.clone());
+        self.state.section_scan = Some(DiscoveryScan::start(path_filter));
+    }
+
+    /// Documents that still fit into the project: the configured limit minus
+    /// the docs already in it, when a listing is known.
+    pub fn remaining_capacity(&self) -> usize {
+        let existing = self.state.remote_docs.as_ref().map(Vec::len).unwrap_or(0);
+        self.settings.project_doc_limit.saturating_sub(existing)
+    }
+
+    pub fn cancel_discovery(&mut self) {
+        if let Some(scan) = self.state.discovery_scan.take() {
+            println!("Cancelling folder scan after {} entries", scan.examined);
+            scan.cancel();
+        }
+    }
+
+    /// Lists every file in the folder in the details with what an upload
+    /// would do with it, without sending anything.
+    pub fn start_dry_run(&mut self) {
+        let Some(key) = self.discovery_key() else {
+            return;
+        };
+        println!("Starting dry run of {}", key.folder_path);
+        let path_filter = PathFilter::new(
+            PathBuf::from(&key.folder_path),
+            self.state.keep_config.clone(),
+            key.selected_sections,
+            key.filter_options,
+        )
+        .with_content_cache(self.content_matches.clone());
+
+        self.state.clear_statuses();
+        self.state.status_pages = 0;
+        self.state.dry_run_receiver = Some(DryRun::start(path_filter));
+    }
+
+    pub fn restart_discovery(&mut self) {
+        self.state.discovery_key = None;
+        self.state.section_key = None;
+    }
+
+    /// Writes a starter .claudekeep into the selected folder and loads it.
+    pub fn generate_claude_keep(&mut self) {
+        let Some(folder_path) = self.folder_path.clone() else {
+            return;
+        };
+        if let Err(e) = ClaudeKeepTemplate::write(Path::new(&folder_path)) {
+            self.state.error_message = Some(format!("Could not write .claudekeep: {}", e));
+            return;
+        }
+        self.reload_claude_keep();
+    }
+
+    /// Re-reads .claudekeep, e.g. after it was edited, keeping the selected
+    /// sections that still exist.
+    pub fn reload_claude_keep(&mut self) {
+        let Some(folder_path) = self.folder_path.clone() else {
+            return;
+        };
+        self.state.keep_config = ClaudeKeepConfig::from_file(Path::new(&folder_path));
+        let sections = self
+            .state
+            .keep_config
+            .as_ref()
+            .map(|config| config.sections.clone())
+            .unwrap_or_default();
+        self.state
+            .selected_sections
+            .retain(|section| sections.contains(section));
+        self.restart_discovery();
+    }
+
+    /// True while any background work may still send updates.
+    fn is_busy(&self) -> bool {
+        self.state.is_uploading
+            || self.state.is_verifying
+            || self.state.is_deleting
+            || self.state.discovery_scan.is_some()
+            || self.state.section_scan.is_some()
+            || self.state.remote_docs_receiver.is_some()
+            || self.state.organizations_receiver.is_some()
+            || self.state.projects_receiver.is_some()
+            || self.state.opened_doc_receiver.is_some()
+            || self.state.description_receiver.is_some()
+            || self.state.dry_run_receiver.is_some()
+            || self.session_health.check_receiver.is_some()
+            || self.state.uploaded_files_receiver.is_some()
+            || self
+                .state
+                .content_analysis
+                .as_ref()
+                .map(|analysis| !analysis.finished)
+                .unwrap_or(false)
+    }
+
+    /// Shows the run progress in the window title, so it can be followed
+    /// while the window is minimized or covered.
+    fn update_window_title(&mut self, ctx: &egui::Context) {
+        let app_title = match &self.window_profile {
+            Some(profile) => format!("{} - {}", profile, APP_TITLE),
+            None => APP_TITLE.to_string(),
+        };
+        let running = self.state.is_uploading || self.state.is_verifying || self.state.is_deleting;
+        let title = match self.state.progress.last_started() {
+            _ if self.shutdown_started.is_some() => {
+                format!("Finishing the run before closing… - {}", app_title)
+            }
+            Some(phase) if running => format!(
+                "{} {:.0}% ({}/{}) - {}",
+                phase.phase.verb(),
+                self.state.progress.overall_fraction() * 100.0,
+                phase.reached(),
+                phase.total,
+                app_title
+            ),
+            _ => app_title,
+        };
+
+        if title != self.window_title {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+            self.window_title = title;
         }
     }
 
+    /// Clears the flags of the phase that just finished. Returns whether it
+    /// was a delete and whether it was an upload.
+    fn finish_phase(&mut self) -> (bool, bool) {
+        let finished = (self.state.is_deleting, self.state.is_uploading);
+        self.state.is_uploading = false;
+        self.state.is_verifying = false;
+        self.state.is_deleting = false;
+        self.state.awaiting_reauth = false;
+        self.state.offline = false;
+        self.state.last_run_stopped = self
+            .state
+            .run_control
+            .take()
+            .map(|control| control.is_stopped())
+            .unwrap_or(false);
+        finished
+    }
+
     pub fn update_state(&mut self, ctx: &egui::Context) {
-        ctx.request_repaint();
+        // Background threads report through channels, so poll them while
+        // something is running and let the app idle otherwise
+        if self.is_busy() {
+            ctx.request_repaint_after(BUSY_REPAINT_INTERVAL);
+        }
+
+        self.refresh_discovery();
+        self.refresh_section_index();
+        if let Some(scan) = &mut self.state.section_scan {
+            if let Some(result) = scan.poll() {
+                if let Some(config) = &self.state.keep_config {
+                    let case_insensitive = self.filter_options().case_insensitive;
+                    self.state.section_index =
+                        Some(SectionIndex::build(config, &result.files, case_insensitive));
+                }
+                self.state.section_scan = None;
+            }
+        }
+        if let Some(scan) = &mut self.state.discovery_scan {
+            if let Some(discovery) = scan.poll() {
+                self.state.content_analysis = Some(ContentAnalysis::start(
+                    discovery.files.clone(),
+                    self.profile_key(),
+                ));
+                self.state.discovery = Some(discovery);
+                self.state.discovery_scan = None;
+            }
+        }
+        if let Some(analysis) = &mut self.state.content_analysis {
+            analysis.poll();
+        }
+        self.start_pending_repeat();
+        self.check_branch(ctx);
+
+        if let Some(receiver) = &self.state.remote_docs_receiver {
+            if let Ok(result) = receiver.try_recv() {
+                match result {
+                    Ok(docs) => self.state.remote_docs = Some(docs),
+                    Err(error) => {
+                        self.state.error_message = Some(format!("Failed to list docs: {}", error))
+                    }
+                }
+                self.state.remote_docs_receiver = None;
+            }
+        }
+
+        if let Some(receiver) = &self.state.organizations_receiver {
+            if let Ok(result) = receiver.try_recv() {
+                match result {
+                    Ok(organizations) => self.state.organizations = Some(organizations),
+                    Err(error) => {
+                        self.state.error_message =
+                            Some(format!("Failed to list organizations: {}", error))
+                    }
+                }
+                self.state.organizations_receiver = None;
+            }
+        }
+
+        if let Some(receiver) = &self.state.projects_receiver {
+            if let Ok(result) = receiver.try_recv() {
+                match result {
+                    Ok(projects) => self.state.projects = Some(projects),
+                    Err(error) => {
+                        self.state.error_message =
+                            Some(format!("Failed to list projects: {}", error))
+                    }
+                }
+                self.state.projects_receiver = None;
+            }
+        }
+
+        if let Some(receiver) = &self.state.opened_doc_receiver {
+            if let Ok(result) = receiver.try_recv() {
+                match result {
+                    Ok(doc) => {
+                        // Keep the fetched content, so it is searched and downloaded too
+                        if let Some(listed) = self
+                            .state
+                            .remote_docs
+                            .iter_mut()
+                            .flatten()
+                            .find(|listed| listed.uuid == doc.uuid)
+                        {
+                            listed.content = doc.content.clone();
+                        }
+                        self.state.opened_doc = Some(doc);
+                    }
+                    Err(error) => {
+                        self.state.error_message = Some(format!("Failed to fetch doc: {}", error))
+                    }
+                }
+                self.state.opened_doc_receiver = None;
+            }
+        }
+
+        if let Some(receiver) = &self.state.description_receiver {
+            if let Ok(result) = receiver.try_recv() {
+                if let Err(error) = result {
+                    self.state.error_message = Some(format!(
+                        "Failed to update the project description: {}",
+                        error
+                    ));
+                }
+                self.state.description_receiver = None;
+            }
+        }
+
+        if let Some(receiver) = &self.state.dry_run_receiver {
+            if let Ok(statuses) = receiver.try_recv() {
+                self.state.clear_statuses();
+                self.state.file_statuses = statuses;
+                self.state.show_details = true;
+                self.state.dry_run_receiver = None;
+            }
+        }
 
         if let Some(receiver) = &self.state.uploaded_files_receiver {
             if let Ok(files) = receiver.try_recv() {
-                self.state.uploaded_files = files;
+                self.state.uploaded_files.extend(files);
                 self.state.uploaded_files_receiver = None;
                 ctx.request_repaint();
             }
         }
 
+        if let Some(receiver) = &self.session_health.check_receiver {
+            if let Ok(result) = receiver.try_recv() {
+                self.session_health.check_receiver = None;
+                match result {
+                    Ok(()) => self.session_health.mark(true),
+                    Err(error) if error.kind == ErrorKind::SessionExpired => {
+                        self.session_health.mark(false)
+                    }
+                    Err(error) => {
+                        self.state.error_message =
+                            Some(format!("Could not check the session: {}", error))
+                    }
+                }
+            }
+        }
+        // Keeps the expiry countdown current while idle
+        if self.session_health.expires_at.is_some() {
+            ctx.request_repaint_after(SESSION_REPAINT_INTERVAL);
+        }
+
+        let mut deletion_finished = false;
+        let mut upload_finished = false;
         if let Some(receiver) = &self.state.status_receiver {
-            let mut had_updates = false;
-
-            while let Ok(status) = receiver.try_recv() {
-                had_updates = true;
-                let mut should_complete = false;
-                let mut completion_state = None;
-
-                match &mut self.state.progress {
-                    ActionProgress::Uploading {
-                        current,
-                        successful,
-                        failed,
-                        skipped,
-                        total,
-                    } => {
-                        match &status.status {
-                            UploadStatus::Processing => {
-                                *current += 1;
-                            }
-                            UploadStatus::Success => *successful += 1,
-                            UploadStatus::Error(_) => *failed += 1,
-                            UploadStatus::Skipped(_) => *skipped += 1,
-                        }
+            let batches: Vec<Vec<FileStatus>> = receiver.try_iter().collect();
+            let had_updates = !batches.is_empty();
+            let mut current_file = None;
 
-                        if (*successful + *failed + *skipped) >= *total {
-                            should_complete = true;
-                            completion_state = Some(ActionProgress::Completed {
-                                total: *total,
-                                successful: *successful,
-                                failed: *failed,
-                                skipped: *skipped,
-                            });
-                        }
-                    }
-                    ActionProgress::Deleting {
-                        current,
-                        successful,
-                        failed,
-                        total,
-                    } => {
-                        match &status.status {
-                            UploadStatus::Processing => {
-                                *current += 1;
-                            }
-                            UploadStatus::Success => *successful += 1,
-                            UploadStatus::Error(_) => *failed += 1,
-                            _ => {}
-                        }
+            self.state
+                .file_statuses
+                .reserve(batches.iter().map(Vec::len).sum());
+            for status in batches.into_iter().flatten() {
+                if let UploadStatus::CheckingSession {
+                    attempt,
+                    max_attempts,
+                } = status.status
+                {
+                    self.state.session_check = Some((attempt, max_attempts));
+                    continue;
+                }
+                self.state.session_check = None;
+                let phase_finished = self.state.progress.record(&status.status);
 
-                        if (*successful + *failed) >= *total {
-                            should_complete = true;
-                            completion_state = Some(ActionProgress::Completed {
-                                total: *total,
-                                successful: *successful,
-                                failed: *failed,
-                                skipped: 0,
-                            });
-                        }
+                // Every answered request tells whether the session still works
+                match &status.status {
+                    UploadStatus::Success => self.session_health.mark(true),
+                    UploadStatus::WaitingForAuth => self.session_health.mark(false),
+                    UploadStatus::Error(error) if error.kind == ErrorKind::SessionExpired => {
+                        self.session_health.mark(false)
                     }
                     _ => {}
                 }
 
-                self.state.current_file = Some(status.name.clone());
+                self.state.awaiting_reauth = matches!(status.status, UploadStatus::WaitingForAuth);
+                self.state.offline = matches!(status.status, UploadStatus::WaitingForNetwork);
+                current_file = Some(self.state.file_statuses.len());
                 self.state.file_statuses.push(status);
 
-                if should_complete {
-                    if let Some(completion_state) = completion_state {
-                        let has_failures = matches!(&completion_state, ActionProgress::Completed { failed, .. } if *failed > 0);
-                        self.state.progress = completion_state;
-
-                        if has_failures {
-                            self.state.error_message = Some(
-                                                        "Operation completed with failures. Check details for more information."
-                                                            .to_string(),
-                                                    );
-                        }
-                        self.state.is_uploading = false;
-                        self.state.is_deleting = false;
-                    }
+                if phase_finished {
+                    (deletion_finished, upload_finished) = self.finish_phase();
                 }
             }
 
+            // Only the last file of the batch is ever shown
+            if let Some(index) = current_file {
+                self.state.current_file =
+                    Some(self.state.file_statuses[index].display_name().to_string());
+            }
+            self.state.evict_statuses();
             if had_updates {
                 ctx.request_repaint();
             }
         }
+
+        // A phase with nothing to process gets no status to finish it
+        let running = self.state.is_uploading || self.state.is_verifying || self.state.is_deleting;
+        let empty_phase = self
+            .state
+            .progress
+            .last_started()
+            .is_some_and(|phase| phase.total == 0);
+        if running && empty_phase {
+            (deletion_finished, upload_finished) = self.finish_phase();
+        }
+
+        if upload_finished {
+            self.record_run();
+            self.note_sync_in_description();
+        }
+
+        // A shown listing is stale once docs were uploaded or deleted
+        if (deletion_finished || upload_finished) && self.state.remote_docs.is_some() {
+            self.list_remote_docs();
+        }
+
+        self.advance_reupload();
+        self.finish_pass();
     }
 }
 
 impl App for ClaudeUploader {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        self.update_state(ctx);
-        self.render(ctx);
+        self.frame(ctx);
+    }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, PROFILES_STORAGE_KEY, &self.profiles);
+        eframe::set_value(storage, HISTORY_STORAGE_KEY, &self.history);
+        eframe::set_value(storage, APPEARANCE_STORAGE_KEY, &self.appearance);
+        eframe::set_value(storage, PASSES_STORAGE_KEY, &self.pass_plans);
+        if let Some(geometry) = &self.geometry {
+            eframe::set_value(storage, GEOMETRY_STORAGE_KEY, geometry);
+        }
+        self.persist_sessions();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uploader() -> ClaudeUploader {
+        ClaudeUploader::with_stores(Profiles::default(), RunHistory::default())
+    }
+
+    #[test]
+    fn manifest_sync_follows_the_setting() {
+        let mut uploader = uploader();
+        assert!(!uploader.manifest_sync_enabled());
+        uploader.settings.sync_with_manifest = true;
+        assert!(uploader.manifest_sync_enabled());
+    }
+
+    fn fixture_dir(fixture: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/upload/fixtures")
+            .join(fixture)
+    }
+
+    /// An uploader with a parsed session whose requests are answered from
+    /// the recorded exchanges in `fixture`.
+    fn replaying(fixture: &str) -> ClaudeUploader {
+        let mut uploader = uploader();
+        uploader.curl_text = CURL.to_string();
+        let replay = crate::upload::Replay::load(&fixture_dir(fixture)).unwrap();
+        uploader.fixtures = Some(crate::upload::Fixtures::Replay(Arc::new(replay)));
+        uploader
+    }
+
+    fn run_until_idle(uploader: &mut ClaudeUploader) {
+        let ctx = egui::Context::default();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while uploader.is_busy() {
+            assert!(Instant::now() < deadline, "run did not finish");
+            std::thread::sleep(Duration::from_millis(10));
+            uploader.update_state(&ctx);
+        }
+    }
+
+    fn succeeded(uploader: &ClaudeUploader, operation: Operation, name: &str) -> bool {
+        uploader.state.file_statuses.iter().any(|status| {
+            status.operation == operation
+                && status.name == name
+                && matches!(status.status, UploadStatus::Success)
+        })
+    }
+
+    #[test]
+    fn reupload_uploads_unchanged_files_with_manifest_sync_on() {
+        let mut uploader = replaying("reupload");
+        uploader.settings.sync_with_manifest = true;
+        let dir = fixture_dir("reupload");
+        uploader.folder_path = Some(dir.display().to_string());
+        let file = DiscoveredFile::from_path(&dir, &dir.join("notes.md")).unwrap();
+        uploader.state.discovery = Some(DiscoveryResult {
+            files: vec![file],
+            ..Default::default()
+        });
+        uploader.state.uploaded_files = vec![UploadedFile {
+            name: "notes.md".to_string(),
+            uuid: "doc-1".to_string(),
+        }];
+
+        // The replay has no manifest listing, a run that consulted the
+        // manifest would run out of step with it
+        uploader.delete_and_reupload();
+        run_until_idle(&mut uploader);
+
+        assert!(uploader.state.error_message.is_none());
+        assert!(succeeded(&uploader, Operation::Upload, "notes.md"));
+        assert!(succeeded(&uploader, Operation::Verify, "notes.md"));
+        assert!(succeeded(&uploader, Operation::Delete, "notes.md"));
+        assert!(uploader.state.replaced_files.is_none());
+        let uuids: Vec<&str> = uploader
+            .state
+            .uploaded_files
+            .iter()
+            .map(|file| file.uuid.as_str())
+            .collect();
+        assert_eq!(uuids, ["doc-2"]);
+    }
+
+    #[test]
+    fn section_resync_uploads_unchanged_files_with_manifest_sync_on() {
+        let mut uploader = replaying("section_resync");
+        uploader.settings.sync_with_manifest = true;
+        let dir = fixture_dir("section_resync");
+        uploader.folder_path = Some(dir.display().to_string());
+        uploader.state.keep_config = ClaudeKeepConfig::from_file(&dir);
+        let files = ["notes.md", "main.rs"]
+            .iter()
+            .map(|name| DiscoveredFile::from_path(&dir, &dir.join(name)).unwrap())
+            .collect();
+        uploader.state.discovery = Some(DiscoveryResult {
+            files,
+            ..Default::default()
+        });
+        let doc = |uuid: &str, file_name: &str| RemoteDoc {
+            uuid: uuid.to_string(),
+            file_name: file_name.to_string(),
+            content: None,
+            created_at: None,
+        };
+        uploader.state.remote_docs = Some(vec![
+            doc("doc-1", "[notes] notes.md"),
+            doc("doc-3", "[code] main.rs"),
+        ]);
+
+        uploader.resync_section("notes");
+        assert!(!uploader.state.replacing_docs);
+        assert!(uploader.state.resync_section.is_none());
+        // A refreshed listing would race the replayed requests
+        uploader.state.remote_docs = None;
+        run_until_idle(&mut uploader);
+
+        assert!(uploader.state.error_message.is_none());
+        assert!(succeeded(&uploader, Operation::Upload, "notes.md"));
+        assert!(succeeded(&uploader, Operation::Delete, "[notes] notes.md"));
+        let uploaded: Vec<(&str, &str)> = uploader
+            .state
+            .uploaded_files
+            .iter()
+            .map(|file| (file.name.as_str(), file.uuid.as_str()))
+            .collect();
+        assert_eq!(uploaded, [("[notes] notes.md", "doc-2")]);
+        // Files and docs of other sections are left alone
+        assert!(!uploader
+            .state
+            .file_statuses
+            .iter()
+            .any(|status| status.name.contains("main.rs")));
+    }
+
+    #[test]
+    fn reupload_that_cannot_start_keeps_the_previous_docs() {
+        let mut uploader = uploader();
+        uploader.settings.sync_with_manifest = true;
+        let previous = vec![UploadedFile {
+            name: "main.rs".to_string(),
+            uuid: "doc-1".to_string(),
+        }];
+        uploader.state.uploaded_files = previous.clone();
+
+        // Nothing discovered, so nothing to reupload
+        uploader.delete_and_reupload();
+        assert!(!uploader.state.replacing_docs);
+        assert!(uploader.state.replaced_files.is_none());
+        assert_eq!(uploader.state.uploaded_files.len(), previous.len());
+        assert!(uploader.manifest_sync_enabled());
+    }
+
+    #[test]
+    fn profile_windows_record_into_the_main_stores() {
+        let mut main = uploader();
+        main.profiles.save_as("work", None, None);
+        main.profiles.save_as("home", None, None);
+        main.profiles.active = Some(1);
+
+        let mut window = uploader();
+        main.lend_stores(&mut window, |window| {
+            window.activate_profile(0);
+            window.profiles.save_as("scratch", None, None);
+            window
+                .pass_plans
+                .replace(PassPlan::split("/project", &[], 10));
+            window.history.push(RunRecord::from_statuses(
+                &[],
+                Duration::ZERO,
+                None,
+                Vec::new(),
+                FilterOptions::default(),
+            ));
+        });
+
+        assert_eq!(main.profiles.profiles.len(), 3);
+        assert_eq!(main.profiles.active, Some(1));
+        assert_eq!(main.history.runs.len(), 1);
+        assert!(main.pass_plans.for_folder("/project").is_some());
+
+        // The window's own active profile comes back with the stores
+        main.lend_stores(&mut window, |window| {
+            assert_eq!(window.profiles.active, Some(2));
+        });
+    }
+
+    #[test]
+    fn switching_profiles_keeps_the_picked_organization_and_project() {
+        let mut uploader = uploader();
+        uploader.profiles.save_as("work", None, None);
+        uploader.profiles.save_as("home", None, None);
+        uploader.activate_profile(0);
+        uploader.curl_text = CURL.to_string();
+        uploader.curl_changed();
+        uploader.curl_parser.organization_override = Some("org-2".to_string());
+        uploader.curl_parser.project_override = Some("project-2".to_string());
+
+        uploader.activate_profile(1);
+        assert!(uploader.curl_text.is_empty());
+        assert!(uploader.curl_parser.organization_override.is_none());
+        let stored = &uploader.sessions["work"];
+        assert_eq!(stored.organization_override.as_deref(), Some("org-2"));
+        assert_eq!(stored.project_override.as_deref(), Some("project-2"));
+
+        uploader.activate_profile(0);
+        assert_eq!(uploader.curl_text, CURL);
+        assert_eq!(
+            uploader.curl_parser.organization_override.as_deref(),
+            Some("org-2")
+        );
+        assert_eq!(
+            uploader.curl_parser.project_override.as_deref(),
+            Some("project-2")
+        );
+    }
+
+    const CURL: &str = "curl 'https://claude.ai/api/organizations/org-1/projects/project-1/docs' \
+        -H 'cookie: sessionKey=abc'";
+
+    #[test]
+    fn runs_with_nothing_to_upload_finish() {
+        let mut uploader = uploader();
+        uploader.curl_text = CURL.to_string();
+        let folder = tempfile::tempdir().unwrap();
+        uploader.folder_path = Some(folder.path().display().to_string());
+        uploader.state.discovery = Some(DiscoveryResult::default());
+        uploader.state.discovery_key = uploader.discovery_key();
+
+        uploader.start_upload(None);
+        assert!(uploader.state.is_uploading);
+        assert!(uploader.state.error_message.is_none());
+
+        uploader.update_state(&egui::Context::default());
+        assert!(!uploader.state.is_uploading);
+        assert!(uploader.state.progress.is_completed());
+        assert!(!uploader.is_busy());
+    }
+
+    #[test]
+    fn long_runs_drop_old_statuses_but_keep_failures_and_history() {
+        let mut uploader = uploader();
+        let files = 20_000;
+        for index in 0..files {
+            let file = DiscoveredFile {
+                path: PathBuf::from(format!("/project/{}.md", index)),
+                relative_path: format!("{}.md", index),
+                size: 1,
+                modified: None,
+            };
+            let outcome = if index == 0 {
+                UploadStatus::Error(UploadError::new(ErrorKind::Network, "offline".to_string()))
+            } else {
+                UploadStatus::Success
+            };
+            let statuses = &mut uploader.state.file_statuses;
+            statuses.push(FileStatus::for_file(&file, UploadStatus::Processing));
+            statuses.push(FileStatus::for_file(&file, outcome));
+            uploader.state.evict_statuses();
+        }
+
+        let evicted = &uploader.state.evicted_statuses;
+        assert!(evicted.entries > 0);
+        assert!(uploader.state.file_statuses.len() < files * 2);
+        assert_eq!(
+            evicted.entries + uploader.state.file_statuses.len(),
+            files * 2
+        );
+        assert_eq!(uploader.failure_groups().get(&ErrorKind::Network), Some(&1));
+
+        uploader.record_run();
+        let record = uploader.history.runs.last().unwrap();
+        assert_eq!(record.files.len(), files);
+
+        uploader.state.clear_statuses();
+        assert_eq!(uploader.state.evicted_statuses.entries, 0);
     }
 }
@@ -0,0 +1,138 @@
+use crate::upload::types::{DiscoveredFile, UploadedFile};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+pub const PASSES_STORAGE_KEY: &str = "passes";
+
+/// A part of a folder uploaded on its own.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pass {
+    pub name: String,
+    /// Relative paths of the files in this pass.
+    pub files: Vec<String>,
+}
+
+/// Passes over a folder with more files than its project holds. Running a
+/// pass replaces the docs of the one run before it, so the project knows
+/// one part of the folder at a time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PassPlan {
+    pub folder_path: String,
+    pub passes: Vec<Pass>,
+    /// Pass whose docs are in the project now.
+    #[serde(default)]
+    pub active: Option<usize>,
+    /// Docs uploaded by the last pass, deleted once the next one succeeds.
+    #[serde(default)]
+    pub active_docs: Vec<UploadedFile>,
+}
+
+impl PassPlan {
+    /// Splits `files` into passes of at most `capacity` files. Paths are
+    /// sorted first, so a directory spreads over as few passes as possible.
+    pub fn split(folder_path: &str, files: &[DiscoveredFile], capacity: usize) -> Self {
+        let mut paths: Vec<&str> = files
+            .iter()
+            .map(|file| file.relative_path.as_str())
+            .collect();
+        paths.sort_unstable();
+        let passes = paths
+            .chunks(capacity.max(1))
+            .enumerate()
+            .map(|(index, chunk)| Pass {
+                name: Self::pass_name(index, chunk),
+                files: chunk.iter().map(|path| path.to_string()).collect(),
+            })
+            .collect();
+
+        Self {
+            folder_path: folder_path.to_string(),
+            passes,
+            active: None,
+            active_docs: Vec::new(),
+        }
+    }
+
+    /// "Pass 2: docs – src", named after the top level entries it spans.
+    fn pass_name(index: usize, paths: &[&str]) -> String {
+        let top_level = |path: &&str| path.split('/').next().unwrap_or(path).to_string();
+        let first = paths.first().map(top_level).unwrap_or_default();
+        let last = paths.last().map(top_level).unwrap_or_default();
+        if first == last {
+            format!("Pass {}: {}", index + 1, first)
+        } else {
+            format!("Pass {}: {} – {}", index + 1, first, last)
+        }
+    }
+
+    /// The discovered files that belong to pass `index`. Files removed since
+    /// the plan was made are left out.
+    pub fn files_of(&self, index: usize, discovered: &[DiscoveredFile]) -> Vec<DiscoveredFile> {
+        let Some(pass) = self.passes.get(index) else {
+            return Vec::new();
+        };
+        let paths: HashSet<&str> = pass.files.iter().map(String::as_str).collect();
+        discovered
+            .iter()
+            .filter(|file| paths.contains(file.relative_path.as_str()))
+            .cloned()
+            .collect()
+    }
+
+    /// Discovered files no pass includes, e.g. ones added since planning.
+    pub fn unplanned(&self, discovered: &[DiscoveredFile]) -> usize {
+        let planned: HashSet<&str> = self
+            .passes
+            .iter()
+            .flat_map(|pass| pass.files.iter().map(String::as_str))
+            .collect();
+        discovered
+            .iter()
+            .filter(|file| !planned.contains(file.relative_path.as_str()))
+            .count()
+    }
+}
+
+/// Saved pass plans, one per folder, kept between launches.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PassPlans {
+    pub plans: Vec<PassPlan>,
+}
+
+impl PassPlans {
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        storage
+            .and_then(|storage| eframe::get_value(storage, PASSES_STORAGE_KEY))
+            .unwrap_or_default()
+    }
+
+    pub fn for_folder(&self, folder_path: &str) -> Option<&PassPlan> {
+        self.plans
+            .iter()
+            .find(|plan| plan.folder_path == folder_path)
+    }
+
+    pub fn for_folder_mut(&mut self, folder_path: &str) -> Option<&mut PassPlan> {
+        self.plans
+            .iter_mut()
+            .find(|plan| plan.folder_path == folder_path)
+    }
+
+    /// Stores `plan` in place of the folder's previous one. Docs of the
+    /// previous plan's active pass are still in the project, so the first
+    /// pass run from the new plan replaces them.
+    pub fn replace(&mut self, mut plan: PassPlan) {
+        match self.for_folder_mut(&plan.folder_path) {
+            Some(previous) => {
+                plan.active_docs = std::mem::take(&mut previous.active_docs);
+                *previous = plan;
+            }
+            None => self.plans.push(plan),
+        }
+    }
+
+    pub fn remove(&mut self, folder_path: &str) {
+        self.plans.retain(|plan| plan.folder_path != folder_path);
+    }
+}
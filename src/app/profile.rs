@@ -0,0 +1,130 @@
+use crate::upload::{FilterOptions, Permissions};
+use glob::Pattern;
+use serde::{Deserialize, Serialize};
+
+pub const PROFILES_STORAGE_KEY: &str = "profiles";
+
+/// A named upload target that can be shared between teammates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub name: String,
+    pub folder_path: Option<String>,
+    pub allow_upload: bool,
+    pub allow_delete: bool,
+    /// Filter rules for this profile; `None` uses the global ones.
+    pub filter_options: Option<FilterOptions>,
+    /// `Name: value` lines applied to every request after curl parsing.
+    pub header_overrides: String,
+    /// Template for the project API URL; empty uses the default shape.
+    pub project_url: String,
+    /// Git branches this profile targets, e.g. `main` or `feature/*`; empty
+    /// matches no branch.
+    pub branch_pattern: String,
+    /// Upload to the Anthropic Files API with an API key instead of the
+    /// pasted claude.ai session.
+    pub use_api_key: bool,
+    /// Organization of the session the profile was created with. Its
+    /// session is never sent to another one.
+    pub organization_id: Option<String>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            folder_path: None,
+            allow_upload: true,
+            allow_delete: true,
+            filter_options: None,
+            header_overrides: String::new(),
+            project_url: String::new(),
+            branch_pattern: String::new(),
+            use_api_key: false,
+            organization_id: None,
+        }
+    }
+}
+
+impl Profile {
+    pub fn permissions(&self) -> Permissions {
+        Permissions {
+            upload: self.allow_upload,
+            delete: self.allow_delete,
+        }
+    }
+
+    pub fn matches_branch(&self, branch: &str) -> bool {
+        let pattern = self.branch_pattern.trim();
+        if pattern.is_empty() {
+            return false;
+        }
+        Pattern::new(pattern)
+            .map(|pattern| pattern.matches(branch))
+            .unwrap_or(false)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profiles {
+    pub profiles: Vec<Profile>,
+    pub active: Option<usize>,
+}
+
+impl Profiles {
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        storage
+            .and_then(|storage| eframe::get_value(storage, PROFILES_STORAGE_KEY))
+            .unwrap_or_default()
+    }
+
+    pub fn active(&self) -> Option<&Profile> {
+        self.active.and_then(|index| self.profiles.get(index))
+    }
+
+    pub fn active_mut(&mut self) -> Option<&mut Profile> {
+        self.active.and_then(|index| self.profiles.get_mut(index))
+    }
+
+    /// Stores the current folder under `name`, replacing a profile with the
+    /// same name but keeping its permissions and organization. Profiles
+    /// saved before organizations were recorded take `organization_id`.
+    pub fn save_as(
+        &mut self,
+        name: &str,
+        folder_path: Option<String>,
+        organization_id: Option<String>,
+    ) {
+        let index = match self.profiles.iter().position(|p| p.name == name) {
+            Some(index) => index,
+            None => {
+                self.profiles.push(Profile {
+                    name: name.to_string(),
+                    ..Profile::default()
+                });
+                self.profiles.len() - 1
+            }
+        };
+
+        let profile = &mut self.profiles[index];
+        profile.folder_path = folder_path;
+        if profile.organization_id.is_none() {
+            profile.organization_id = organization_id;
+        }
+        self.active = Some(index);
+    }
+
+    /// The first profile whose branch pattern matches `branch`.
+    pub fn for_branch(&self, branch: &str) -> Option<usize> {
+        self.profiles.iter().position(|p| p.matches_branch(branch))
+    }
+
+    pub fn remove_active(&mut self) {
+        if let Some(index) = self.active.take() {
+            if index < self.profiles.len() {
+                self.profiles.remove(index);
+            }
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use crate::upload::UploadStatus;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Upload,
+    Verify,
+    Delete,
+}
+
+impl Phase {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Upload => "Upload",
+            Self::Verify => "Verify",
+            Self::Delete => "Delete",
+        }
+    }
+
+    pub fn verb(&self) -> &'static str {
+        match self {
+            Self::Upload => "Uploading",
+            Self::Verify => "Verifying",
+            Self::Delete => "Deleting",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PhaseProgress {
+    pub phase: Phase,
+    pub started: bool,
+    pub total: usize,
+    pub current: usize,
+    pub successful: usize,
+    pub failed: usize,
+    pub skipped: usize,
+}
+
+impl PhaseProgress {
+    fn planned(phase: Phase) -> Self {
+        Self {
+            phase,
+            started: false,
+            total: 0,
+            current: 0,
+            successful: 0,
+            failed: 0,
+            skipped: 0,
+        }
+    }
+
+    fn finished(&self) -> usize {
+        self.successful + self.failed + self.skipped
+    }
+
+    /// Files reached so far; phases without Processing events only count
+    /// finished files.
+    pub fn reached(&self) -> usize {
+        self.current.max(self.finished()).min(self.total)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.started && self.finished() >= self.total
+    }
+
+    pub fn fraction(&self) -> f32 {
+        if self.is_done() {
+            1.0
+        } else if self.total == 0 {
+            0.0
+        } else {
+            (self.reached() as f32) / (self.total as f32)
+        }
+    }
+
+    pub fn status_text(&self) -> String {
+        if !self.started {
+            return format!("{}: waiting", self.phase.label());
+        }
+        format!(
+            "{}: {}/{} files | ✅ Success: {} | ⏩ Skipped: {} | ❌ Failed: {}",
+            self.phase.label(),
+            self.reached(),
+            self.total,
+            self.successful,
+            self.skipped,
+            self.failed
+        )
+    }
+}
+
+/// Progress of a run made of one or more phases, e.g. upload → verify →
+/// delete for a reupload. Phases that are planned but not yet started are
+/// shown as waiting and count as 0% towards the overall progress.
+#[derive(Debug, Clone, Default)]
+pub struct RunProgress {
+    phases: Vec<PhaseProgress>,
+    started_at: Option<Instant>,
+    finished_at: Option<Instant>,
+    cancelled: bool,
+}
+
+impl RunProgress {
+    pub fn plan(phases: &[Phase]) -> Self {
+        Self {
+            phases: phases.iter().copied().map(PhaseProgress::planned).collect(),
+            ..Self::default()
+        }
+    }
+
+    pub fn phases(&self) -> &[PhaseProgress] {
+        &self.phases
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.phases.iter().any(|phase| phase.started)
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.is_started() && self.phases.iter().all(PhaseProgress::is_done)
+    }
+
+    pub fn is_planned(&self, phase: Phase) -> bool {
+        self.phases.iter().any(|p| p.phase == phase && !p.started)
+    }
+
+    /// Marks the run as cancelled by the user; its remaining files still
+    /// arrive as skipped.
+    pub fn cancel(&mut self) {
+        self.cancelled = true;
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    pub fn has_failures(&self) -> bool {
+        self.phases.iter().any(|phase| phase.failed > 0)
+    }
+
+    pub fn last_started(&self) -> Option<&PhaseProgress> {
+        self.phases.iter().rev().find(|phase| phase.started)
+    }
+
+    /// Starts the next planned entry for `phase`, or appends one.
+    pub fn start_phase(&mut self, phase: Phase, total: usize) {
+        let index = match self
+            .phases
+            .iter()
+            .position(|p| p.phase == phase && !p.started)
+        {
+            Some(index) => index,
+            None => {
+                self.phases.push(PhaseProgress::planned(phase));
+                self.phases.len() - 1
+            }
+        };
+
+        let entry = &mut self.phases[index];
+        entry.started = true;
+        entry.total = total;
+        self.started_at.get_or_insert_with(Instant::now);
+        self.finished_at = None;
+        self.mark_if_finished();
+    }
+
+    /// Removes the phases that will no longer run, e.g. after a rollback.
+    pub fn drop_pending(&mut self) {
+        self.phases.retain(|phase| phase.started);
+        self.mark_if_finished();
+    }
+
+    /// Time since the first phase started, up to the end of the last one.
+    pub fn elapsed(&self) -> Option<Duration> {
+        let started_at = self.started_at?;
+        let end = self.finished_at.unwrap_or_else(Instant::now);
+        Some(end.duration_since(started_at))
+    }
+
+    fn mark_if_finished(&mut self) {
+        if self.finished_at.is_none() && self.is_completed() {
+            self.finished_at = Some(Instant::now());
+        }
+    }
+
+    /// Counts a status event towards the running phase. Returns true if it
+    /// finished that phase.
+    pub fn record(&mut self, status: &UploadStatus) -> bool {
+        let Some(active) = self
+            .phases
+            .iter_mut()
+            .find(|phase| phase.started && !phase.is_done())
+        else {
+            return false;
+        };
+
+        match status {
+            UploadStatus::Processing => active.current += 1,
+            UploadStatus::Success => active.successful += 1,
+            UploadStatus::Error(_) => active.failed += 1,
+            UploadStatus::Skipped(_) => active.skipped += 1,
+            _ => {}
+        }
+        let done = active.is_done();
+        self.mark_if_finished();
+        done
+    }
+
+    pub fn overall_fraction(&self) -> f32 {
+        if self.phases.is_empty() {
+            return 0.0;
+        }
+        let sum: f32 = self.phases.iter().map(PhaseProgress::fraction).sum();
+        sum / self.phases.len() as f32
+    }
+}
@@ -0,0 +1,76 @@
+use crate::upload::{estimate_tokens, DiscoveredFile};
+use crate::utils::claude_keep::ClaudeKeepConfig;
+use std::path::Path;
+
+/// What selecting a section would add to the upload.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SectionImpact {
+    pub files: usize,
+    pub tokens: usize,
+}
+
+struct SectionFile {
+    tokens: usize,
+    /// Indices into `SectionIndex::sections` of the sections matching the file.
+    sections: Vec<usize>,
+}
+
+/// Which `.claudekeep` sections every candidate file belongs to, built from
+/// a discovery over all sections so the impact of each one is known before
+/// it is selected.
+pub struct SectionIndex {
+    sections: Vec<String>,
+    files: Vec<SectionFile>,
+}
+
+impl SectionIndex {
+    pub fn build(
+        config: &ClaudeKeepConfig,
+        files: &[DiscoveredFile],
+        case_insensitive: bool,
+    ) -> Self {
+        let sections = config.sections.clone();
+        let files = files
+            .iter()
+            .map(|file| SectionFile {
+                // Bytes stand in for characters, which is close enough for source code
+                tokens: estimate_tokens(file.size as usize),
+                sections: sections
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, section)| {
+                        config.should_include_file(
+                            Path::new(&file.relative_path),
+                            std::slice::from_ref(*section),
+                            case_insensitive,
+                        )
+                    })
+                    .map(|(index, _)| index)
+                    .collect(),
+            })
+            .collect();
+
+        Self { sections, files }
+    }
+
+    /// Files of `section` that none of the `selected` sections cover yet.
+    pub fn impact(&self, section: &str, selected: &[String]) -> SectionImpact {
+        let Some(index) = self.index_of(section) else {
+            return SectionImpact::default();
+        };
+        let selected: Vec<usize> = selected.iter().filter_map(|s| self.index_of(s)).collect();
+
+        self.files
+            .iter()
+            .filter(|file| file.sections.contains(&index))
+            .filter(|file| !file.sections.iter().any(|s| selected.contains(s)))
+            .fold(SectionImpact::default(), |impact, file| SectionImpact {
+                files: impact.files + 1,
+                tokens: impact.tokens + file.tokens,
+            })
+    }
+
+    fn index_of(&self, section: &str) -> Option<usize> {
+        self.sections.iter().position(|s| s == section)
+    }
+}
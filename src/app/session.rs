@@ -0,0 +1,108 @@
+use crate::upload::UploadError;
+use reqwest::header::{HeaderMap, COOKIE};
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Remaining lifetime below which the session is reported as expiring.
+pub const EXPIRY_WARNING: Duration = Duration::from_secs(60 * 60);
+
+/// Passphrase prompt shown at startup when there is no OS keychain to
+/// keep sessions in.
+#[derive(Debug, Default)]
+pub struct SessionUnlock {
+    pub passphrase: String,
+    /// Whether sessions saved with a passphrase are waiting, rather than a
+    /// passphrase being chosen for the first time.
+    pub existing: bool,
+    pub error: Option<String>,
+}
+
+/// What is known about the validity of the pasted session: the result of
+/// the last request that proved or disproved it, and the expiry of any
+/// JWT cookie it carries.
+#[derive(Default)]
+pub struct SessionHealth {
+    pub last_check: Option<(SystemTime, bool)>,
+    pub expires_at: Option<SystemTime>,
+    pub check_receiver: Option<Receiver<Result<(), UploadError>>>,
+}
+
+impl SessionHealth {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let expires_at = headers
+            .get_all(COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|cookies| cookies.split(';'))
+            .filter_map(|cookie| cookie.split_once('=').map(|(_, value)| value.trim()))
+            .filter_map(jwt_expiry)
+            .min();
+
+        Self {
+            expires_at,
+            ..Self::default()
+        }
+    }
+
+    pub fn mark(&mut self, valid: bool) {
+        self.last_check = Some((SystemTime::now(), valid));
+    }
+
+    pub fn is_expired(&self) -> bool {
+        matches!(self.last_check, Some((_, false))) || self.remaining() == Some(Duration::ZERO)
+    }
+
+    /// Time left until the cookie expiry, if one is known.
+    pub fn remaining(&self) -> Option<Duration> {
+        let expires_at = self.expires_at?;
+        Some(
+            expires_at
+                .duration_since(SystemTime::now())
+                .unwrap_or(Duration::ZERO),
+        )
+    }
+
+    pub fn is_expiring(&self) -> bool {
+        self.remaining()
+            .map(|remaining| remaining < EXPIRY_WARNING)
+            .unwrap_or(false)
+    }
+}
+
+/// The `exp` claim of a cookie value that is a JWT.
+fn jwt_expiry(value: &str) -> Option<SystemTime> {
+    let mut parts = value.split('.');
+    let (_, payload, _) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() {
+        return None;
+    }
+
+    let payload = decode_base64_url(payload)?;
+    let claims: serde_json::Value = serde_json::from_slice(&payload).ok()?;
+    let exp = claims.get("exp")?.as_u64()?;
+    Some(UNIX_EPOCH + Duration::from_secs(exp))
+}
+
+fn decode_base64_url(input: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(input.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for c in input.trim_end_matches('=').bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'-' | b'+' => 62,
+            b'_' | b'/' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    Some(bytes)
+}
@@ -0,0 +1,87 @@
+use super::trim::RunCap;
+use crate::upload::{PacingPolicy, PreflightPolicy, RetryPolicy};
+use std::time::Duration;
+
+pub const DEFAULT_PROJECT_DOC_LIMIT: usize = 500;
+
+/// User preferences that survive "Clear All".
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub project_doc_limit: usize,
+    /// Most files a single run may upload, 0 for no cap.
+    pub run_cap_files: usize,
+    /// Most megabytes a single run may upload, 0 for no cap.
+    pub run_cap_mb: f64,
+    pub preflight_attempts: u32,
+    pub preflight_interval_secs: u64,
+    /// Tries per file for uploads failing with network or server errors.
+    pub upload_attempts: u32,
+    pub retry_delay_secs: u64,
+    pub sync_with_manifest: bool,
+    /// Note the last sync in the project description after each run.
+    pub note_sync_in_description: bool,
+    /// Keep a SYNC_INFO.md doc describing the last run in the project.
+    pub upload_sync_info: bool,
+    /// Upload files again at the end of a run if they changed mid-upload.
+    pub reupload_modified_files: bool,
+    /// Prefix doc names with their `.claudekeep` section.
+    pub tag_docs_with_section: bool,
+    pub inspection_mode: bool,
+    pub request_interval_ms: u64,
+    pub delete_concurrency: usize,
+    pub rate_limit_retries: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            project_doc_limit: DEFAULT_PROJECT_DOC_LIMIT,
+            run_cap_files: 0,
+            run_cap_mb: 0.0,
+            preflight_attempts: 3,
+            preflight_interval_secs: 15,
+            upload_attempts: 3,
+            retry_delay_secs: 2,
+            sync_with_manifest: false,
+            note_sync_in_description: false,
+            upload_sync_info: false,
+            reupload_modified_files: false,
+            tag_docs_with_section: false,
+            inspection_mode: false,
+            request_interval_ms: 250,
+            delete_concurrency: 2,
+            rate_limit_retries: 5,
+        }
+    }
+}
+
+impl Settings {
+    pub fn preflight_policy(&self) -> PreflightPolicy {
+        PreflightPolicy {
+            attempts: self.preflight_attempts.max(1),
+            interval: Duration::from_secs(self.preflight_interval_secs),
+        }
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            attempts: self.upload_attempts.max(1),
+            initial_delay: Duration::from_secs(self.retry_delay_secs),
+        }
+    }
+
+    pub fn run_cap(&self) -> RunCap {
+        RunCap {
+            max_files: Some(self.run_cap_files).filter(|&files| files > 0),
+            max_bytes: Some((self.run_cap_mb * 1024.0 * 1024.0) as u64).filter(|&bytes| bytes > 0),
+        }
+    }
+
+    pub fn pacing_policy(&self) -> PacingPolicy {
+        PacingPolicy {
+            request_interval: Duration::from_millis(self.request_interval_ms),
+            delete_concurrency: self.delete_concurrency.max(1),
+            rate_limit_retries: self.rate_limit_retries,
+        }
+    }
+}
@@ -1,51 +1,162 @@
-use crate::upload::{FileStatus, UploadedFile};
+use super::history::{FileOutcome, RunFile};
+use super::progress::RunProgress;
+use super::sections::SectionIndex;
+use super::trim::TrimProposal;
+use crate::upload::{
+    ContentAnalysis, DiscoveryKey, DiscoveryResult, DiscoveryScan, FileStatus, FilterOptions,
+    Operation, Preset, RemoteDoc, RemoteOrganization, RemoteProject, RunControl, UploadError,
+    UploadPlan, UploadStatus, UploadedFile,
+};
 use crate::utils::claude_keep::ClaudeKeepConfig;
 use derivative::Derivative;
+use std::collections::HashSet;
 use std::sync::mpsc::Receiver;
+use std::time::Instant;
 
-#[derive(Clone)]
-pub enum ActionProgress {
-    NotStarted,
-    Uploading {
-        total: usize,
-        current: usize,
-        successful: usize,
-        failed: usize,
-        skipped: usize,
-    },
-    Deleting {
-        total: usize,
-        current: usize,
-        successful: usize,
-        failed: usize,
-    },
-    Completed {
-        total: usize,
-        successful: usize,
-        failed: usize,
-        skipped: usize,
-    },
+/// Statuses kept for the details list. Beyond this the oldest entries are
+/// dropped, except failures, which can still be retried or skipped.
+const MAX_FILE_STATUSES: usize = 20_000;
+
+/// Entries dropped at once, so a long run doesn't shift the list every frame.
+const STATUS_EVICTION_BATCH: usize = 1_000;
+
+/// What the statuses dropped from the details list added up to.
+#[derive(Debug, Clone, Default)]
+pub struct EvictedStatuses {
+    pub entries: usize,
+    pub uploaded: usize,
+    pub skipped: usize,
+    /// Final outcome of each dropped upload, for the run history and the
+    /// selection report.
+    pub files: Vec<RunFile>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DocSort {
+    #[default]
+    Name,
+    Size,
+    Created,
 }
 
-impl Default for ActionProgress {
-    fn default() -> Self {
-        Self::NotStarted
+impl DocSort {
+    pub const ALL: [DocSort; 3] = [Self::Name, Self::Size, Self::Created];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Name => "Name",
+            Self::Size => "Size",
+            Self::Created => "Upload date",
+        }
     }
 }
 
 #[derive(Default)]
 pub struct UploadState {
-    pub progress: ActionProgress,
+    pub progress: RunProgress,
     pub current_file: Option<String>,
     pub file_statuses: Vec<FileStatus>,
+    /// Totals of the statuses dropped from `file_statuses` in a long run.
+    pub evicted_statuses: EvictedStatuses,
     pub uploaded_files: Vec<UploadedFile>,
     pub error_message: Option<String>,
     pub show_details: bool,
+    /// Extra pages of older statuses loaded into the details list.
+    pub status_pages: usize,
+    /// Show the details list grouped by top-level directory.
+    pub group_by_directory: bool,
+    /// Keep the details list scrolled to the newest entry.
+    pub follow_latest: bool,
+    /// Preview of a failed file opened from the failure summary.
+    pub fix_preview: Option<(String, String)>,
     pub is_uploading: bool,
     pub is_deleting: bool,
+    pub is_verifying: bool,
     pub keep_config: Option<ClaudeKeepConfig>,
     pub selected_sections: Vec<String>,
-    pub status_receiver: Option<Receiver<FileStatus>>,
+    pub filter_options: FilterOptions,
+    /// Filter presets matching the selected folder's project type.
+    pub presets: Vec<&'static Preset>,
+    /// Outcome of the last ignore file import.
+    pub import_message: Option<String>,
+    pub discovery: Option<DiscoveryResult>,
+    pub discovery_key: Option<DiscoveryKey>,
+    pub discovery_scan: Option<DiscoveryScan>,
+    /// When the folder's git branch was last compared with the discovered one.
+    pub branch_checked_at: Option<Instant>,
+    /// Branch whose profile suggestion was dismissed.
+    pub dismissed_branch: Option<String>,
+    /// Paths pasted or loaded as an explicit selection, one per line.
+    pub file_list_text: String,
+    /// Upload exactly the listed paths instead of discovering files.
+    pub use_file_list: bool,
+    pub file_list_message: Option<String>,
+    /// Sensitive file waiting for the user to confirm its upload.
+    pub confirm_sensitive: Option<String>,
+    /// "Delete selected" in the remote files panel was clicked and waits
+    /// for confirmation.
+    pub confirm_delete_docs: bool,
+    /// Files proposed to be left out because the selection is over the run cap.
+    pub trim: Option<TrimProposal>,
+    /// Plan being executed; nothing outside it is uploaded.
+    pub approved_plan: Option<UploadPlan>,
+    /// Shared key plans are signed and checked with. Never stored.
+    pub plan_key: String,
+    /// Discovery over all `.claudekeep` sections, to estimate each one.
+    pub section_key: Option<DiscoveryKey>,
+    pub section_scan: Option<DiscoveryScan>,
+    pub section_index: Option<SectionIndex>,
+    pub content_analysis: Option<ContentAnalysis>,
+    pub run_control: Option<RunControl>,
+    pub session_check: Option<(u32, u32)>,
+    pub awaiting_reauth: bool,
+    pub offline: bool,
+    pub reauth_curl_text: String,
+    pub last_run_stopped: bool,
+    /// Docs that a running reupload will replace once it succeeds.
+    pub replaced_files: Option<Vec<UploadedFile>>,
+    /// Section whose docs are being re-synced; every uploaded file is tagged
+    /// with it.
+    pub resync_section: Option<String>,
+    /// Pass of the folder's pass plan being run; its uploaded docs become
+    /// the plan's active ones once it finishes.
+    pub running_pass: Option<usize>,
+    /// Set while starting a "Sync changes" run, which uploads new and
+    /// modified files only and deletes docs of removed ones.
+    pub syncing_changes: bool,
+    /// Set while starting a run whose docs replace existing ones. Such runs
+    /// upload unchanged files too, since the replaced docs get deleted.
+    pub replacing_docs: bool,
+    pub remote_docs: Option<Vec<RemoteDoc>>,
+    pub remote_docs_receiver: Option<Receiver<Result<Vec<RemoteDoc>, UploadError>>>,
+    /// Organizations of the session's account, for the organization picker.
+    pub organizations: Option<Vec<RemoteOrganization>>,
+    pub organizations_receiver: Option<Receiver<Result<Vec<RemoteOrganization>, UploadError>>>,
+    /// Projects of the session's organization, for the project picker.
+    pub projects: Option<Vec<RemoteProject>>,
+    pub projects_receiver: Option<Receiver<Result<Vec<RemoteProject>, UploadError>>>,
+    /// Remote doc opened in the inspection panel, with its content.
+    pub opened_doc: Option<RemoteDoc>,
+    pub opened_doc_receiver: Option<Receiver<Result<RemoteDoc, UploadError>>>,
+    pub description_receiver: Option<Receiver<Result<(), UploadError>>>,
+    /// Statuses of a dry run walking the folder.
+    pub dry_run_receiver: Option<Receiver<Vec<FileStatus>>>,
+    pub doc_search: String,
+    /// Search across the contents of all listed docs.
+    pub docs_search: String,
+    /// Uuids of the docs selected for a bulk download or delete.
+    pub selected_docs: HashSet<String>,
+    pub docs_glob: String,
+    /// Only show docs tagged with this section.
+    pub docs_section: Option<String>,
+    pub docs_sort: DocSort,
+    pub docs_sort_descending: bool,
+    /// A repeated run waiting for discovery of its configuration to finish.
+    pub repeat_pending: bool,
+    /// Indices into the run history of the two runs being compared.
+    pub compared_runs: (Option<usize>, Option<usize>),
+    /// Status events of the running phase, in batches.
+    pub status_receiver: Option<Receiver<Vec<FileStatus>>>,
     pub uploaded_files_receiver: Option<Receiver<Vec<UploadedFile>>>,
 }
 
@@ -54,87 +165,119 @@ impl UploadState {
         *self = UploadState::default();
     }
 
+    pub fn clear_statuses(&mut self) {
+        self.file_statuses.clear();
+        self.evicted_statuses = EvictedStatuses::default();
+    }
+
+    /// Drops the oldest statuses but failures once there are too many to
+    /// keep, counting what they were.
+    pub fn evict_statuses(&mut self) {
+        if self.file_statuses.len() <= MAX_FILE_STATUSES + STATUS_EVICTION_BATCH {
+            return;
+        }
+        let mut excess = self.file_statuses.len() - MAX_FILE_STATUSES;
+        let evicted = &mut self.evicted_statuses;
+        self.file_statuses.retain(|status| {
+            if excess == 0 || matches!(status.status, UploadStatus::Error(_)) {
+                return true;
+            }
+            excess -= 1;
+            evicted.entries += 1;
+            let outcome = match status.status {
+                UploadStatus::Success => {
+                    evicted.uploaded += 1;
+                    FileOutcome::Uploaded
+                }
+                UploadStatus::Skipped(_) => {
+                    evicted.skipped += 1;
+                    FileOutcome::Skipped
+                }
+                _ => return false,
+            };
+            if let (Operation::Upload, Some(relative_path)) =
+                (status.operation, &status.relative_path)
+            {
+                evicted.files.push(RunFile {
+                    relative_path: relative_path.clone(),
+                    size: status.size.unwrap_or(0),
+                    outcome,
+                });
+            }
+            false
+        });
+    }
+
     pub fn clone_without_receivers(&self) -> Self {
         Self {
             progress: self.progress.clone(),
             current_file: self.current_file.clone(),
             file_statuses: self.file_statuses.clone(),
+            evicted_statuses: self.evicted_statuses.clone(),
             uploaded_files: self.uploaded_files.clone(),
             error_message: self.error_message.clone(),
             show_details: self.show_details,
+            status_pages: self.status_pages,
+            group_by_directory: self.group_by_directory,
+            follow_latest: self.follow_latest,
+            fix_preview: self.fix_preview.clone(),
             is_uploading: self.is_uploading,
             is_deleting: self.is_deleting,
+            is_verifying: self.is_verifying,
             keep_config: self.keep_config.clone(),
             selected_sections: self.selected_sections.clone(),
+            filter_options: self.filter_options.clone(),
+            presets: self.presets.clone(),
+            import_message: self.import_message.clone(),
+            discovery: self.discovery.clone(),
+            discovery_key: self.discovery_key.clone(),
+            discovery_scan: None,
+            branch_checked_at: self.branch_checked_at,
+            dismissed_branch: self.dismissed_branch.clone(),
+            file_list_text: self.file_list_text.clone(),
+            use_file_list: self.use_file_list,
+            file_list_message: self.file_list_message.clone(),
+            confirm_sensitive: self.confirm_sensitive.clone(),
+            confirm_delete_docs: self.confirm_delete_docs,
+            trim: self.trim.clone(),
+            approved_plan: self.approved_plan.clone(),
+            plan_key: self.plan_key.clone(),
+            section_key: None,
+            section_scan: None,
+            section_index: None,
+            content_analysis: None,
+            run_control: None,
+            session_check: self.session_check,
+            awaiting_reauth: self.awaiting_reauth,
+            offline: self.offline,
+            reauth_curl_text: self.reauth_curl_text.clone(),
+            last_run_stopped: self.last_run_stopped,
+            replaced_files: self.replaced_files.clone(),
+            resync_section: self.resync_section.clone(),
+            running_pass: self.running_pass,
+            syncing_changes: self.syncing_changes,
+            replacing_docs: self.replacing_docs,
+            remote_docs: self.remote_docs.clone(),
+            opened_doc: self.opened_doc.clone(),
+            opened_doc_receiver: None,
+            description_receiver: None,
+            dry_run_receiver: None,
+            doc_search: self.doc_search.clone(),
+            docs_search: self.docs_search.clone(),
+            selected_docs: self.selected_docs.clone(),
+            docs_glob: self.docs_glob.clone(),
+            docs_section: self.docs_section.clone(),
+            docs_sort: self.docs_sort,
+            docs_sort_descending: self.docs_sort_descending,
+            repeat_pending: self.repeat_pending,
+            compared_runs: self.compared_runs,
+            remote_docs_receiver: None,
+            organizations: self.organizations.clone(),
+            organizations_receiver: None,
+            projects: self.projects.clone(),
+            projects_receiver: None,
             status_receiver: None,
             uploaded_files_receiver: None,
         }
     }
-
-    pub fn get_progress_percentage(&self) -> f32 {
-        match &self.progress {
-            ActionProgress::NotStarted => 0.0,
-            ActionProgress::Uploading { total, current, .. } => {
-                if *total == 0 {
-                    0.0
-                } else {
-                    (*current as f32) / (*total as f32)
-                }
-            }
-            ActionProgress::Deleting { total, current, .. } => {
-                if *total == 0 {
-                    0.0
-                } else {
-                    (*current as f32) / (*total as f32)
-                }
-            }
-            ActionProgress::Completed { total, .. } => {
-                if *total == 0 {
-                    0.0
-                } else {
-                    1.0
-                }
-            }
-        }
-    }
-
-    pub fn get_status_text(&self) -> String {
-        match &self.progress {
-            ActionProgress::NotStarted => String::new(),
-            ActionProgress::Uploading {
-                total,
-                current,
-                successful,
-                failed,
-                skipped,
-            } => {
-                format!(
-                    "Progress: {}/{} files | ✅ Success: {} | ⏩ Skipped: {} | ❌ Failed: {}",
-                    current, total, successful, skipped, failed
-                )
-            }
-            ActionProgress::Deleting {
-                total,
-                current,
-                successful,
-                failed,
-            } => {
-                format!(
-                    "Deleting: {}/{} files | ✅ Success: {} | ❌ Failed: {}",
-                    current, total, successful, failed
-                )
-            }
-            ActionProgress::Completed {
-                total,
-                successful,
-                failed,
-                skipped,
-            } => {
-                format!(
-                    "Final Status: {}/{} files | ✅ Success: {} | ⏩ Skipped: {} | ❌ Failed: {}",
-                    total, total, successful, skipped, failed
-                )
-            }
-        }
-    }
 }
@@ -0,0 +1,112 @@
+use crate::upload::DiscoveredFile;
+use std::collections::{HashMap, HashSet};
+
+/// Limits for a single run, `None` where there is no limit.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RunCap {
+    pub max_files: Option<usize>,
+    pub max_bytes: Option<u64>,
+}
+
+impl RunCap {
+    pub fn allows(&self, files: usize, bytes: u64) -> bool {
+        self.max_files.is_none_or(|max| files <= max)
+            && self.max_bytes.is_none_or(|max| bytes <= max)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrimStrategy {
+    LargestFiles,
+    LowestPrioritySections,
+}
+
+impl TrimStrategy {
+    pub const ALL: [TrimStrategy; 2] = [Self::LargestFiles, Self::LowestPrioritySections];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::LargestFiles => "Drop largest files",
+            Self::LowestPrioritySections => "Drop lowest-priority sections",
+        }
+    }
+}
+
+/// Files proposed to be left out of a run so it fits the cap. Each one can
+/// be put back before the trimmed run starts.
+#[derive(Debug, Clone)]
+pub struct TrimProposal {
+    pub strategy: TrimStrategy,
+    /// Relative paths in the order they were dropped.
+    pub proposed: Vec<String>,
+    /// The same paths, to look files up while filtering the run.
+    proposed_paths: HashSet<String>,
+    /// Proposed files the user decided to upload after all.
+    pub restored: HashSet<String>,
+}
+
+impl TrimProposal {
+    /// Drops files until the rest fits `cap`. For sections, `priorities`
+    /// holds the position of each file's first `.claudekeep` section; the
+    /// last section goes first, as a whole, and files in no section before
+    /// any of them.
+    pub fn propose(
+        files: &[DiscoveredFile],
+        cap: RunCap,
+        strategy: TrimStrategy,
+        priorities: &HashMap<String, usize>,
+    ) -> Self {
+        let priority = |file: &DiscoveredFile| {
+            priorities
+                .get(&file.relative_path)
+                .copied()
+                .unwrap_or(usize::MAX)
+        };
+        let mut candidates: Vec<&DiscoveredFile> = files.iter().collect();
+        match strategy {
+            TrimStrategy::LargestFiles => candidates.sort_by(|a, b| b.size.cmp(&a.size)),
+            TrimStrategy::LowestPrioritySections => {
+                candidates.sort_by(|a, b| (priority(b), b.size).cmp(&(priority(a), a.size)))
+            }
+        }
+
+        let mut count = files.len();
+        let mut bytes: u64 = files.iter().map(|file| file.size).sum();
+        let mut proposed = Vec::new();
+        let mut dropped_priority = None;
+        for file in candidates {
+            let fits = cap.allows(count, bytes);
+            // A section that started to go is dropped completely
+            let same_section = strategy == TrimStrategy::LowestPrioritySections
+                && dropped_priority == Some(priority(file));
+            if fits && !same_section {
+                break;
+            }
+            dropped_priority = Some(priority(file));
+            count -= 1;
+            bytes -= file.size;
+            proposed.push(file.relative_path.clone());
+        }
+
+        Self {
+            strategy,
+            proposed_paths: proposed.iter().cloned().collect(),
+            proposed,
+            restored: HashSet::new(),
+        }
+    }
+
+    pub fn drops(&self, relative_path: &str) -> bool {
+        !self.restored.contains(relative_path) && self.proposed_paths.contains(relative_path)
+    }
+
+    /// Files and bytes left in the run after trimming.
+    pub fn remaining(&self, files: &[DiscoveredFile]) -> (usize, u64) {
+        files
+            .iter()
+            .filter(|file| !self.drops(&file.relative_path))
+            .fold((0, 0), |(count, bytes), file| {
+                (count + 1, bytes + file.size)
+            })
+    }
+}
@@ -1,15 +1,37 @@
-use super::ActionProgress;
+use super::history::{FileOutcome, RunComparison, RunRecord};
+use super::state::DocSort;
+use super::trim::TrimStrategy;
 use super::ClaudeUploader;
-use crate::upload::FileProcessor;
-use crate::upload::UploadStatus;
+use crate::upload::{
+    ConflictResolution, ContentFilter, DocName, ErrorKind, FileStatus, FilterExpr, Operation,
+    RemoteDoc, SectionTag, UploadStatus, DEFAULT_PROJECT_URL, SMART_RULES,
+};
 use crate::utils::claude_keep::ClaudeKeepConfig;
+use crate::utils::curl_parser::CurlParser;
+use crate::utils::file_preview::FilePreview;
+use crate::utils::file_size::FileSizeUtils;
+use crate::utils::ignore_import::IMPORTABLE_IGNORE_FILES;
+use crate::utils::number_format::NumberFormat;
+use crate::utils::time_format::TimeFormat;
 use eframe::egui::{self, Align, Color32, RichText};
 use reqwest::header::HeaderMap;
 use rfd::FileDialog;
+use std::collections::{BTreeMap, HashSet};
 use std::path::Path;
+use std::time::Duration;
+
+const STATUS_PAGE_SIZE: usize = 500;
+const FIX_PREVIEW_LINES: usize = 20;
+/// Runs compared against the earlier ones for the failure rate trend.
+const RECENT_RUNS: usize = 5;
+const HISTORY_CHART_HEIGHT: f32 = 50.0;
 
 impl ClaudeUploader {
     pub fn render(&mut self, ctx: &egui::Context) {
+        if self.is_compact() {
+            self.render_compact(ctx);
+            return;
+        }
         egui::CentralPanel::default().show(ctx, |ui| {
             let total_height = ui.available_height();
             let footer_height = 40.0;
@@ -21,7 +43,7 @@ impl ClaudeUploader {
                 .show(ui, |ui| {
                     ui.add_space(20.0);
                     ui.vertical_centered(|ui| {
-                        ui.heading("Claude.ai File Uploader");
+                        ui.heading(super::APP_TITLE);
                         ui.add_space(3.0);
                         ui.add_space(5.0);
                         ui.label(RichText::new("Upload your files to Claude.ai projects easily")
@@ -30,40 +52,72 @@ impl ClaudeUploader {
 
                     ui.add_space(20.0);
 
-                    ui.group(|ui| {
-                        ui.horizontal(|ui| {
-                            ui.label("Paste the curl request from Claude.ai");
-                            ui.add_space(4.0);
-                            ui.label("ℹ").on_hover_text_at_pointer(
-                                "To get the curl command:\n\
-                                1. Open Developer Tools (F12)\n\
-                                2. Go to Network tab\n\
-                                3. Upload a single file manually on Claude.ai\n\
-                                4. Find the upload request (first 'docs' rq)\n\
-                                5. Right-click and Copy as cURL",
-                            );
-                        });
+                    self.render_profiles(ui);
 
-                        ui.add_space(8.0);
+                    ui.add_space(10.0);
 
-                        egui::Frame::none()
-                            .inner_margin(0.0)
-                            .show(ui, |ui| {
-                                egui::ScrollArea::vertical()
-                                    .max_height(150.0)
-                                    .show(ui, |ui| {
-                                        let text_edit = egui::TextEdit::multiline(&mut self.curl_text)
-                                            .desired_width(ui.available_width())
-                                            .font(egui::TextStyle::Monospace)
-                                            .hint_text("curl 'https://claude.ai/api/organizations/<org-id>/projects/<project-id>/docs' ...");
-
-                                        ui.add_sized(
-                                            [ui.available_width(), 150.0],
-                                            text_edit
-                                        );
-                                    });
+                    if self.uses_api_key() {
+                        self.render_api_key(ui);
+                    } else {
+                        ui.group(|ui| {
+                            ui.horizontal(|ui| {
+                                ui.label("Paste the curl request from Claude.ai");
+                                ui.add_space(4.0);
+                                ui.label("ℹ").on_hover_text_at_pointer(
+                                    "To get the curl command:\n\
+                                    1. Open Developer Tools (F12)\n\
+                                    2. Go to Network tab\n\
+                                    3. Upload a single file manually on Claude.ai\n\
+                                    4. Find the upload request (first 'docs' rq)\n\
+                                    5. Right-click and Copy as cURL",
+                                );
                             });
-                    });
+
+                            ui.add_space(8.0);
+
+                            egui::Frame::none()
+                                .inner_margin(0.0)
+                                .show(ui, |ui| {
+                                    egui::ScrollArea::vertical()
+                                        .max_height(150.0)
+                                        .show(ui, |ui| {
+                                            let text_edit = egui::TextEdit::multiline(&mut self.curl_text)
+                                                .desired_width(ui.available_width())
+                                                .font(egui::TextStyle::Monospace)
+                                                .hint_text("curl 'https://claude.ai/api/organizations/<org-id>/projects/<project-id>/docs' ...");
+
+                                            let response = ui.add_sized(
+                                                [ui.available_width(), 150.0],
+                                                text_edit
+                                            );
+                                            if response.changed() {
+                                                self.curl_changed();
+                                            }
+                                        });
+                                });
+
+                            if let Some(warning) = &self.curl_warning {
+                                ui.colored_label(Color32::from_rgb(230, 160, 30), format!("⚠ {}", warning));
+                            }
+                            self.render_session_health(ui);
+                            self.render_session_unlock(ui);
+                            if !self.curl_text.is_empty() && !self.is_profile_window() {
+                                let hover = "The session is kept encrypted between launches; \
+                                    this removes it from disk too";
+                                if ui.button("🚪 Forget session").on_hover_text(hover).clicked() {
+                                    self.forget_session();
+                                }
+                            }
+                            self.render_organization_picker(ui);
+                            self.render_project_picker(ui);
+
+                            ui.checkbox(
+                                &mut self.settings.inspection_mode,
+                                "🔍 Inspection mode (read-only)",
+                            )
+                            .on_hover_text("Only list and download project docs. Uploads and deletes are refused.");
+                        });
+                    }
 
                     ui.add_space(20.0);
 
@@ -73,122 +127,322 @@ impl ClaudeUploader {
                         ui.horizontal(|ui| {
                             if ui.button("📁 Select Folder").clicked() {
                                 if let Some(path) = FileDialog::new().pick_folder() {
-                                    self.folder_path = Some(path.display().to_string());
-
-                                    // Load .claudekeep configuration
-                                    let path = Path::new(&path);
-                                    self.state.keep_config = ClaudeKeepConfig::from_file(path);
-                                    self.state.selected_sections.clear();
+                                    self.open_folder(path);
                                 }
                             }
                             if let Some(folder) = &self.folder_path {
                                 ui.label(format!("Selected: {}", folder));
                             }
                         });
+                        if self.folder_path.is_some() {
+                            self.render_discovery_status(ui);
+                        }
+                        ui.checkbox(
+                            &mut self.filter_options_mut().case_insensitive,
+                            "Case-insensitive matching",
+                        )
+                        .on_hover_text(
+                            "Applies to .claudekeep patterns, .gitignore rules and file extensions",
+                        );
+                        self.render_filter_rules(ui);
+                        self.render_claude_keep_actions(ui);
+                        self.render_file_list(ui);
+                        self.render_approval(ui);
                     });
 
                     // Section selector with file preview
+                    let capacity = self.remaining_capacity();
+                    let selected_files = self.state.discovery.as_ref().map(|d| d.files.len());
                     if let Some(config) = &self.state.keep_config {
                         ui.add_space(10.0);
                         ui.group(|ui| {
                             ui.label(RichText::new("Select sections to upload:").strong());
                             ui.add_space(5.0);
 
-                            let processor = FileProcessor::new(
-                                self.folder_path.clone().unwrap_or_default(),
-                                String::new(),
-                                String::new(),
-                                HeaderMap::new(),
-                                Some(config.clone()),
-                                self.state.selected_sections.clone(),
-                            );
-                            let file_count = processor.count_supported_files();
-
                             for section in &config.sections {
                                 let mut selected = self.state.selected_sections.contains(section);
-                                if ui.checkbox(&mut selected, section).changed() {
+                                ui.horizontal(|ui| {
+                                    if ui.checkbox(&mut selected, section).changed() {
+                                        if selected {
+                                            self.state.selected_sections.push(section.clone());
+                                        } else {
+                                            self.state.selected_sections.retain(|s| s != section);
+                                        }
+                                    }
+
+                                    let Some(index) = &self.state.section_index else {
+                                        return;
+                                    };
+                                    let impact = index.impact(section, &self.state.selected_sections);
                                     if selected {
-                                        self.state.selected_sections.push(section.clone());
+                                        return;
+                                    }
+                                    // With nothing selected everything is uploaded, so a
+                                    // first section narrows the upload down to itself
+                                    let total_after = if self.state.selected_sections.is_empty() {
+                                        Some(impact.files)
                                     } else {
-                                        self.state.selected_sections.retain(|s| s != section);
+                                        selected_files.map(|files| files + impact.files)
+                                    };
+                                    let fits = total_after.map(|total| total <= capacity).unwrap_or(true);
+                                    let text = RichText::new(format!(
+                                        "would add ~{} files / ~{} tokens",
+                                        NumberFormat::thousands(impact.files),
+                                        NumberFormat::thousands(impact.tokens)
+                                    ))
+                                    .small();
+                                    if fits {
+                                        ui.label(text);
+                                    } else {
+                                        ui.label(text.color(ui.visuals().weak_text_color()))
+                                            .on_hover_text(format!(
+                                                "Would exceed the remaining capacity of {} documents",
+                                                NumberFormat::thousands(capacity)
+                                            ));
                                     }
-                                }
+                                });
                             }
 
                             ui.add_space(8.0);
-                            ui.label(RichText::new(format!("Files to be uploaded: {}", file_count))
+                            let file_count_text = match (&self.state.discovery, &self.state.discovery_scan) {
+                                (Some(discovery), _) => format!(
+                                    "Files to be uploaded: {}",
+                                    NumberFormat::thousands(discovery.files.len())
+                                ),
+                                (None, Some(scan)) => format!(
+                                    "Files to be uploaded: {} so far…",
+                                    NumberFormat::thousands(scan.files.len())
+                                ),
+                                (None, None) => "Files to be uploaded: unknown (scan cancelled)".to_string(),
+                            };
+                            ui.label(RichText::new(file_count_text)
                                 .color(Color32::from_rgb(100, 150, 255)));
                         });
                     }
 
+                    if self.state.discovery.is_some() {
+                        ui.add_space(10.0);
+                        self.render_preview(ui);
+                    }
+
+                    if !self.settings.inspection_mode && !self.uses_api_key() {
+                        ui.add_space(10.0);
+                        self.render_remote_files(ui);
+                    }
+
+                    ui.add_space(10.0);
+                    self.render_settings(ui);
+                    ui.add_space(10.0);
+                    self.render_history(ui);
+
                     ui.add_space(20.0);
 
-                    ui.vertical_centered(|ui| {
-                        if !matches!(self.state.progress, ActionProgress::Completed { .. }) {
-                            let can_upload = !self.curl_text.is_empty()
-                                && self.folder_path.is_some()
-                                && !self.state.is_uploading
-                                && !self.state.is_deleting;
-
-                            ui.add_enabled_ui(can_upload, |ui| {
-                                let button = egui::Button::new("📤 Upload Files")
-                                    .min_size(egui::vec2(200.0, 40.0));
-                                if ui.add(button).clicked() {
-                                    self.start_upload();
+                    if self.settings.inspection_mode {
+                        self.render_inspection_panel(ui);
+                    } else {
+                        self.render_uncommitted_warning(ui);
+                        ui.vertical_centered(|ui| {
+                            if !self.state.progress.is_completed() {
+                                let has_files = self
+                                    .state
+                                    .discovery
+                                    .as_ref()
+                                    .is_some_and(|discovery| !discovery.files.is_empty());
+                                let can_upload = self.has_credentials()
+                                    && self.folder_path.is_some()
+                                    && has_files
+                                    && !self.state.is_uploading
+                                    && !self.state.is_verifying
+                                    && !self.state.is_deleting;
+                                let over_limit = self.exceeds_doc_limit();
+                                let over_cap = self.exceeds_run_cap();
+
+                                let allowed = self.permissions().upload;
+                                let within_limits = !over_limit && !over_cap;
+
+                                ui.add_enabled_ui(can_upload && within_limits && allowed, |ui| {
+                                    let button = egui::Button::new("📤 Upload Files")
+                                        .min_size(egui::vec2(200.0, 40.0));
+                                    if ui.add(button).clicked() {
+                                        self.start_upload(None);
+                                    }
+                                    let hover = "Upload new and changed files only, \
+                                        and delete the docs of removed ones";
+                                    let sync = ui.button("🔄 Sync changes").on_hover_text(hover);
+                                    if sync.clicked() {
+                                        self.start_sync();
+                                    }
+                                });
+                                let dry_running = self.state.dry_run_receiver.is_some();
+                                let can_dry_run = self.folder_path.is_some()
+                                    && !self.state.use_file_list
+                                    && !self.state.is_uploading
+                                    && !dry_running;
+                                ui.add_enabled_ui(can_dry_run, |ui| {
+                                    let label = if dry_running {
+                                        "🔍 Walking folder…"
+                                    } else {
+                                        "🔍 Dry run"
+                                    };
+                                    let hover = "Show what an upload would do with each file";
+                                    if ui.button(label).on_hover_text(hover).clicked()
+                                    {
+                                        self.start_dry_run();
+                                    }
+                                });
+                                if !allowed {
+                                    ui.label(RichText::new("Uploading is disabled for this profile").small());
                                 }
-                            });
-                        } else {
-                            let can_delete = !self.state.is_uploading && !self.state.is_deleting;
-                            let can_upload = !self.curl_text.is_empty() && self.folder_path.is_some();
 
-                            ui.add_enabled_ui(can_delete && can_upload, |ui| {
-                                if ui.button("🔄 Delete & Reupload").clicked() {
-                                    self.delete_and_reupload();
+                                if over_limit {
+                                    ui.add_space(8.0);
+                                    self.render_doc_limit_warning(ui, can_upload);
                                 }
-                            });
+                                if self.pass_plan().is_some() {
+                                    ui.add_space(8.0);
+                                    self.render_pass_plan(ui, can_upload && allowed);
+                                }
+                                if over_cap {
+                                    ui.add_space(8.0);
+                                    self.render_trim_panel(ui, can_upload && allowed);
+                                }
+                            } else {
+                                let can_delete = !self.state.is_uploading
+                                    && !self.state.is_verifying
+                                    && !self.state.is_deleting
+                                    && self.state.replaced_files.is_none();
+                                let can_upload = !self.curl_text.is_empty() && self.folder_path.is_some();
 
-                            ui.add_space(5.0);
-                            if ui.button("🗑 Clear All").clicked() {
-                                self.reset_upload_state();
+                                let permissions = self.permissions();
+                                let allowed = permissions.upload && permissions.delete;
+
+                                ui.add_enabled_ui(can_delete && can_upload && allowed, |ui| {
+                                    if ui.button("🔄 Delete & Reupload").clicked() {
+                                        self.delete_and_reupload();
+                                    }
+                                });
+                                if !allowed {
+                                    ui.label(RichText::new("Delete & Reupload is disabled for this profile").small());
+                                }
+
+                                let synced = !self.state.progress.has_failures()
+                                    && !self.state.uploaded_files.is_empty();
+                                if let Some(url) = self.project_chat_url().filter(|_| synced) {
+                                    ui.add_space(5.0);
+                                    ui.horizontal(|ui| {
+                                        if ui.button("💬 Ask Claude about this project").clicked() {
+                                            if let Err(e) = open::that(&url) {
+                                                println!("Failed to open {}: {}", url, e);
+                                            }
+                                        }
+                                        if ui
+                                            .small_button("📋 Copy prompt")
+                                            .on_hover_text("Copy a first message about the synced files")
+                                            .clicked()
+                                        {
+                                            let prompt = self.chat_prompt();
+                                            ui.output_mut(|output| output.copied_text = prompt);
+                                        }
+                                    });
+                                }
+
+                                ui.add_space(5.0);
+                                if ui.button("🗑 Clear All").clicked() {
+                                    self.reset_upload_state();
+                                }
                             }
-                        }
-                    });
+                        });
+                    }
 
                     ui.add_space(20.0);
 
-                    if !matches!(self.state.progress, ActionProgress::NotStarted) {
+                    if self.state.progress.is_started() {
                         ui.group(|ui| {
-                            if let Some(current_file) = &self.state.current_file {
-                                let status_text = match &self.state.progress {
-                                    ActionProgress::Completed { failed, .. } => {
-                                        if *failed > 0 {
-                                            "Upload Failed"
-                                        } else {
-                                            "Upload Complete"
-                                        }
-                                    }
-                                    _ => {
-                                        if self.state.is_deleting {
-                                            "🗑 Deleting"
-                                        } else {
-                                            "📤 Uploading"
-                                        }
+                            if let Some((attempt, max_attempts)) = self.state.session_check {
+                                ui.horizontal(|ui| {
+                                    ui.spinner();
+                                    ui.label(if attempt > 1 {
+                                        format!("🔐 Checking session (attempt {}/{})…", attempt, max_attempts)
+                                    } else {
+                                        "🔐 Checking session…".to_string()
+                                    });
+                                });
+                            } else if let Some(current_file) = &self.state.current_file {
+                                let status_text = if self.state.is_verifying {
+                                    "✔ Verifying"
+                                } else if self.state.replaced_files.is_some() {
+                                    // Also covers the frames between two phases
+                                    "🔄 Reuploading"
+                                } else if self.state.progress.is_completed() {
+                                    if self.state.progress.is_cancelled() {
+                                        "Upload Cancelled"
+                                    } else if self.state.progress.has_failures() {
+                                        "Upload Failed"
+                                    } else {
+                                        "Upload Complete"
                                     }
+                                } else if self.state.is_deleting {
+                                    "🗑 Deleting"
+                                } else {
+                                    "📤 Uploading"
                                 };
                                 ui.label(format!("{}: {}", status_text, current_file));
                             }
+                            if ui
+                                .small_button("🗕 Compact")
+                                .on_hover_text("Shrink to a progress strip that stays on top")
+                                .clicked()
+                            {
+                                self.set_compact(ui.ctx(), true);
+                            }
+                            if self.state.is_uploading && self.state.run_control.is_some() {
+                                let cancelling = self.state.progress.is_cancelled();
+                                ui.add_enabled_ui(!cancelling, |ui| {
+                                    let label = if cancelling {
+                                        "Cancelling…"
+                                    } else {
+                                        "✖ Cancel"
+                                    };
+                                    let hover = "Abort the upload in progress and skip the rest";
+                                    if ui.button(label).on_hover_text(hover).clicked()
+                                    {
+                                        self.cancel_run();
+                                    }
+                                });
+                            }
 
-                            let progress = self.state.get_progress_percentage();
+                            let progress = self.state.progress.overall_fraction();
                             let progress_bar = egui::ProgressBar::new(progress)
                                 .show_percentage()
                                 .animate(false)
                                 .fill(Color32::from_rgb(161, 89, 225));
                             ui.add(progress_bar);
 
-                            ui.label(self.state.get_status_text());
+                            self.render_phases(ui);
+                            if let Some(elapsed) = self.state.progress.elapsed() {
+                                ui.label(
+                                    RichText::new(format!(
+                                        "⏱ Elapsed: {}",
+                                        TimeFormat::duration(elapsed)
+                                    ))
+                                    .small(),
+                                );
+                            }
                         });
                     }
 
+                    if self.state.awaiting_reauth {
+                        self.render_reauth_panel(ui);
+                    }
+
+                    if self.state.offline {
+                        self.render_offline_panel(ui);
+                    }
+
+                    if !self.state.is_uploading && !self.state.is_deleting {
+                        self.render_failure_summary(ui);
+                    }
+
                     if !self.state.file_statuses.is_empty() {
                         ui.add_space(10.0);
                         self.render_details(ui);
@@ -204,70 +458,2776 @@ impl ClaudeUploader {
         });
     }
 
-    fn render_details(&mut self, ui: &mut egui::Ui) {
-        if ui
-            .button(if self.state.show_details {
-                "Hide Details"
+    /// The whole window in compact mode: progress bar, counts, cancel.
+    fn render_compact(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui
+                    .small_button("⤢")
+                    .on_hover_text("Back to the full window")
+                    .clicked()
+                {
+                    self.set_compact(ui.ctx(), false);
+                }
+                let cancelling = self.state.progress.is_cancelled();
+                let running = self.state.is_uploading && self.state.run_control.is_some();
+                ui.add_enabled_ui(running && !cancelling, |ui| {
+                    if ui
+                        .small_button("✖")
+                        .on_hover_text("Cancel the upload")
+                        .clicked()
+                    {
+                        self.cancel_run();
+                    }
+                });
+                ui.add(
+                    egui::ProgressBar::new(self.state.progress.overall_fraction())
+                        .show_percentage()
+                        .animate(false)
+                        .fill(Color32::from_rgb(161, 89, 225)),
+                );
+            });
+
+            let text = match self.state.progress.last_started() {
+                Some(phase) => format!(
+                    "{} {}/{} | ✅ {} | ⏩ {} | ❌ {}",
+                    phase.phase.label(),
+                    phase.reached(),
+                    phase.total,
+                    phase.successful,
+                    phase.skipped,
+                    phase.failed
+                ),
+                None => "Nothing running".to_string(),
+            };
+            let text = if self.state.progress.is_cancelled() {
+                format!("{} | Cancelled", text)
+            } else if self.state.progress.is_completed() {
+                format!("{} | Done", text)
             } else {
-                "Show Details"
-            })
-            .clicked()
-        {
-            self.state.show_details = !self.state.show_details;
+                text
+            };
+            ui.label(RichText::new(text).small());
+        });
+    }
+
+    fn render_phases(&self, ui: &mut egui::Ui) {
+        let phases = self.state.progress.phases();
+        if phases.len() == 1 {
+            ui.label(phases[0].status_text());
+            return;
         }
 
-        if self.state.show_details {
-            egui::ScrollArea::vertical()
-                .max_height(200.0)
-                .show(ui, |ui| {
-                    egui::Frame::none()
-                        .fill(ui.style().visuals.extreme_bg_color)
-                        .show(ui, |ui| {
-                            ui.add_space(8.0);
-                            for status in &self.state.file_statuses {
-                                match &status.status {
-                                    UploadStatus::Processing => {
-                                        ui.horizontal(|ui| {
-                                            ui.label("⏳");
-                                            ui.colored_label(
-                                                Color32::from_rgb(150, 150, 150),
-                                                &format!("{} - Processing...", status.name),
-                                            );
-                                        });
-                                    }
-                                    UploadStatus::Success => {
-                                        ui.horizontal(|ui| {
-                                            ui.label("✅");
-                                            ui.colored_label(
-                                                Color32::from_rgb(0, 180, 0),
-                                                &status.name,
-                                            );
-                                        });
-                                    }
-                                    UploadStatus::Error(err) => {
-                                        ui.horizontal(|ui| {
-                                            ui.label("❌");
-                                            ui.colored_label(
-                                                Color32::from_rgb(220, 50, 50),
-                                                &format!("{} - {}", status.name, err),
-                                            );
-                                        });
-                                    }
-                                    UploadStatus::Skipped(reason) => {
-                                        ui.horizontal(|ui| {
-                                            ui.label("⏩");
-                                            ui.colored_label(
-                                                Color32::from_rgb(150, 150, 150),
-                                                &format!("{} - {}", status.name, reason),
-                                            );
-                                        });
-                                    }
+        for phase in phases {
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::ProgressBar::new(phase.fraction())
+                        .desired_width(80.0)
+                        .animate(false),
+                );
+                let text = RichText::new(phase.status_text()).small();
+                if phase.started {
+                    ui.label(text);
+                } else {
+                    ui.label(text.color(ui.visuals().weak_text_color()));
+                }
+            });
+        }
+    }
+
+    fn render_claude_keep_actions(&mut self, ui: &mut egui::Ui) {
+        let Some(folder_path) = self.folder_path.clone() else {
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            if self.state.keep_config.is_none() {
+                if ui
+                    .button("📝 Generate .claudekeep")
+                    .on_hover_text("Writes a starter file with sections for the folder layout")
+                    .clicked()
+                {
+                    self.generate_claude_keep();
+                }
+                return;
+            }
+
+            if ui.button("📝 Edit .claudekeep").clicked() {
+                if let Err(e) = open::that(Path::new(&folder_path).join(".claudekeep")) {
+                    self.state.error_message = Some(format!("Could not open .claudekeep: {}", e));
+                }
+            }
+            if ui
+                .button("🔄 Reload")
+                .on_hover_text("Picks up changes made to .claudekeep")
+                .clicked()
+            {
+                self.reload_claude_keep();
+            }
+        });
+    }
+
+    fn render_filter_rules(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Filter rules")
+            .id_source("filter_rules")
+            .show(ui, |ui| {
+                if let Some(profile) = self.profiles.active() {
+                    let mut per_profile = profile.filter_options.is_some();
+                    let label = format!("Use separate rules for profile '{}'", profile.name);
+                    if ui
+                        .checkbox(&mut per_profile, label)
+                        .on_hover_text("Otherwise the global rules apply")
+                        .changed()
+                    {
+                        self.set_profile_filters(per_profile);
+                    }
+                }
+
+                if !self.state.presets.is_empty() {
+                    let presets = self.state.presets.clone();
+                    ui.horizontal(|ui| {
+                        ui.label("Presets");
+                        for preset in presets {
+                            if ui
+                                .small_button(preset.name)
+                                .on_hover_text("Replaces the rules below with the preset's")
+                                .clicked()
+                            {
+                                preset.apply(self.filter_options_mut());
+                            }
+                        }
+                    });
+                }
+
+                let matched = self.state.discovery.as_ref().map(|d| d.files.len());
+                let options = self.filter_options_mut();
+                ui.horizontal(|ui| {
+                    ui.label("Also upload");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut options.extra_extensions)
+                            .hint_text("proto, sql")
+                            .desired_width(150.0),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Never upload");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut options.excluded_extensions)
+                            .hint_text("json, lock")
+                            .desired_width(150.0),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    let mut limit_depth = options.max_depth.is_some();
+                    if ui
+                        .checkbox(&mut limit_depth, "Limit depth")
+                        .on_hover_text("Only walk this many directory levels below the folder")
+                        .changed()
+                    {
+                        options.max_depth = limit_depth.then_some(2);
+                    }
+                    if let Some(depth) = options.max_depth.as_mut() {
+                        ui.add(
+                            egui::DragValue::new(depth)
+                                .clamp_range(1..=32)
+                                .suffix(" levels"),
+                        );
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Skip files under");
+                    ui.add(
+                        egui::DragValue::new(&mut options.min_content.min_bytes)
+                            .clamp_range(0..=100_000)
+                            .suffix(" bytes"),
+                    );
+                    ui.label("or");
+                    ui.add(
+                        egui::DragValue::new(&mut options.min_content.min_chars)
+                            .clamp_range(0..=10_000)
+                            .suffix(" characters"),
+                    )
+                    .on_hover_text("Whitespace is not counted; 0 turns the check off");
+                });
+                ui.checkbox(&mut options.include_vendored, "Include vendored code")
+                    .on_hover_text("Vendor directories, minified bundles and bundled libraries");
+                if !options.confirmed_sensitive.is_empty() {
+                    ui.horizontal(|ui| {
+                        ui.colored_label(
+                            Color32::from_rgb(230, 160, 30),
+                            format!(
+                                "🔓 {} sensitive files confirmed for upload",
+                                options.confirmed_sensitive.len()
+                            ),
+                        )
+                        .on_hover_text(options.confirmed_sensitive.join("\n"));
+                        if ui.small_button("Clear").clicked() {
+                            println!(
+                                "Cleared {} sensitive file confirmations",
+                                options.confirmed_sensitive.len()
+                            );
+                            options.confirmed_sensitive.clear();
+                        }
+                    });
+                }
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("Skip");
+                    for rule in &SMART_RULES {
+                        let mut enabled = rule.is_enabled(options);
+                        if ui
+                            .checkbox(&mut enabled, rule.name)
+                            .on_hover_text(rule.description)
+                            .changed()
+                        {
+                            rule.set_enabled(options, enabled);
+                        }
+                    }
+                });
+                ui.label("Only upload files matching, one pattern per line");
+                ui.add(
+                    egui::TextEdit::multiline(&mut options.include_rules)
+                        .hint_text("/src/\n/Cargo.toml")
+                        .font(egui::TextStyle::Monospace)
+                        .desired_rows(2),
+                );
+                ui.label("Ignore rules, one pattern per line");
+                ui.add(
+                    egui::TextEdit::multiline(&mut options.ignore_rules)
+                        .hint_text("tests/**\n*.min.js")
+                        .font(egui::TextStyle::Monospace)
+                        .desired_rows(3),
+                );
+                ui.label("Filter expression").on_hover_text(
+                    "Fields: ext, name, path, size\n\
+                     Operators: = != < <= > >= ~ in\n\
+                     Combine with and, or, not",
+                );
+                ui.add(
+                    egui::TextEdit::singleline(&mut options.expression)
+                        .hint_text("ext in [rs, toml] and size < 200kb and not path ~ \"tests/\"")
+                        .font(egui::TextStyle::Monospace)
+                        .desired_width(f32::INFINITY),
+                );
+                if !options.expression.trim().is_empty() {
+                    match FilterExpr::parse(&options.expression) {
+                        Ok(_) => {
+                            let text = match matched {
+                                Some(count) => {
+                                    format!("✔ {} files match", NumberFormat::thousands(count))
+                                }
+                                None => "✔ Valid".to_string(),
+                            };
+                            ui.label(RichText::new(text).small());
+                        }
+                        Err(error) => {
+                            let error = format!("⚠ {}", error);
+                            ui.colored_label(Color32::from_rgb(220, 50, 50), error);
+                        }
+                    }
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Content must match");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut options.content_include)
+                            .hint_text("TODO|pub fn")
+                            .font(egui::TextStyle::Monospace)
+                            .desired_width(200.0),
+                    )
+                    .on_hover_text("A regular expression searched in each file's content");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Content must not match");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut options.content_exclude)
+                            .hint_text("DO NOT UPLOAD")
+                            .font(egui::TextStyle::Monospace)
+                            .desired_width(200.0),
+                    );
+                });
+                for pattern in [&options.content_include, &options.content_exclude] {
+                    if let Some(error) = ContentFilter::check(pattern) {
+                        let error = format!("⚠ Invalid regex: {}", error);
+                        ui.colored_label(Color32::from_rgb(220, 50, 50), error);
+                    }
+                }
+
+                // Offer the ignore files other tools already keep in the folder
+                let Some(folder_path) = self.folder_path.clone() else {
+                    return;
+                };
+                ui.horizontal(|ui| {
+                    for (file_name, root_relative) in IMPORTABLE_IGNORE_FILES {
+                        if !Path::new(&folder_path).join(file_name).is_file() {
+                            continue;
+                        }
+                        if ui
+                            .small_button(format!("Import {}", file_name))
+                            .on_hover_text("Adds its patterns to the ignore rules")
+                            .clicked()
+                        {
+                            self.import_ignore_file(file_name, root_relative);
+                        }
+                    }
+                });
+                if let Some(message) = &self.state.import_message {
+                    ui.label(
+                        RichText::new(message)
+                            .small()
+                            .color(ui.visuals().weak_text_color()),
+                    );
+                }
+            });
+    }
+
+    fn render_session_unlock(&mut self, ui: &mut egui::Ui) {
+        let Some(unlock) = &mut self.session_unlock else {
+            return;
+        };
+        let prompt = if unlock.existing {
+            "Enter the passphrase of the sessions saved on this computer"
+        } else {
+            "No OS keychain found. Choose a passphrase to keep sessions between launches"
+        };
+
+        let mut submit = false;
+        let mut skip = false;
+        ui.group(|ui| {
+            ui.label(prompt);
+            ui.horizontal(|ui| {
+                let field = ui.add(
+                    egui::TextEdit::singleline(&mut unlock.passphrase)
+                        .password(true)
+                        .desired_width(200.0),
+                );
+                let entered = field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                let label = if unlock.existing {
+                    "🔓 Unlock"
+                } else {
+                    "🔒 Keep sessions"
+                };
+                submit = ui.button(label).clicked() || entered;
+                skip = ui
+                    .button("Not now")
+                    .on_hover_text("Sessions are kept in memory only until the app closes")
+                    .clicked();
+            });
+            if let Some(error) = &unlock.error {
+                ui.colored_label(Color32::from_rgb(220, 50, 50), error);
+            }
+        });
+
+        if submit {
+            self.unlock_sessions();
+        } else if skip {
+            self.skip_session_unlock();
+        }
+    }
+
+    fn render_session_health(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let checking = self.session_health.check_receiver.is_some();
+            ui.add_enabled_ui(!self.curl_text.is_empty() && !checking, |ui| {
+                if ui
+                    .small_button("🔐 Check session")
+                    .on_hover_text("Sends a read-only request to see if the session still works")
+                    .clicked()
+                {
+                    self.check_session_health();
+                }
+            });
+            if checking {
+                ui.spinner();
+            }
+
+            let health = &self.session_health;
+            if health.is_expired() {
+                ui.colored_label(
+                    Color32::from_rgb(220, 50, 50),
+                    "Session expired, paste a fresh curl command",
+                );
+                return;
+            }
+
+            if let Some(remaining) = health.remaining() {
+                if health.is_expiring() {
+                    ui.colored_label(
+                        Color32::from_rgb(230, 160, 30),
+                        format!(
+                            "Session expires in ~{}, refresh it before a long run",
+                            TimeFormat::duration(remaining)
+                        ),
+                    );
+                } else {
+                    ui.colored_label(
+                        Color32::from_rgb(0, 180, 0),
+                        format!(
+                            "Session likely valid for ~{}",
+                            TimeFormat::duration(remaining)
+                        ),
+                    );
+                }
+            }
+            if let Some((checked_at, true)) = health.last_check {
+                ui.label(
+                    RichText::new(format!("verified {}", TimeFormat::ago(checked_at)))
+                        .small()
+                        .color(ui.visuals().weak_text_color()),
+                );
+            }
+        });
+    }
+
+    fn render_profiles(&mut self, ui: &mut egui::Ui) {
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.label("Profile");
+
+                let selected_text = self
+                    .profiles
+                    .active()
+                    .map(|profile| profile.name.clone())
+                    .unwrap_or_else(|| "None".to_string());
+                let mut selected = None;
+                // A profile window stays bound to the profile it was opened for
+                ui.add_enabled_ui(!self.is_profile_window(), |ui| {
+                    egui::ComboBox::from_id_source("profile")
+                        .selected_text(selected_text)
+                        .show_ui(ui, |ui| {
+                            for (index, profile) in self.profiles.profiles.iter().enumerate() {
+                                let is_active = self.profiles.active == Some(index);
+                                if ui.selectable_label(is_active, &profile.name).clicked() {
+                                    selected = Some(index);
                                 }
-                                ui.add_space(4.0);
                             }
-                            ui.add_space(8.0);
                         });
                 });
+                if let Some(index) = selected {
+                    self.activate_profile(index);
+                }
+
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.profile_name)
+                        .desired_width(120.0)
+                        .hint_text("Profile name"),
+                );
+                ui.add_enabled_ui(!self.profile_name.trim().is_empty(), |ui| {
+                    if ui
+                        .button("💾 Save")
+                        .on_hover_text("Save the selected folder under this name")
+                        .clicked()
+                    {
+                        self.save_profile();
+                    }
+                });
+                if self.profiles.active.is_some() && ui.button("Remove").clicked() {
+                    self.remove_profile();
+                }
+                if self.profiles.active.is_some()
+                    && !self.is_profile_window()
+                    && ui
+                        .button("🗗 New window")
+                        .on_hover_text("Open this profile in its own window to sync two at once")
+                        .clicked()
+                {
+                    self.open_profile_window();
+                }
+            });
+
+            if let Some(profile) = self.profiles.active_mut() {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut profile.allow_upload, "Allow upload");
+                    ui.checkbox(&mut profile.allow_delete, "Allow delete")
+                        .on_hover_text(
+                        "Turn off for shared profiles that must never remove docs from the project",
+                    );
+                    ui.label("Branch");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut profile.branch_pattern)
+                            .desired_width(120.0)
+                            .hint_text("e.g. feature/*"),
+                    )
+                    .on_hover_text("Suggest this profile when the folder is on a matching branch");
+                    ui.checkbox(&mut profile.use_api_key, "Use API key")
+                        .on_hover_text("Upload to the Anthropic Files API instead of a project");
+                });
+            }
+
+            if let Some((index, branch)) = self.branch_suggestion() {
+                let name = self.profiles.profiles[index].name.clone();
+                ui.horizontal(|ui| {
+                    ui.label(format!("🌿 Branch '{}' matches profile '{}'", branch, name));
+                    if ui.button("Switch").clicked() {
+                        self.activate_profile(index);
+                    }
+                    if ui.small_button("Dismiss").clicked() {
+                        self.state.dismissed_branch = Some(branch);
+                    }
+                });
+            }
+        });
+    }
+
+    /// Organization to send the session to, for accounts in several of them.
+    /// Defaults to the organization of the pasted curl.
+    fn render_organization_picker(&mut self, ui: &mut egui::Ui) {
+        if self.curl_text.trim().is_empty() {
+            return;
+        }
+        let current = self.curl_parser.organization_id.clone();
+        let mut picked = None;
+        ui.horizontal(|ui| {
+            ui.label("Organization");
+            let current_name = match (&self.state.organizations, &current) {
+                (Some(organizations), Some(id)) => organizations
+                    .iter()
+                    .find(|organization| &organization.uuid == id)
+                    .map(|organization| organization.name.clone())
+                    .unwrap_or_else(|| id.clone()),
+                (None, Some(id)) => id.clone(),
+                (_, None) => "From the curl command".to_string(),
+            };
+            let running = self.state.is_uploading || self.state.is_deleting;
+            ui.add_enabled_ui(self.state.organizations.is_some() && !running, |ui| {
+                egui::ComboBox::from_id_source("organization_picker")
+                    .selected_text(current_name)
+                    .width(250.0)
+                    .show_ui(ui, |ui| {
+                        for organization in self.state.organizations.iter().flatten() {
+                            let selected = current.as_deref() == Some(organization.uuid.as_str());
+                            if ui.selectable_label(selected, &organization.name).clicked() {
+                                picked = Some(organization.uuid.clone());
+                            }
+                        }
+                    });
+            });
+            let listing = self.state.organizations_receiver.is_some();
+            ui.add_enabled_ui(!listing && !running, |ui| {
+                if ui
+                    .small_button("🔄")
+                    .on_hover_text("List the organizations of this account")
+                    .clicked()
+                {
+                    self.list_organizations();
+                }
+            });
+            if listing {
+                ui.spinner();
+            }
+        });
+        if let Some(organization_id) = picked {
+            self.select_organization(&organization_id);
+        }
+    }
+
+    /// Destination project, chosen among the organization's projects once
+    /// they are listed. Defaults to the project of the pasted curl.
+    fn render_project_picker(&mut self, ui: &mut egui::Ui) {
+        if self.curl_text.trim().is_empty() {
+            return;
+        }
+        let current = self.curl_parser.project_id.clone();
+        let mut picked = None;
+        ui.horizontal(|ui| {
+            ui.label("Project");
+            let current_name = match (&self.state.projects, &current) {
+                (Some(projects), Some(id)) => projects
+                    .iter()
+                    .find(|project| &project.uuid == id)
+                    .map(|project| project.name.clone())
+                    .unwrap_or_else(|| id.clone()),
+                (None, Some(id)) => id.clone(),
+                (_, None) => "From the curl command".to_string(),
+            };
+            let running = self.state.is_uploading || self.state.is_deleting;
+            ui.add_enabled_ui(self.state.projects.is_some() && !running, |ui| {
+                egui::ComboBox::from_id_source("project_picker")
+                    .selected_text(current_name)
+                    .width(250.0)
+                    .show_ui(ui, |ui| {
+                        for project in self.state.projects.iter().flatten() {
+                            let selected = current.as_deref() == Some(project.uuid.as_str());
+                            if ui.selectable_label(selected, &project.name).clicked() {
+                                picked = Some(project.uuid.clone());
+                            }
+                        }
+                    });
+            });
+            let listing = self.state.projects_receiver.is_some();
+            ui.add_enabled_ui(!listing && !running, |ui| {
+                if ui
+                    .small_button("🔄")
+                    .on_hover_text("List the projects of this organization")
+                    .clicked()
+                {
+                    self.list_projects();
+                }
+            });
+            if listing {
+                ui.spinner();
+            }
+        });
+        let foreign = match (&self.state.projects, &current) {
+            (Some(projects), Some(id)) => !projects.iter().any(|project| &project.uuid == id),
+            _ => false,
+        };
+        if foreign {
+            ui.colored_label(
+                Color32::from_rgb(230, 160, 30),
+                "⚠ The project is not in this organization, pick one of its projects",
+            );
+        }
+        if let Some(project_id) = picked {
+            self.select_project(&project_id);
+        }
+    }
+
+    fn render_api_key(&mut self, ui: &mut egui::Ui) {
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.label("Anthropic API key");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.api_key)
+                        .password(true)
+                        .desired_width(300.0)
+                        .hint_text("sk-ant-..."),
+                );
+            });
+            ui.label(
+                RichText::new(
+                    "Files go to the Files API of the key's workspace, not to a claude.ai project",
+                )
+                .small()
+                .color(ui.visuals().text_color().gamma_multiply(0.7)),
+            );
+        });
+    }
+
+    fn render_inspection_panel(&mut self, ui: &mut egui::Ui) {
+        ui.group(|ui| {
+            ui.label(RichText::new("🔍 Project docs").strong());
+            ui.label(
+                RichText::new("Inspection mode: nothing in the project will be modified")
+                    .color(ui.visuals().text_color().gamma_multiply(0.7)),
+            );
+            ui.add_space(5.0);
+
+            ui.horizontal(|ui| {
+                let listing = self.state.remote_docs_receiver.is_some();
+                ui.add_enabled_ui(!self.curl_text.is_empty() && !listing, |ui| {
+                    if ui.button("📋 List docs").clicked() {
+                        self.list_remote_docs();
+                    }
+                });
+                if listing {
+                    ui.spinner();
+                }
+
+                let has_docs = self
+                    .state
+                    .remote_docs
+                    .as_ref()
+                    .map(|docs| !docs.is_empty())
+                    .unwrap_or(false);
+                ui.add_enabled_ui(has_docs, |ui| {
+                    if ui.button("💾 Download all").clicked() {
+                        if let Some(dir) = FileDialog::new().pick_folder() {
+                            self.download_remote_docs(&dir, false);
+                        }
+                    }
+                });
+            });
+
+            if self.state.remote_docs.is_none() {
+                return;
+            }
+
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                ui.label("🔎");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.state.docs_search)
+                        .hint_text("Search all docs")
+                        .desired_width(200.0),
+                );
+            });
+
+            let Some(docs) = &self.state.remote_docs else {
+                return;
+            };
+            let mut sections: Vec<&str> = docs
+                .iter()
+                .filter_map(|doc| SectionTag::parse(&doc.file_name))
+                .collect();
+            sections.sort_unstable();
+            sections.dedup();
+            let mut resync = None;
+            if !sections.is_empty() {
+                let permissions = self.permissions();
+                let can_resync = permissions.upload
+                    && permissions.delete
+                    && self.folder_path.is_some()
+                    && !self.is_busy();
+                ui.horizontal(|ui| {
+                    ui.label("Section");
+                    egui::ComboBox::from_id_source("docs_section")
+                        .selected_text(self.state.docs_section.as_deref().unwrap_or("All"))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.state.docs_section, None, "All");
+                            for section in &sections {
+                                ui.selectable_value(
+                                    &mut self.state.docs_section,
+                                    Some(section.to_string()),
+                                    *section,
+                                );
+                            }
+                        });
+                    if let Some(section) = &self.state.docs_section {
+                        ui.add_enabled_ui(can_resync, |ui| {
+                            if ui
+                                .button("🔄 Re-sync section")
+                                .on_hover_text("Replace this section's docs with its local files")
+                                .on_disabled_hover_text("Needs upload and delete permission")
+                                .clicked()
+                            {
+                                resync = Some(section.clone());
+                            }
+                        });
+                    }
+                });
+            }
+            let section = self.state.docs_section.clone();
+            let query = self.state.docs_search.trim().to_lowercase();
+            let mut shown: Vec<(&RemoteDoc, Vec<&str>)> = docs
+                .iter()
+                .filter(|doc| {
+                    section.is_none() || SectionTag::parse(&doc.file_name) == section.as_deref()
+                })
+                .filter_map(|doc| {
+                    if query.is_empty() {
+                        return Some((doc, Vec::new()));
+                    }
+                    let lines: Vec<&str> = doc
+                        .content
+                        .as_deref()
+                        .unwrap_or_default()
+                        .lines()
+                        .filter(|line| line.to_lowercase().contains(&query))
+                        .collect();
+                    let name_matches = doc.file_name.to_lowercase().contains(&query);
+                    (name_matches || !lines.is_empty()).then_some((doc, lines))
+                })
+                .collect();
+
+            let descending = self.state.docs_sort_descending;
+            shown.sort_by(|(a, _), (b, _)| {
+                let ordering = match self.state.docs_sort {
+                    DocSort::Name => a.file_name.to_lowercase().cmp(&b.file_name.to_lowercase()),
+                    DocSort::Size => a.size().cmp(&b.size()),
+                    DocSort::Created => a.created_at.cmp(&b.created_at),
+                };
+                if descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Sort by");
+                egui::ComboBox::from_id_source("docs_sort")
+                    .selected_text(self.state.docs_sort.label())
+                    .show_ui(ui, |ui| {
+                        for sort in DocSort::ALL {
+                            ui.selectable_value(&mut self.state.docs_sort, sort, sort.label());
+                        }
+                    });
+                let direction = if descending { "⬇" } else { "⬆" };
+                if ui
+                    .small_button(direction)
+                    .on_hover_text("Toggle ascending / descending")
+                    .clicked()
+                {
+                    self.state.docs_sort_descending = !descending;
+                }
+            });
+
+            if query.is_empty() && section.is_none() {
+                ui.label(format!("{} docs", NumberFormat::thousands(docs.len())));
+            } else {
+                ui.label(format!(
+                    "{} of {} docs match",
+                    NumberFormat::thousands(shown.len()),
+                    NumberFormat::thousands(docs.len())
+                ));
+                let unlisted = docs.iter().filter(|doc| doc.content.is_none()).count();
+                if unlisted > 0 {
+                    ui.label(
+                        RichText::new(format!(
+                            "{} docs came without content and were only searched by name; open them to search their content",
+                            NumberFormat::thousands(unlisted)
+                        ))
+                        .small()
+                        .color(ui.visuals().weak_text_color()),
+                    );
+                }
+            }
+
+            // Selection operations act on the docs currently shown
+            let can_delete = self.permissions().delete && !self.state.is_deleting;
+            let case_insensitive = self.filter_options().case_insensitive;
+            let selection = &mut self.state.selected_docs;
+            ui.horizontal(|ui| {
+                if ui.small_button("Select all").clicked() {
+                    selection.extend(shown.iter().map(|(doc, _)| doc.uuid.clone()));
+                }
+                if ui.small_button("Select none").clicked() {
+                    selection.clear();
+                }
+                if ui.small_button("Invert").clicked() {
+                    for (doc, _) in &shown {
+                        if !selection.remove(&doc.uuid) {
+                            selection.insert(doc.uuid.clone());
+                        }
+                    }
+                }
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.state.docs_glob)
+                        .hint_text("*.md")
+                        .desired_width(100.0),
+                );
+                if ui
+                    .small_button("Select matching")
+                    .on_hover_text("Adds the docs whose name matches the pattern, using .claudekeep syntax")
+                    .clicked()
+                {
+                    selection.extend(
+                        shown
+                            .iter()
+                            .filter(|(doc, _)| {
+                                ClaudeKeepConfig::pattern_matches(
+                                    &self.state.docs_glob,
+                                    Path::new(&doc.file_name),
+                                    case_insensitive,
+                                )
+                            })
+                            .map(|(doc, _)| doc.uuid.clone()),
+                    );
+                }
+            });
+
+            let selected = selection.len();
+            let mut download_selected = false;
+            let mut delete_selected = false;
+            ui.horizontal(|ui| {
+                ui.label(format!("{} selected", NumberFormat::thousands(selected)));
+                ui.add_enabled_ui(selected > 0, |ui| {
+                    download_selected = ui.button("💾 Download selected").clicked();
+                });
+                ui.add_enabled_ui(selected > 0 && can_delete, |ui| {
+                    delete_selected = ui
+                        .button("🗑 Delete selected")
+                        .on_disabled_hover_text("Deleting is not permitted in inspection mode")
+                        .clicked();
+                });
+            });
+
+            let opened_uuid = self.state.opened_doc.as_ref().map(|doc| doc.uuid.as_str());
+            let mut clicked = None;
+            egui::ScrollArea::vertical()
+                .id_source("remote_docs")
+                .max_height(200.0)
+                .show(ui, |ui| {
+                    for (doc, lines) in &shown {
+                        ui.horizontal(|ui| {
+                            let mut is_selected = selection.contains(&doc.uuid);
+                            if ui.checkbox(&mut is_selected, "").changed() {
+                                if is_selected {
+                                    selection.insert(doc.uuid.clone());
+                                } else {
+                                    selection.remove(&doc.uuid);
+                                }
+                            }
+                            let is_open = opened_uuid == Some(doc.uuid.as_str());
+                            if ui.selectable_label(is_open, &doc.file_name).clicked() {
+                                clicked = Some((*doc).clone());
+                            }
+                            if let Some(size) = doc.size() {
+                                ui.label(
+                                    RichText::new(FileSizeUtils::format_size(size))
+                                        .small()
+                                        .color(ui.visuals().weak_text_color()),
+                                );
+                            }
+                            if let Some(created) = doc.created_label() {
+                                ui.label(
+                                    RichText::new(created)
+                                        .small()
+                                        .color(ui.visuals().weak_text_color()),
+                                );
+                            }
+                            if !lines.is_empty() {
+                                ui.label(
+                                    RichText::new(format!(
+                                        "{} matching lines",
+                                        NumberFormat::thousands(lines.len())
+                                    ))
+                                    .small(),
+                                );
+                            }
+                        });
+                        if let Some(line) = lines.first() {
+                            ui.horizontal(|ui| {
+                                ui.add_space(15.0);
+                                ui.label(Self::highlight_matches(ui, line.trim(), &query));
+                            });
+                        }
+                    }
+                });
+
+            if let Some(doc) = clicked {
+                if !query.is_empty() {
+                    self.state.doc_search = query.clone();
+                }
+                self.open_remote_doc(&doc);
+            }
+            if download_selected {
+                if let Some(dir) = FileDialog::new().pick_folder() {
+                    self.download_remote_docs(&dir, true);
+                }
+            }
+            if delete_selected {
+                self.delete_selected_docs();
+            }
+            if let Some(section) = resync {
+                self.resync_section(&section);
+            }
+            self.render_opened_doc(ui);
+        });
+    }
+
+    /// Monospace text with every case-insensitive occurrence of `query`
+    /// highlighted.
+    fn highlight_matches(ui: &egui::Ui, text: &str, query: &str) -> egui::text::LayoutJob {
+        let font = egui::TextStyle::Monospace.resolve(ui.style());
+        let normal = egui::TextFormat {
+            font_id: font.clone(),
+            color: ui.visuals().text_color(),
+            ..Default::default()
+        };
+        let highlighted = egui::TextFormat {
+            font_id: font,
+            color: Color32::BLACK,
+            background: Color32::from_rgb(255, 210, 80),
+            ..Default::default()
+        };
+
+        let mut job = egui::text::LayoutJob::default();
+        if query.is_empty() {
+            job.append(text, 0.0, normal);
+            return job;
+        }
+
+        // ASCII lowercasing keeps byte offsets identical to `text`
+        let haystack = text.to_ascii_lowercase();
+        let needle = query.to_ascii_lowercase();
+        let mut start = 0;
+        while let Some(offset) = haystack[start..].find(&needle) {
+            let found = start + offset;
+            job.append(&text[start..found], 0.0, normal.clone());
+            job.append(&text[found..found + needle.len()], 0.0, highlighted.clone());
+            start = found + needle.len();
+        }
+        job.append(&text[start..], 0.0, normal);
+        job
+    }
+
+    /// Read-only view of the opened remote doc. The search box narrows the
+    /// view down to the matching lines.
+    fn render_opened_doc(&mut self, ui: &mut egui::Ui) {
+        if self.state.opened_doc_receiver.is_some() {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Fetching doc…");
+            });
+            return;
+        }
+        let Some(doc) = &self.state.opened_doc else {
+            return;
+        };
+
+        let mut close = false;
+        ui.add_space(5.0);
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(format!("📄 {}", doc.file_name)).strong());
+            close = ui.small_button("✖").clicked();
+        });
+        ui.horizontal(|ui| {
+            ui.label("🔎");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.state.doc_search)
+                    .hint_text("Search in doc")
+                    .desired_width(200.0),
+            );
+        });
+
+        let content = doc.content.as_deref().unwrap_or_default();
+        let query = self.state.doc_search.to_lowercase();
+        let text = if query.is_empty() {
+            content.to_string()
+        } else {
+            let matches: Vec<String> = content
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| line.to_lowercase().contains(&query))
+                .map(|(index, line)| format!("{:>5}: {}", index + 1, line))
+                .collect();
+            ui.label(
+                RichText::new(format!("{} matching lines", matches.len()))
+                    .small()
+                    .color(ui.visuals().weak_text_color()),
+            );
+            matches.join("\n")
+        };
+
+        egui::ScrollArea::both()
+            .id_source("opened_doc")
+            .max_height(300.0)
+            .show(ui, |ui| {
+                // A &str buffer keeps the text selectable but read-only
+                ui.add(
+                    egui::TextEdit::multiline(&mut text.as_str())
+                        .font(egui::TextStyle::Monospace)
+                        .desired_width(f32::INFINITY),
+                );
+            });
+
+        if close {
+            self.state.opened_doc = None;
+        }
+    }
+
+    fn render_discovery_status(&mut self, ui: &mut egui::Ui) {
+        let muted = ui.visuals().text_color().gamma_multiply(0.7);
+
+        ui.horizontal(|ui| {
+            if let Some(scan) = &self.state.discovery_scan {
+                ui.spinner();
+                ui.label(
+                    RichText::new(format!(
+                        "Scanning… {} files examined, {} matched",
+                        NumberFormat::thousands(scan.examined),
+                        NumberFormat::thousands(scan.files.len())
+                    ))
+                    .color(muted),
+                );
+                if ui.small_button("Cancel").clicked() {
+                    self.cancel_discovery();
+                }
+            } else if let Some(discovery) = &self.state.discovery {
+                let found = if self.state.use_file_list {
+                    "files from the explicit list"
+                } else {
+                    "supported files found"
+                };
+                ui.label(
+                    RichText::new(format!(
+                        "{} {}",
+                        NumberFormat::thousands(discovery.files.len()),
+                        found
+                    ))
+                    .color(muted),
+                );
+                if let Some(git) = &discovery.git {
+                    ui.label(RichText::new(format!("· {}", git.label())).color(muted));
+                }
+            } else {
+                ui.label(RichText::new("Scan cancelled").color(muted));
+                if ui.small_button("Rescan").clicked() {
+                    self.restart_discovery();
+                }
+            }
+        });
+    }
+
+    /// Non-blocking, since some teams deliberately sync work in progress.
+    fn render_uncommitted_warning(&self, ui: &mut egui::Ui) {
+        let Some(git) = self.state.discovery.as_ref().and_then(|d| d.git.as_ref()) else {
+            return;
+        };
+        if !git.dirty || self.state.progress.is_completed() {
+            return;
+        }
+
+        ui.colored_label(
+            Color32::from_rgb(230, 160, 30),
+            format!(
+                "⚠ Syncing uncommitted changes ({} files differ from {})",
+                git.changed_files.len(),
+                git.commit
+            ),
+        );
+        egui::CollapsingHeader::new("Changed files")
+            .id_source("uncommitted_files")
+            .show(ui, |ui| {
+                egui::ScrollArea::vertical()
+                    .id_source("uncommitted_files_list")
+                    .max_height(120.0)
+                    .show(ui, |ui| {
+                        for file in &git.changed_files {
+                            ui.label(RichText::new(file).small().monospace());
+                        }
+                    });
+            });
+        ui.add_space(10.0);
+    }
+
+    fn render_file_list(&mut self, ui: &mut egui::Ui) {
+        if self.folder_path.is_none() {
+            return;
+        }
+
+        egui::CollapsingHeader::new("Explicit file list")
+            .id_source("file_list")
+            .show(ui, |ui| {
+                ui.label("Upload exactly these paths, one per line, relative to the folder");
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.state.file_list_text)
+                        .hint_text("src/main.rs\nREADME.md")
+                        .font(egui::TextStyle::Monospace)
+                        .desired_width(ui.available_width())
+                        .desired_rows(4),
+                );
+                ui.horizontal(|ui| {
+                    if ui.button("📂 Load from file").clicked() {
+                        if let Some(path) = FileDialog::new().pick_file() {
+                            match std::fs::read_to_string(&path) {
+                                Ok(text) => self.state.file_list_text = text,
+                                Err(e) => {
+                                    self.state.error_message =
+                                        Some(format!("Could not read file list: {}", e))
+                                }
+                            }
+                        }
+                    }
+                    if ui
+                        .button("✔ Use this list")
+                        .on_hover_text("Skips discovery, filter rules and sections")
+                        .clicked()
+                    {
+                        self.use_file_list();
+                    }
+                    if self.state.use_file_list && ui.button("Back to discovery").clicked() {
+                        self.stop_using_file_list();
+                    }
+                });
+                if let Some(message) = &self.state.file_list_message {
+                    ui.label(RichText::new(message).small());
+                }
+            });
+    }
+
+    fn render_approval(&mut self, ui: &mut egui::Ui) {
+        if self.folder_path.is_none() {
+            return;
+        }
+
+        egui::CollapsingHeader::new("Approval")
+            .id_source("approval")
+            .show(ui, |ui| {
+                ui.label("Freeze the selection into a signed plan, or run an approved one");
+                ui.horizontal(|ui| {
+                    ui.label("Approval key");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.state.plan_key)
+                            .password(true)
+                            .desired_width(150.0),
+                    )
+                    .on_hover_text("Shared with the reviewer; other keys are refused");
+                });
+                ui.horizontal(|ui| {
+                    let has_key = !self.state.plan_key.is_empty();
+                    ui.add_enabled_ui(has_key && self.state.discovery.is_some(), |ui| {
+                        if ui.button("🔒 Freeze plan").clicked() {
+                            if let Some(path) = FileDialog::new()
+                                .set_file_name("upload-plan.json")
+                                .save_file()
+                            {
+                                self.freeze_plan(&path);
+                            }
+                        }
+                    });
+                    ui.add_enabled_ui(has_key, |ui| {
+                        if ui.button("📂 Load approved plan").clicked() {
+                            if let Some(path) =
+                                FileDialog::new().add_filter("Plan", &["json"]).pick_file()
+                            {
+                                self.load_plan(&path);
+                            }
+                        }
+                    });
+                    if self.state.approved_plan.is_some()
+                        && ui.button("Back to discovery").clicked()
+                    {
+                        self.stop_using_file_list();
+                    }
+                });
+                if let Some(plan) = &self.state.approved_plan {
+                    let mut details = format!(
+                        "Approved plan from {}, {} files",
+                        TimeFormat::ago(plan.created_at),
+                        plan.files.len()
+                    );
+                    if let Some(git) = &plan.git {
+                        details.push_str(&format!(", {}", git.label()));
+                    }
+                    ui.label(RichText::new(details).small());
+                }
+            });
+    }
+
+    fn render_details(&mut self, ui: &mut egui::Ui) {
+        let mut jump_to_latest = false;
+        ui.horizontal(|ui| {
+            if ui
+                .button(if self.state.show_details {
+                    "Hide Details"
+                } else {
+                    "Show Details"
+                })
+                .clicked()
+            {
+                self.state.show_details = !self.state.show_details;
+            }
+            if self.state.show_details {
+                ui.checkbox(&mut self.state.group_by_directory, "Group by directory");
+                if !self.state.group_by_directory {
+                    jump_to_latest = ui
+                        .checkbox(&mut self.state.follow_latest, "Follow latest")
+                        .changed()
+                        && self.state.follow_latest;
+                }
+            }
+        });
+
+        if self.state.show_details {
+            let evicted = &self.state.evicted_statuses;
+            if evicted.entries > 0 {
+                ui.label(
+                    RichText::new(format!(
+                        "{} older entries cleared: ✅ {} uploaded | ⏩ {} skipped",
+                        NumberFormat::thousands(evicted.entries),
+                        NumberFormat::thousands(evicted.uploaded),
+                        NumberFormat::thousands(evicted.skipped)
+                    ))
+                    .color(ui.visuals().weak_text_color()),
+                );
+            }
+
+            // Only the newest entries are shown, older ones are paged in on demand
+            let total = self.state.file_statuses.len();
+            let shown = total.min((self.state.status_pages + 1) * STATUS_PAGE_SIZE);
+            let hidden = total - shown;
+            if hidden > 0 {
+                ui.horizontal(|ui| {
+                    ui.label(
+                        RichText::new(format!(
+                            "{} earlier entries hidden",
+                            NumberFormat::thousands(hidden)
+                        ))
+                        .color(ui.visuals().weak_text_color()),
+                    );
+                    if ui.small_button("Load more").clicked() {
+                        self.state.status_pages += 1;
+                    }
+                });
+            }
+
+            let statuses = &self.state.file_statuses[hidden..];
+            if self.state.group_by_directory {
+                Self::render_grouped_statuses(ui, statuses);
+                return;
+            }
+
+            let row_height = ui.text_style_height(&egui::TextStyle::Body);
+            let follow_latest = self.state.follow_latest;
+            let output = egui::Frame::none()
+                .fill(ui.style().visuals.extreme_bg_color)
+                .inner_margin(8.0)
+                .show(ui, |ui| {
+                    let mut scroll_area = egui::ScrollArea::vertical()
+                        .id_source("status_list")
+                        .max_height(200.0)
+                        .stick_to_bottom(follow_latest);
+                    if jump_to_latest {
+                        scroll_area = scroll_area.vertical_scroll_offset(f32::MAX);
+                    }
+                    scroll_area.show_rows(ui, row_height, statuses.len(), |ui, range| {
+                        for status in &statuses[range] {
+                            Self::render_status_row(ui, status);
+                        }
+                    })
+                })
+                .inner;
+
+            // Scrolling away from the bottom by hand stops following
+            let max_offset = output.content_size.y - output.inner_rect.height();
+            if follow_latest && !jump_to_latest && output.state.offset.y < max_offset - 1.0 {
+                self.state.follow_latest = false;
+            }
+        }
+    }
+
+    fn render_grouped_statuses(ui: &mut egui::Ui, statuses: &[FileStatus]) {
+        let mut groups: BTreeMap<String, Vec<&FileStatus>> = BTreeMap::new();
+        for status in statuses.iter().filter(|status| status.status.is_final()) {
+            groups
+                .entry(Self::top_level_dir(status))
+                .or_default()
+                .push(status);
+        }
+
+        egui::Frame::none()
+            .fill(ui.style().visuals.extreme_bg_color)
+            .inner_margin(8.0)
+            .show(ui, |ui| {
+                egui::ScrollArea::vertical()
+                    .id_source("status_groups")
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        for (dir, statuses) in &groups {
+                            let successful = statuses
+                                .iter()
+                                .filter(|s| matches!(s.status, UploadStatus::Success))
+                                .count();
+                            let failed = statuses
+                                .iter()
+                                .filter(|s| matches!(s.status, UploadStatus::Error(_)))
+                                .count();
+                            egui::CollapsingHeader::new(format!(
+                                "{} — {} | ✅ {} | ❌ {}",
+                                dir,
+                                statuses.len(),
+                                successful,
+                                failed
+                            ))
+                            .id_source(("status_group", dir))
+                            .show(ui, |ui| {
+                                for status in statuses {
+                                    Self::render_status_row(ui, status);
+                                }
+                            });
+                        }
+                    });
+            });
+    }
+
+    /// Top-level directory of a status below the selected folder. Files in
+    /// the folder itself and docs without a local path get their own groups.
+    fn top_level_dir(status: &FileStatus) -> String {
+        let Some(relative_path) = &status.relative_path else {
+            return "(project docs)".to_string();
+        };
+        match relative_path.split_once('/') {
+            Some((dir, _)) => format!("{}/", dir),
+            None => "(root)".to_string(),
+        }
+    }
+
+    fn render_reauth_panel(&mut self, ui: &mut egui::Ui) {
+        ui.add_space(10.0);
+        ui.group(|ui| {
+            ui.colored_label(
+                Color32::from_rgb(230, 160, 30),
+                RichText::new("🔑 Session expired — uploads are paused").strong(),
+            );
+            ui.label(
+                "Copy a fresh curl request from Claude.ai and paste it below. \
+                The run resumes with the remaining files once it is validated.",
+            );
+            ui.add_space(5.0);
+
+            ui.add(
+                egui::TextEdit::multiline(&mut self.state.reauth_curl_text)
+                    .desired_width(ui.available_width())
+                    .desired_rows(4)
+                    .font(egui::TextStyle::Monospace)
+                    .hint_text("curl 'https://claude.ai/api/organizations/<org-id>/projects/<project-id>/docs' ..."),
+            );
+
+            ui.horizontal(|ui| {
+                ui.add_enabled_ui(!self.state.reauth_curl_text.trim().is_empty(), |ui| {
+                    if ui.button("✔ Validate & resume").clicked() {
+                        self.resume_with_new_session();
+                    }
+                });
+                if ui.button("⏹ Stop run").clicked() {
+                    self.stop_run();
+                }
+            });
+        });
+    }
+
+    fn render_offline_panel(&mut self, ui: &mut egui::Ui) {
+        ui.add_space(10.0);
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.colored_label(
+                    Color32::from_rgb(230, 160, 30),
+                    RichText::new("📡 Offline — will retry when the connection returns").strong(),
+                );
+            });
+            ui.label("This is a network problem, not an expired session. No action is needed.");
+            if ui.button("⏹ Stop run").clicked() {
+                self.stop_run();
+            }
+        });
+    }
+
+    fn render_failure_summary(&mut self, ui: &mut egui::Ui) {
+        let groups = self.failure_groups();
+        if groups.is_empty() {
+            return;
+        }
+
+        ui.add_space(10.0);
+        ui.group(|ui| {
+            ui.label(RichText::new("Failures").strong());
+            ui.add_space(5.0);
+
+            for (kind, count) in groups {
+                ui.horizontal(|ui| {
+                    ui.colored_label(
+                        Color32::from_rgb(220, 50, 50),
+                        format!(
+                            "{} {}: {}",
+                            count,
+                            if count == 1 { "file" } else { "files" },
+                            kind.label()
+                        ),
+                    );
+
+                    match kind {
+                        ErrorKind::Conflict => {
+                            if ui
+                                .button("Overwrite")
+                                .on_hover_text("Replace the edited docs with the local files")
+                                .clicked()
+                            {
+                                self.resolve_conflicts(ConflictResolution::Overwrite);
+                            }
+                            if ui
+                                .button("Keep remote")
+                                .on_hover_text("Keep the edits made in Claude and mark the local files as synced")
+                                .clicked()
+                            {
+                                self.resolve_conflicts(ConflictResolution::KeepRemote);
+                            }
+                        }
+                        ErrorKind::SessionExpired => {
+                            if ui
+                                .button("🔑 Re-auth & retry")
+                                .on_hover_text("Paste a fresh curl command above, then retry")
+                                .clicked()
+                            {
+                                self.retry_failed(kind);
+                            }
+                        }
+                        ErrorKind::TooLarge | ErrorKind::InvalidEncoding => {
+                            if ui
+                                .button("🔄 Retry")
+                                .on_hover_text("Retry once the files below are fixed")
+                                .clicked()
+                            {
+                                self.retry_failed(kind);
+                            }
+                        }
+                        // Retrying cannot change the outcome
+                        ErrorKind::NotPermitted => {}
+                        _ => {
+                            if ui.button("🔄 Retry").clicked() {
+                                self.retry_failed(kind);
+                            }
+                        }
+                    }
+
+                    if ui.button("Skip").clicked() {
+                        self.skip_failed(kind);
+                    }
+                });
+
+                if matches!(kind, ErrorKind::TooLarge | ErrorKind::InvalidEncoding) {
+                    self.render_fixable_files(ui, kind);
+                }
+            }
+
+            if let Some((name, preview)) = &self.state.fix_preview {
+                let mut close = false;
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new(format!("👁 {}", name)).strong());
+                    close = ui.small_button("✖").clicked();
+                });
+                egui::Frame::none()
+                    .fill(ui.style().visuals.extreme_bg_color)
+                    .inner_margin(8.0)
+                    .show(ui, |ui| {
+                        egui::ScrollArea::both()
+                            .id_source("fix_preview")
+                            .max_height(200.0)
+                            .show(ui, |ui| {
+                                ui.label(RichText::new(preview).monospace());
+                            });
+                    });
+                if close {
+                    self.state.fix_preview = None;
+                }
+            }
+        });
+    }
+
+    /// Lists the files of a group that need a local fix, with shortcuts to
+    /// open them in the default editor or preview their first and last lines.
+    fn render_fixable_files(&mut self, ui: &mut egui::Ui, kind: ErrorKind) {
+        for file in self.failed_files(kind) {
+            ui.horizontal(|ui| {
+                ui.add_space(15.0);
+                ui.label(RichText::new(&file.relative_path).small());
+                if ui
+                    .small_button("📝 Open")
+                    .on_hover_text("Open in the default editor")
+                    .clicked()
+                {
+                    if let Err(e) = open::that(&file.path) {
+                        self.state.error_message =
+                            Some(format!("Could not open {}: {}", file.relative_path, e));
+                    }
+                }
+                if ui
+                    .small_button("👁 Preview")
+                    .on_hover_text("Show the first and last lines")
+                    .clicked()
+                {
+                    let preview = FilePreview::head_and_tail(&file.path, FIX_PREVIEW_LINES)
+                        .unwrap_or_else(|e| format!("Could not read file: {}", e));
+                    self.state.fix_preview = Some((file.relative_path.clone(), preview));
+                }
+            });
+        }
+    }
+
+    fn render_settings(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("⚙ Settings")
+            .id_source("settings")
+            .show(ui, |ui| {
+                self.render_header_overrides(ui);
+                ui.horizontal(|ui| {
+                    ui.label("UI scale");
+                    // Applied on release, the slider would move under the pointer otherwise
+                    let mut ui_scale = self.ui_scale_draft.unwrap_or(self.appearance.ui_scale);
+                    let response = ui.add(
+                        egui::Slider::new(&mut ui_scale, 0.5..=3.0)
+                            .step_by(0.05)
+                            .suffix("×"),
+                    );
+                    if response.dragged() {
+                        self.ui_scale_draft = Some(ui_scale);
+                    } else if response.drag_released() || response.changed() {
+                        self.appearance.ui_scale = ui_scale;
+                        self.ui_scale_draft = None;
+                    }
+                    ui.label("Font size");
+                    ui.add(
+                        egui::DragValue::new(&mut self.appearance.font_size)
+                            .clamp_range(8.0..=32.0)
+                            .speed(0.5)
+                            .suffix(" pt"),
+                    );
+                    if ui.small_button("Reset").clicked() {
+                        self.appearance = Default::default();
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Monospace font");
+                    let current = self.appearance.monospace_font.as_deref().map(|path| {
+                        Path::new(path)
+                            .file_name()
+                            .map_or(path.to_string(), |name| name.to_string_lossy().to_string())
+                    });
+                    let current = current.unwrap_or_else(|| "Default".to_string());
+                    ui.label(RichText::new(current).monospace());
+                    if ui.small_button("Choose…").clicked() {
+                        let picked = FileDialog::new()
+                            .add_filter("Fonts", &["ttf", "otf", "ttc"])
+                            .pick_file();
+                        if let Some(path) = picked {
+                            self.appearance.monospace_font =
+                                Some(path.to_string_lossy().to_string());
+                        }
+                    }
+                    if self.appearance.monospace_font.is_some()
+                        && ui.small_button("Default").clicked()
+                    {
+                        self.appearance.monospace_font = None;
+                    }
+                })
+                .response
+                .on_hover_text("Pick a font covering CJK or other scripts shown as boxes");
+                ui.horizontal(|ui| {
+                    ui.label("Project document limit");
+                    ui.add(
+                        egui::DragValue::new(&mut self.settings.project_doc_limit)
+                            .clamp_range(1..=100_000),
+                    )
+                    .on_hover_text("Uploads larger than this need an explicit confirmation");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Run cap");
+                    ui.add(
+                        egui::DragValue::new(&mut self.settings.run_cap_files)
+                            .clamp_range(0..=100_000)
+                            .suffix(" files"),
+                    );
+                    ui.label("and");
+                    ui.add(
+                        egui::DragValue::new(&mut self.settings.run_cap_mb)
+                            .clamp_range(0.0..=10_000.0)
+                            .speed(0.1)
+                            .suffix(" MB"),
+                    );
+                })
+                .response
+                .on_hover_text("Larger selections are trimmed before a run starts; 0 means no cap");
+                ui.horizontal(|ui| {
+                    ui.label("Session check attempts");
+                    ui.add(egui::DragValue::new(&mut self.settings.preflight_attempts).clamp_range(1..=10));
+                    ui.label("every");
+                    ui.add(
+                        egui::DragValue::new(&mut self.settings.preflight_interval_secs)
+                            .clamp_range(1..=300)
+                            .suffix(" s"),
+                    );
+                })
+                .response
+                .on_hover_text("Transient network or server errors during the pre-flight check are retried before the run fails");
+                ui.horizontal(|ui| {
+                    ui.label("Upload attempts");
+                    ui.add(egui::DragValue::new(&mut self.settings.upload_attempts).clamp_range(1..=10));
+                    ui.label("starting");
+                    ui.add(
+                        egui::DragValue::new(&mut self.settings.retry_delay_secs)
+                            .clamp_range(1..=60)
+                            .suffix(" s"),
+                    );
+                    ui.label("apart");
+                })
+                .response
+                .on_hover_text("Files failing with a network or server error are sent again, doubling the wait each time");
+                ui.horizontal(|ui| {
+                    ui.label("Pause between requests");
+                    ui.add(
+                        egui::DragValue::new(&mut self.settings.request_interval_ms)
+                            .clamp_range(0..=10_000)
+                            .suffix(" ms"),
+                    );
+                    ui.label("Parallel deletes");
+                    ui.add(egui::DragValue::new(&mut self.settings.delete_concurrency).clamp_range(1..=8));
+                })
+                .response
+                .on_hover_text("Applies to uploads and deletes alike, so large deletes don't trip the rate limit");
+                ui.horizontal(|ui| {
+                    ui.label("Retries when rate limited");
+                    ui.add(egui::DragValue::new(&mut self.settings.rate_limit_retries).clamp_range(0..=20));
+                })
+                .response
+                .on_hover_text("Waits with exponential backoff before each retry");
+                ui.checkbox(
+                    &mut self.settings.sync_with_manifest,
+                    "Skip files unchanged since the last sync",
+                )
+                .on_hover_text("Keeps a manifest of content hashes in the project so other machines can skip files that were already uploaded");
+                ui.horizontal(|ui| {
+                    if self.plan_policy.is_required() {
+                        ui.label("🔒 Only approved plans are uploaded");
+                        let hover = "Takes the approval key the requirement was set with";
+                        if ui.button("Lift").on_hover_text(hover).clicked() {
+                            self.lift_plan_requirement();
+                        }
+                    } else {
+                        let hover = "Refuses to upload unless a signed plan is loaded under \
+                            Approval. Locked with the approval key entered there.";
+                        if ui.button("🔒 Require approved plans").on_hover_text(hover).clicked() {
+                            self.require_approved_plans();
+                        }
+                    }
+                });
+                ui.checkbox(
+                    &mut self.settings.reupload_modified_files,
+                    "Upload files changed during the run again",
+                )
+                .on_hover_text("Otherwise they are only marked as possibly stale");
+                ui.checkbox(
+                    &mut self.settings.tag_docs_with_section,
+                    "Tag doc names with their section",
+                )
+                .on_hover_text("Names docs like [api] src/routes/users.ts");
+                ui.checkbox(
+                    &mut self.settings.upload_sync_info,
+                    "Keep a SYNC_INFO.md doc in the project",
+                )
+                .on_hover_text("Lists when and from which commit the project was last synced, so Claude can tell how current it is");
+                ui.checkbox(
+                    &mut self.settings.note_sync_in_description,
+                    "Note the last sync in the project description",
+                )
+                .on_hover_text("Appends \"Last synced from <repo> @ <commit> on <date>\" after each run, replacing the previous note");
+            });
+    }
+
+    fn render_header_overrides(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Requests")
+            .id_source("header_overrides")
+            .show(ui, |ui| {
+                let Some(profile) = self.profiles.active_mut() else {
+                    ui.label("Save a profile to change the URL or headers of its requests");
+                    return;
+                };
+                ui.label("Project URL, for accounts with another URL shape");
+                ui.add(
+                    egui::TextEdit::singleline(&mut profile.project_url)
+                        .hint_text(DEFAULT_PROJECT_URL)
+                        .font(egui::TextStyle::Monospace)
+                        .desired_width(ui.available_width()),
+                );
+                if !profile.project_url.trim().is_empty()
+                    && !profile.project_url.contains("{project}")
+                {
+                    ui.colored_label(
+                        Color32::from_rgb(230, 160, 30),
+                        "⚠ The URL needs a {project} placeholder",
+                    );
+                }
+                ui.add_space(5.0);
+
+                ui.label(format!(
+                    "Applied to every request of profile '{}', one \"Name: value\" per line",
+                    profile.name
+                ));
+                ui.add(
+                    egui::TextEdit::multiline(&mut profile.header_overrides)
+                        .hint_text("x-auth: <token>\nuser-agent:")
+                        .font(egui::TextStyle::Monospace)
+                        .desired_width(ui.available_width())
+                        .desired_rows(3),
+                );
+                ui.label(
+                    RichText::new(
+                        "Replaces headers of the curl command, an empty value removes one",
+                    )
+                    .small()
+                    .color(ui.visuals().weak_text_color()),
+                );
+
+                let invalid =
+                    CurlParser::override_headers(&mut HeaderMap::new(), &profile.header_overrides);
+                for line in invalid {
+                    ui.colored_label(
+                        Color32::from_rgb(230, 160, 30),
+                        format!("⚠ Not a valid header: {}", line),
+                    );
+                }
+            });
+    }
+
+    /// What the project already holds, listed on demand in normal mode so
+    /// name clashes are visible before uploading.
+    fn render_remote_files(&mut self, ui: &mut egui::Ui) {
+        let title = match &self.state.remote_docs {
+            Some(docs) => format!("☁ Remote files ({})", NumberFormat::thousands(docs.len())),
+            None => "☁ Remote files".to_string(),
+        };
+        let listing = self.state.remote_docs_receiver.is_some();
+        let can_list = !self.curl_text.is_empty() && !listing;
+        let can_delete = self.permissions().delete && !self.is_busy();
+        let mut delete_selected = false;
+        let response = egui::CollapsingHeader::new(title)
+            .id_source("remote_files")
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add_enabled_ui(can_list, |ui| {
+                        if ui.button("🔄 Refresh").clicked() {
+                            self.list_remote_docs();
+                        }
+                    });
+                    if listing {
+                        ui.spinner();
+                    }
+                });
+
+                let Some(docs) = &self.state.remote_docs else {
+                    let hint = if self.curl_text.is_empty() {
+                        "Paste a curl command to list the project's docs"
+                    } else {
+                        "Not listed yet"
+                    };
+                    ui.label(RichText::new(hint).color(ui.visuals().weak_text_color()));
+                    return;
+                };
+                if docs.is_empty() {
+                    ui.label("The project has no docs yet");
+                    return;
+                }
+
+                let total_size: u64 = docs.iter().filter_map(RemoteDoc::size).sum();
+                ui.label(format!(
+                    "{} docs, {}",
+                    NumberFormat::thousands(docs.len()),
+                    FileSizeUtils::format_size(total_size)
+                ));
+                let local_names: HashSet<String> = self
+                    .state
+                    .discovery
+                    .iter()
+                    .flat_map(|discovery| discovery.files.iter())
+                    .map(|file| DocName::sanitize(&file.file_name()))
+                    .collect();
+                let clashes = docs
+                    .iter()
+                    .filter(|doc| local_names.contains(&doc.file_name))
+                    .count();
+                if clashes > 0 {
+                    ui.label(
+                        RichText::new(format!(
+                            "⚠ {} selected files already have a doc with the same name",
+                            NumberFormat::thousands(clashes)
+                        ))
+                        .color(Color32::from_rgb(230, 160, 30)),
+                    );
+                }
+
+                // Uuids of docs gone since the last refresh would never show up
+                let selection = &mut self.state.selected_docs;
+                selection.retain(|uuid| docs.iter().any(|doc| &doc.uuid == uuid));
+                let selected = selection.len();
+                ui.horizontal(|ui| {
+                    if ui.small_button("Select all").clicked() {
+                        selection.extend(docs.iter().map(|doc| doc.uuid.clone()));
+                    }
+                    if ui.small_button("Select none").clicked() {
+                        selection.clear();
+                    }
+                    ui.label(format!("{} selected", NumberFormat::thousands(selected)));
+                    if self.state.confirm_delete_docs && selected > 0 {
+                        let confirm = RichText::new(format!("⚠ Delete {} docs", selected))
+                            .color(Color32::from_rgb(220, 50, 50));
+                        if ui.button(confirm).clicked() {
+                            delete_selected = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.state.confirm_delete_docs = false;
+                        }
+                    } else {
+                        ui.add_enabled_ui(selected > 0 && can_delete, |ui| {
+                            if ui
+                                .button("🗑 Delete selected")
+                                .on_disabled_hover_text(
+                                    "Needs delete permission and no running run",
+                                )
+                                .clicked()
+                            {
+                                self.state.confirm_delete_docs = true;
+                            }
+                        });
+                    }
+                });
+
+                let mut sorted: Vec<&RemoteDoc> = docs.iter().collect();
+                sorted.sort_by_key(|doc| doc.file_name.to_lowercase());
+                egui::ScrollArea::vertical()
+                    .id_source("remote_files_list")
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("remote_files_grid")
+                            .num_columns(4)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for doc in sorted {
+                                    let mut is_selected = selection.contains(&doc.uuid);
+                                    if ui.checkbox(&mut is_selected, "").changed() {
+                                        if is_selected {
+                                            selection.insert(doc.uuid.clone());
+                                        } else {
+                                            selection.remove(&doc.uuid);
+                                        }
+                                    }
+                                    let name = RichText::new(&doc.file_name);
+                                    if local_names.contains(&doc.file_name) {
+                                        ui.label(name.color(Color32::from_rgb(230, 160, 30)))
+                                            .on_hover_text("A selected file has the same name");
+                                    } else {
+                                        ui.label(name);
+                                    }
+                                    let size = doc.size().map(FileSizeUtils::format_size);
+                                    ui.label(
+                                        RichText::new(size.unwrap_or_default())
+                                            .small()
+                                            .color(ui.visuals().weak_text_color()),
+                                    );
+                                    let uuid = ui
+                                        .label(RichText::new(&doc.uuid).small().monospace())
+                                        .on_hover_text("Click to copy");
+                                    if uuid.clicked() {
+                                        ui.output_mut(|output| {
+                                            output.copied_text = doc.uuid.clone()
+                                        });
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+
+        if delete_selected {
+            self.state.confirm_delete_docs = false;
+            self.delete_selected_docs();
+        }
+
+        // Listed the first time the panel is opened
+        let clicked = response.header_response.clicked();
+        if clicked && self.state.remote_docs.is_none() && can_list {
+            self.list_remote_docs();
+        }
+    }
+
+    fn render_history(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new(format!("📊 Run history ({})", self.history.runs.len()))
+            .id_source("run_history")
+            .show(ui, |ui| {
+                let runs = &self.history.runs;
+                if runs.is_empty() {
+                    ui.label("No runs recorded yet");
+                    return;
+                }
+
+                let uploaded: usize = runs
+                    .iter()
+                    .map(|run| run.count(FileOutcome::Uploaded))
+                    .sum();
+                ui.label(format!(
+                    "{} runs, {} files uploaded, {} per run on average",
+                    runs.len(),
+                    NumberFormat::thousands(uploaded),
+                    self.history
+                        .average_duration()
+                        .map(TimeFormat::duration)
+                        .unwrap_or_default()
+                ));
+
+                // Failure rate of the latest runs against the ones before
+                if runs.len() > RECENT_RUNS {
+                    let average = |runs: &[RunRecord]| {
+                        runs.iter().map(RunRecord::failure_rate).sum::<f32>() / runs.len() as f32
+                    };
+                    let (earlier, recent) = runs.split_at(runs.len() - RECENT_RUNS);
+                    let (recent, earlier) = (average(recent), average(earlier));
+                    let arrow = if recent > earlier {
+                        "⬆"
+                    } else if recent < earlier {
+                        "⬇"
+                    } else {
+                        "➡"
+                    };
+                    ui.label(format!(
+                        "Failure rate {} {:.1}% over the last {} runs, {:.1}% before",
+                        arrow,
+                        recent * 100.0,
+                        RECENT_RUNS,
+                        earlier * 100.0
+                    ));
+                }
+
+                ui.add_space(5.0);
+                ui.label(RichText::new("Files uploaded per run").small());
+                let uploads: Vec<f32> = runs
+                    .iter()
+                    .map(|run| run.count(FileOutcome::Uploaded) as f32)
+                    .collect();
+                Self::render_run_bars(ui, runs, &uploads, Color32::from_rgb(100, 150, 255));
+
+                ui.label(RichText::new("Failure rate per run").small());
+                let failure_rates: Vec<f32> = runs.iter().map(RunRecord::failure_rate).collect();
+                Self::render_run_bars(ui, runs, &failure_rates, Color32::from_rgb(220, 50, 50));
+
+                let most_failing = self.history.most_failing(5);
+                if !most_failing.is_empty() {
+                    ui.add_space(5.0);
+                    ui.label(RichText::new("Most frequently failing files").small());
+                    for (relative_path, count) in most_failing {
+                        ui.label(format!("{} - failed in {} runs", relative_path, count));
+                    }
+                }
+
+                ui.add_space(5.0);
+                self.render_recent_runs(ui);
+
+                ui.add_space(5.0);
+                self.render_run_comparison(ui);
+
+                ui.add_space(5.0);
+                if ui.button("🗑 Clear history").clicked() {
+                    self.history.runs.clear();
+                    self.state.compared_runs = (None, None);
+                }
+            });
+    }
+
+    fn render_recent_runs(&mut self, ui: &mut egui::Ui) {
+        let can_repeat = !self.curl_text.is_empty()
+            && !self.state.is_uploading
+            && !self.state.is_verifying
+            && !self.state.is_deleting
+            && !self.state.repeat_pending;
+
+        let mut repeat = None;
+        egui::CollapsingHeader::new("Runs")
+            .id_source("recent_runs")
+            .show(ui, |ui| {
+                egui::ScrollArea::vertical()
+                    .id_source("recent_runs_list")
+                    .max_height(150.0)
+                    .show(ui, |ui| {
+                        for (index, run) in self.history.runs.iter().enumerate().rev() {
+                            ui.horizontal(|ui| {
+                                ui.label(format!(
+                                    "{} | ✅ {} | ❌ {} | ⏩ {}",
+                                    run.label(),
+                                    run.count(FileOutcome::Uploaded),
+                                    run.count(FileOutcome::Failed),
+                                    run.count(FileOutcome::Skipped)
+                                ));
+                                if ui
+                                    .add_enabled(can_repeat, egui::Button::new("🔁 Repeat").small())
+                                    .on_hover_text(
+                                        "Upload again with the same folder, sections and rules",
+                                    )
+                                    .on_disabled_hover_text(
+                                        "Needs a curl request and no run in progress",
+                                    )
+                                    .clicked()
+                                {
+                                    repeat = Some(index);
+                                }
+                            });
+                        }
+                    });
+            });
+        if let Some(index) = repeat {
+            self.repeat_run(index);
+        }
+        if self.state.repeat_pending {
+            ui.label(RichText::new("Scanning the folder before repeating the run...").small());
+        }
+    }
+
+    fn render_run_comparison(&mut self, ui: &mut egui::Ui) {
+        let runs = &self.history.runs;
+        let (earlier, later) = &mut self.state.compared_runs;
+        ui.horizontal(|ui| {
+            ui.label("Compare");
+            for (id, selected) in [
+                ("compare_earlier", &mut *earlier),
+                ("compare_later", &mut *later),
+            ] {
+                let text = selected
+                    .and_then(|index| runs.get(index))
+                    .map(RunRecord::label)
+                    .unwrap_or_else(|| "Select a run".to_string());
+                egui::ComboBox::from_id_source(id)
+                    .selected_text(text)
+                    .show_ui(ui, |ui| {
+                        for (index, run) in runs.iter().enumerate().rev() {
+                            ui.selectable_value(selected, Some(index), run.label());
+                        }
+                    });
+                if id == "compare_earlier" {
+                    ui.label("with");
+                }
+            }
+        });
+
+        let (Some(earlier), Some(later)) = (
+            earlier.and_then(|index| runs.get(index)),
+            later.and_then(|index| runs.get(index)),
+        ) else {
+            return;
+        };
+        let comparison = RunComparison::between(earlier, later);
+
+        let size_sign = if comparison.size_delta < 0 { "-" } else { "+" };
+        let duration_sign = if comparison.duration_delta < 0.0 {
+            "-"
+        } else {
+            "+"
+        };
+        ui.label(format!(
+            "Size {}{}, duration {}{}",
+            size_sign,
+            FileSizeUtils::format_size(comparison.size_delta.unsigned_abs()),
+            duration_sign,
+            TimeFormat::duration(Duration::from_secs_f64(comparison.duration_delta.abs()))
+        ));
+
+        for (title, files, color) in [
+            (
+                "Now failing",
+                &comparison.now_failing,
+                Color32::from_rgb(220, 50, 50),
+            ),
+            ("Fixed", &comparison.fixed, Color32::from_rgb(0, 180, 0)),
+            ("Added", &comparison.added, ui.visuals().text_color()),
+            (
+                "Removed",
+                &comparison.removed,
+                ui.visuals().weak_text_color(),
+            ),
+        ] {
+            if files.is_empty() {
+                continue;
+            }
+            egui::CollapsingHeader::new(format!("{} ({})", title, files.len()))
+                .id_source(("run_comparison", title))
+                .show(ui, |ui| {
+                    egui::ScrollArea::vertical()
+                        .id_source(("run_comparison_files", title))
+                        .max_height(150.0)
+                        .show(ui, |ui| {
+                            for file in files {
+                                ui.colored_label(color, file);
+                            }
+                        });
+                });
+        }
+        if comparison.now_failing.is_empty()
+            && comparison.fixed.is_empty()
+            && comparison.added.is_empty()
+            && comparison.removed.is_empty()
+        {
+            ui.label("Both runs covered the same files with the same outcome");
+        }
+    }
+
+    /// One bar per run, scaled to the largest value, with the run's details
+    /// on hover.
+    fn render_run_bars(ui: &mut egui::Ui, runs: &[RunRecord], values: &[f32], color: Color32) {
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(ui.available_width(), HISTORY_CHART_HEIGHT),
+            egui::Sense::hover(),
+        );
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+
+        let max = values.iter().cloned().fold(0.0, f32::max);
+        let slot = rect.width() / values.len().max(1) as f32;
+        for (index, value) in values.iter().enumerate() {
+            let height = if max > 0.0 {
+                value / max * rect.height()
+            } else {
+                0.0
+            };
+            let left = rect.left() + index as f32 * slot;
+            let bar = egui::Rect::from_min_max(
+                egui::pos2(left + 1.0, rect.bottom() - height),
+                egui::pos2(left + slot - 1.0, rect.bottom()),
+            );
+            painter.rect_filled(bar, 0.0, color);
+        }
+
+        if let Some(pointer) = response.hover_pos() {
+            let index = (((pointer.x - rect.left()) / slot) as usize).min(runs.len() - 1);
+            let run = &runs[index];
+            response.on_hover_text_at_pointer(format!(
+                "{}, took {}\n✅ {} | ❌ {} | ⏩ {}",
+                TimeFormat::ago(run.started_at),
+                TimeFormat::duration(run.duration),
+                run.count(FileOutcome::Uploaded),
+                run.count(FileOutcome::Failed),
+                run.count(FileOutcome::Skipped)
+            ));
+        }
+    }
+
+    fn render_doc_limit_warning(&mut self, ui: &mut egui::Ui, can_upload: bool) {
+        let file_count = self
+            .state
+            .discovery
+            .as_ref()
+            .map(|discovery| discovery.files.len())
+            .unwrap_or(0);
+        let limit = self.settings.project_doc_limit;
+
+        ui.group(|ui| {
+            ui.colored_label(
+                Color32::from_rgb(220, 50, 50),
+                format!(
+                    "⚠ {} files exceed the project limit of {} documents",
+                    NumberFormat::thousands(file_count),
+                    NumberFormat::thousands(limit)
+                ),
+            );
+            ui.label("Uploads past the limit would fail partway through the run.");
+            if self.state.keep_config.is_some() {
+                ui.label("Select fewer .claudekeep sections above to narrow the upload, or:");
+            }
+
+            ui.add_space(5.0);
+            ui.add_enabled_ui(can_upload, |ui| {
+                ui.horizontal(|ui| {
+                    if ui
+                        .button(format!(
+                            "Upload first {} files",
+                            NumberFormat::thousands(limit)
+                        ))
+                        .clicked()
+                    {
+                        self.start_upload(Some(limit));
+                    }
+                    if ui.button("Upload all anyway").clicked() {
+                        self.start_upload(None);
+                    }
+                });
+                let hover = "Save passes that each fit the project; running one \
+                    replaces the docs of the pass run before it";
+                if ui
+                    .button("✂ Split into passes")
+                    .on_hover_text(hover)
+                    .clicked()
+                {
+                    self.plan_passes();
+                }
+            });
+        });
+    }
+
+    fn render_pass_plan(&mut self, ui: &mut egui::Ui, can_upload: bool) {
+        let Some(plan) = self.pass_plan().cloned() else {
+            return;
+        };
+        let discovered = self
+            .state
+            .discovery
+            .as_ref()
+            .map(|discovery| discovery.files.as_slice())
+            .unwrap_or_default();
+        let unplanned = plan.unplanned(discovered);
+        // Replacing the previous pass deletes its docs
+        let can_replace = plan.active_docs.is_empty() || self.permissions().delete;
+        let can_run = can_upload && can_replace && self.state.replaced_files.is_none();
+
+        ui.group(|ui| {
+            ui.heading(format!("Passes ({})", plan.passes.len()));
+            ui.label(
+                RichText::new("The project holds one pass at a time; run another to rotate it.")
+                    .small(),
+            );
+            ui.add_space(5.0);
+
+            let mut run = None;
+            egui::Grid::new("pass_plan").striped(true).show(ui, |ui| {
+                for (index, pass) in plan.passes.iter().enumerate() {
+                    if plan.active == Some(index) {
+                        ui.label(RichText::new(&pass.name).strong())
+                            .on_hover_text("Its docs are in the project now");
+                    } else {
+                        ui.label(&pass.name);
+                    }
+                    ui.label(format!(
+                        "{} files",
+                        NumberFormat::thousands(pass.files.len())
+                    ));
+                    ui.add_enabled_ui(can_run, |ui| {
+                        if ui.button("▶ Run").clicked() {
+                            run = Some(index);
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+            if let Some(index) = run {
+                self.run_pass(index);
+            }
+
+            if !plan.active_docs.is_empty() {
+                ui.label(format!(
+                    "The next pass replaces {} docs",
+                    NumberFormat::thousands(plan.active_docs.len())
+                ));
+            }
+            if !can_replace {
+                ui.label(RichText::new("Replacing a pass needs delete permission").small());
+            }
+            if unplanned > 0 {
+                ui.colored_label(
+                    Color32::from_rgb(230, 160, 30),
+                    format!(
+                        "{} files are in no pass, split again to include them",
+                        NumberFormat::thousands(unplanned)
+                    ),
+                );
+            }
+            ui.horizontal(|ui| {
+                if ui.button("✂ Split again").clicked() {
+                    self.plan_passes();
+                }
+                let hover = "Docs of the last pass stay in the project";
+                if ui.button("Forget passes").on_hover_text(hover).clicked() {
+                    self.forget_pass_plan();
+                }
+            });
+        });
+    }
+
+    fn render_trim_panel(&mut self, ui: &mut egui::Ui, can_upload: bool) {
+        if self.state.trim.is_none() {
+            self.propose_trim(TrimStrategy::LargestFiles);
+        }
+        let (Some(discovery), Some(trim)) = (&self.state.discovery, &mut self.state.trim) else {
+            return;
+        };
+        let cap = self.settings.run_cap();
+        let total_size: u64 = discovery.files.iter().map(|file| file.size).sum();
+        let sizes: BTreeMap<&str, u64> = discovery
+            .files
+            .iter()
+            .map(|file| (file.relative_path.as_str(), file.size))
+            .collect();
+        let mut strategy = trim.strategy;
+        let mut start = None;
+
+        ui.group(|ui| {
+            let limits: Vec<String> = cap
+                .max_files
+                .map(|files| format!("{} files", NumberFormat::thousands(files)))
+                .into_iter()
+                .chain(cap.max_bytes.map(FileSizeUtils::format_size))
+                .collect();
+            ui.colored_label(
+                Color32::from_rgb(230, 160, 30),
+                format!(
+                    "⚠ {} files, {} exceed the run cap of {}",
+                    NumberFormat::thousands(discovery.files.len()),
+                    FileSizeUtils::format_size(total_size),
+                    limits.join(" and ")
+                ),
+            );
+
+            ui.horizontal(|ui| {
+                ui.label("Trim by");
+                egui::ComboBox::from_id_source("trim_strategy")
+                    .selected_text(strategy.label())
+                    .show_ui(ui, |ui| {
+                        for option in TrimStrategy::ALL {
+                            ui.selectable_value(&mut strategy, option, option.label());
+                        }
+                    });
+            });
+            if strategy == TrimStrategy::LowestPrioritySections && self.state.keep_config.is_none()
+            {
+                ui.label(
+                    RichText::new("Without a .claudekeep file this drops the largest files")
+                        .small()
+                        .color(ui.visuals().weak_text_color()),
+                );
+            }
+
+            ui.label(
+                RichText::new("Untick a file to upload it after all")
+                    .small()
+                    .color(ui.visuals().weak_text_color()),
+            );
+            egui::ScrollArea::vertical()
+                .id_source("trim_files_scroll")
+                .max_height(150.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("trim_files_grid")
+                        .num_columns(2)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for path in &trim.proposed {
+                                let mut dropped = !trim.restored.contains(path);
+                                if ui.checkbox(&mut dropped, path.as_str()).changed() {
+                                    if dropped {
+                                        trim.restored.remove(path);
+                                    } else {
+                                        trim.restored.insert(path.clone());
+                                    }
+                                }
+                                let size = sizes.get(path.as_str()).copied().unwrap_or(0);
+                                ui.label(FileSizeUtils::format_size(size));
+                                ui.end_row();
+                            }
+                        });
+                });
+
+            let (files, bytes) = trim.remaining(&discovery.files);
+            let summary = format!(
+                "After trimming: {} files, {}",
+                NumberFormat::thousands(files),
+                FileSizeUtils::format_size(bytes)
+            );
+            if cap.allows(files, bytes) {
+                ui.label(summary);
+            } else {
+                ui.colored_label(
+                    Color32::from_rgb(230, 160, 30),
+                    format!("{}, still over the cap", summary),
+                );
+            }
+
+            ui.add_space(5.0);
+            ui.add_enabled_ui(can_upload, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Upload trimmed selection").clicked() {
+                        start = Some(true);
+                    }
+                    if ui.button("Upload all anyway").clicked() {
+                        start = Some(false);
+                    }
+                });
+            });
+        });
+
+        if strategy
+            != self
+                .state
+                .trim
+                .as_ref()
+                .map_or(strategy, |trim| trim.strategy)
+        {
+            self.propose_trim(strategy);
+        }
+        match start {
+            Some(true) => self.start_trimmed_upload(),
+            Some(false) => self.start_upload(None),
+            None => {}
+        }
+    }
+
+    fn render_status_row(ui: &mut egui::Ui, status: &FileStatus) {
+        let is_delete = status.operation == Operation::Delete;
+        let is_verify = status.operation == Operation::Verify;
+        let is_preview = status.operation == Operation::Preview;
+        let (icon, color, text) = match &status.status {
+            UploadStatus::Success if is_preview => (
+                "👁",
+                Color32::from_rgb(100, 150, 255),
+                format!("{} - Would upload", status.display_name()),
+            ),
+            UploadStatus::Success if is_verify => (
+                "✔",
+                Color32::from_rgb(0, 180, 0),
+                format!("{} - Verified", status.display_name()),
+            ),
+            UploadStatus::Error(err) if is_verify => (
+                "❌",
+                Color32::from_rgb(220, 50, 50),
+                format!("{} - Verification failed: {}", status.display_name(), err),
+            ),
+            UploadStatus::Processing if is_delete => (
+                "⏳",
+                Color32::from_rgb(150, 150, 150),
+                format!("{} - Deleting...", status.display_name()),
+            ),
+            UploadStatus::Processing => (
+                "⏳",
+                Color32::from_rgb(150, 150, 150),
+                format!("{} - Processing...", status.display_name()),
+            ),
+            UploadStatus::WaitingForAuth => (
+                "🔑",
+                Color32::from_rgb(230, 160, 30),
+                format!(
+                    "{} - Waiting for re-authentication...",
+                    status.display_name()
+                ),
+            ),
+            UploadStatus::WaitingForNetwork => (
+                "📡",
+                Color32::from_rgb(230, 160, 30),
+                format!(
+                    "{} - Offline, waiting for connection...",
+                    status.display_name()
+                ),
+            ),
+            UploadStatus::Retrying {
+                attempt,
+                max_attempts,
+            } => (
+                "🔁",
+                Color32::from_rgb(230, 160, 30),
+                format!(
+                    "{} - Retrying {}/{}...",
+                    status.display_name(),
+                    attempt,
+                    max_attempts
+                ),
+            ),
+            UploadStatus::CheckingSession { .. } => (
+                "🔐",
+                Color32::from_rgb(150, 150, 150),
+                "Checking session...".to_string(),
+            ),
+            UploadStatus::Success if is_delete => (
+                "🗑",
+                Color32::from_rgb(0, 180, 0),
+                format!("{} - Deleted", status.display_name()),
+            ),
+            UploadStatus::Success if status.warning.is_some() => (
+                "⚠",
+                Color32::from_rgb(230, 160, 30),
+                format!(
+                    "{} - {}",
+                    status.display_name(),
+                    status.warning.as_deref().unwrap_or_default()
+                ),
+            ),
+            UploadStatus::Success => (
+                "✅",
+                Color32::from_rgb(0, 180, 0),
+                status.display_name().to_string(),
+            ),
+            UploadStatus::Error(err) if is_delete => (
+                "❌",
+                Color32::from_rgb(220, 50, 50),
+                format!("{} - Delete failed: {}", status.display_name(), err),
+            ),
+            UploadStatus::Error(err) => (
+                "❌",
+                Color32::from_rgb(220, 50, 50),
+                format!("{} - {}", status.display_name(), err),
+            ),
+            UploadStatus::Skipped(reason) => (
+                "⏩",
+                Color32::from_rgb(150, 150, 150),
+                format!("{} - {}", status.display_name(), reason),
+            ),
+        };
+
+        ui.horizontal(|ui| {
+            ui.label(icon);
+            let response = ui.colored_label(color, text);
+            if let Some(modified) = status.modified {
+                response.on_hover_text(format!("Modified {}", TimeFormat::ago(modified)));
+            }
+            if let Some(size) = status.size {
+                ui.label(
+                    RichText::new(FileSizeUtils::format_size(size))
+                        .small()
+                        .color(ui.visuals().weak_text_color()),
+                );
+            }
+            if status.attempts > 1 {
+                ui.label(
+                    RichText::new(format!("{} attempts", status.attempts))
+                        .small()
+                        .color(ui.visuals().weak_text_color()),
+                );
+            }
+            if let Some(duration) = status.duration {
+                ui.label(
+                    RichText::new(TimeFormat::duration(duration))
+                        .small()
+                        .color(ui.visuals().weak_text_color()),
+                );
+            }
+        });
+    }
+
+    fn render_preview(&mut self, ui: &mut egui::Ui) {
+        let Some(discovery) = &self.state.discovery else {
+            return;
+        };
+        let root = self.folder_path.clone().unwrap_or_default();
+        let total_size: u64 = discovery.files.iter().map(|file| file.size).sum();
+        let analysis = self.state.content_analysis.as_ref();
+        let tokens_text = match analysis {
+            Some(analysis) if analysis.finished => format!(
+                ", ~{} tokens",
+                NumberFormat::thousands(analysis.total_tokens())
+            ),
+            Some(analysis) => format!(
+                ", estimating tokens {}/{}…",
+                NumberFormat::thousands(analysis.summaries.len()),
+                NumberFormat::thousands(analysis.total)
+            ),
+            None => String::new(),
+        };
+        let mut export_to = None;
+        let mut report_to = None;
+        let mut ask_sensitive = None;
+        let mut confirm_sensitive = None;
+        let mut cancel_sensitive = false;
+
+        egui::CollapsingHeader::new(format!(
+            "📋 Preview: {} files, {}{}",
+            NumberFormat::thousands(discovery.files.len()),
+            FileSizeUtils::format_size(total_size),
+            tokens_text
+        ))
+        .id_source("file_preview")
+        .show(ui, |ui| {
+            ui.horizontal(|ui| {
+                if ui
+                    .small_button("💾 Export file list")
+                    .on_hover_text("Write the relative paths, one per line, e.g. for tar or rsync")
+                    .clicked()
+                {
+                    export_to = FileDialog::new().set_file_name("file-list.txt").save_file();
+                }
+                if ui
+                    .small_button("📄 Export report")
+                    .on_hover_text(
+                        "HTML summary of the selection for a reviewer, without file contents",
+                    )
+                    .clicked()
+                {
+                    report_to = FileDialog::new()
+                        .set_file_name("upload-selection.html")
+                        .save_file();
+                }
+            });
+
+            // Largest first, since that is usually what slows a run down
+            let mut files: Vec<_> = discovery.files.iter().collect();
+            files.sort_by(|a, b| b.size.cmp(&a.size));
+
+            let row_height = ui.text_style_height(&egui::TextStyle::Body);
+            egui::ScrollArea::vertical()
+                .id_source("file_preview_scroll")
+                .max_height(200.0)
+                .show_rows(ui, row_height, files.len(), |ui, range| {
+                    egui::Grid::new("file_preview_grid")
+                        .num_columns(4)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for file in &files[range] {
+                                let relative = file.path.strip_prefix(&root).unwrap_or(&file.path);
+                                ui.label(relative.display().to_string());
+                                ui.label(FileSizeUtils::format_size(file.size));
+                                ui.label(
+                                    analysis
+                                        .and_then(|analysis| analysis.summaries.get(&file.path))
+                                        .map(|summary| {
+                                            format!(
+                                                "~{} tok",
+                                                NumberFormat::thousands(summary.tokens)
+                                            )
+                                        })
+                                        .unwrap_or_default(),
+                                );
+                                ui.label(file.modified.map(TimeFormat::ago).unwrap_or_default());
+                                ui.end_row();
+                            }
+                        });
+                });
+
+            if !discovery.sensitive.is_empty() {
+                egui::CollapsingHeader::new(
+                    RichText::new(format!(
+                        "🔒 {} sensitive files blocked",
+                        NumberFormat::thousands(discovery.sensitive.len())
+                    ))
+                    .color(Color32::from_rgb(230, 160, 30)),
+                )
+                .id_source("sensitive_files")
+                .show(ui, |ui| {
+                    ui.label(
+                        RichText::new(
+                            "Keys, credentials and secrets are never uploaded by any filter \
+                             rule; confirm a file to upload it anyway",
+                        )
+                        .small()
+                        .color(ui.visuals().weak_text_color()),
+                    );
+                    egui::ScrollArea::vertical()
+                        .id_source("sensitive_files_scroll")
+                        .max_height(150.0)
+                        .show(ui, |ui| {
+                            egui::Grid::new("sensitive_files_grid")
+                                .num_columns(3)
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    for file in &discovery.sensitive {
+                                        ui.label(&file.relative_path);
+                                        ui.label(file.pattern);
+                                        if self.state.confirm_sensitive.as_ref()
+                                            == Some(&file.relative_path)
+                                        {
+                                            ui.horizontal(|ui| {
+                                                if ui.small_button("⚠ Confirm upload").clicked() {
+                                                    confirm_sensitive =
+                                                        Some(file.relative_path.clone());
+                                                }
+                                                if ui.small_button("Cancel").clicked() {
+                                                    cancel_sensitive = true;
+                                                }
+                                            });
+                                        } else if ui.small_button("Upload anyway").clicked() {
+                                            ask_sensitive = Some(file.relative_path.clone());
+                                        }
+                                        ui.end_row();
+                                    }
+                                });
+                        });
+                });
+            }
+
+            if !discovery.vendored.is_empty() {
+                egui::CollapsingHeader::new(format!(
+                    "🧩 {} files left out as vendored code",
+                    NumberFormat::thousands(discovery.vendored.len())
+                ))
+                .id_source("vendored_files")
+                .show(ui, |ui| {
+                    ui.label(
+                        RichText::new(
+                            "Tick \"Include vendored code\" under Filter rules to upload them",
+                        )
+                        .small()
+                        .color(ui.visuals().weak_text_color()),
+                    );
+                    egui::ScrollArea::vertical()
+                        .id_source("vendored_files_scroll")
+                        .max_height(150.0)
+                        .show(ui, |ui| {
+                            egui::Grid::new("vendored_files_grid")
+                                .num_columns(2)
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    for file in &discovery.vendored {
+                                        ui.label(&file.relative_path);
+                                        ui.label(file.reason);
+                                        ui.end_row();
+                                    }
+                                });
+                        });
+                });
+            }
+        });
+
+        if let Some(path) = export_to {
+            self.export_file_list(&path);
+        }
+        if let Some(path) = report_to {
+            self.export_report(&path);
+        }
+        if ask_sensitive.is_some() {
+            self.state.confirm_sensitive = ask_sensitive;
+        }
+        if cancel_sensitive {
+            self.state.confirm_sensitive = None;
+        }
+        if let Some(relative_path) = confirm_sensitive {
+            self.confirm_sensitive(&relative_path);
         }
     }
 
@@ -0,0 +1,56 @@
+use super::ClaudeUploader;
+use eframe::egui;
+
+/// A profile opened in a window of its own. The window has its own
+/// uploader, so folder, session, filters and runs are never shared with the
+/// main window or with other profile windows. Profiles, run history and pass
+/// plans are the main window's, lent to it while it draws.
+pub struct ProfileWindow {
+    id: egui::ViewportId,
+    uploader: ClaudeUploader,
+}
+
+impl ProfileWindow {
+    pub fn new(id: egui::ViewportId, uploader: ClaudeUploader) -> Self {
+        Self { id, uploader }
+    }
+
+    pub fn cancel_run(&mut self) {
+        self.uploader.cancel_run();
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.uploader.has_running_work()
+    }
+
+    /// Records what the window's run got done before the app closes.
+    pub fn abandon_run(&mut self, main: &mut ClaudeUploader) {
+        main.lend_stores(&mut self.uploader, ClaudeUploader::abandon_run);
+    }
+
+    /// Draws the window; returns false once the user closed it.
+    pub fn show(&mut self, ctx: &egui::Context, main: &mut ClaudeUploader) -> bool {
+        let mut open = true;
+        let builder = egui::ViewportBuilder::default()
+            .with_title(self.uploader.window_title.clone())
+            .with_inner_size([600.0, 600.0])
+            .with_min_inner_size([400.0, 500.0]);
+
+        ctx.show_viewport_immediate(self.id, builder, |ctx, _class| {
+            main.lend_stores(&mut self.uploader, |uploader| uploader.frame(ctx));
+            if ctx.input(|input| input.viewport().close_requested()) {
+                open = false;
+            }
+        });
+
+        if !open {
+            println!(
+                "Closing the window of profile '{}'",
+                self.uploader.profile_key()
+            );
+            // Nobody would see the rest of the run
+            self.uploader.cancel_run();
+        }
+        open
+    }
+}
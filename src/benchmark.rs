@@ -0,0 +1,148 @@
+//! Hidden `benchmark` command used to pick sensible pacing defaults. It
+//! uploads synthetic docs to a scratch project one at a time, like a run
+//! does, once per pause between requests. Then it reports throughput and
+//! deletes the docs again with the given number of parallel deletes.
+//!
+//! Usage: `claude_uploader benchmark <curl-file> [file-count] [pauses-ms...]
+//! [--delete-concurrency N] [--retries N]`
+
+use crate::cli::BenchmarkArgs;
+use crate::upload::{ClaudeClient, PacingPolicy, RateLimiter};
+use crate::utils::curl_parser::CurlParser;
+use std::collections::VecDeque;
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const SYNTHETIC_FILE_SIZE: usize = 8 * 1024;
+
+struct LevelResult {
+    upload_elapsed: Duration,
+    delete_elapsed: Duration,
+    uploaded: usize,
+    failed: usize,
+}
+
+pub fn run(args: BenchmarkArgs) -> Result<(), String> {
+    let curl_text = fs::read_to_string(&args.curl_file)
+        .map_err(|e| format!("Failed to read {}: {}", args.curl_file.display(), e))?;
+
+    let mut parser = CurlParser::new();
+    parser.parse(&curl_text)?;
+
+    let BenchmarkArgs {
+        file_count,
+        pauses,
+        delete_concurrency,
+        retries,
+        ..
+    } = args;
+
+    let rate_limiter = RateLimiter::default();
+    let client = ClaudeClient::new(
+        parser.organization_id.unwrap(),
+        parser.project_id.unwrap(),
+        parser.headers.unwrap(),
+    )
+    .with_rate_limiter(rate_limiter.clone());
+    let rt = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+
+    println!(
+        "Uploading {} synthetic docs of {} bytes per pause. Use a scratch project!",
+        file_count, SYNTHETIC_FILE_SIZE
+    );
+    println!(
+        "{:>8} {:>9} {:>9} {:>8} {:>10}",
+        "pause ms", "seconds", "files/s", "failed", "deletes/s"
+    );
+
+    for pause in pauses {
+        rate_limiter.configure(PacingPolicy {
+            request_interval: Duration::from_millis(pause),
+            delete_concurrency: delete_concurrency.max(1),
+            rate_limit_retries: retries,
+        });
+        let result = rt.block_on(benchmark_level(
+            &client,
+            file_count,
+            pause,
+            delete_concurrency.max(1),
+        ));
+        let seconds = result.upload_elapsed.as_secs_f64();
+        println!(
+            "{:>8} {:>9.2} {:>9.2} {:>8} {:>10.2}",
+            pause,
+            seconds,
+            result.uploaded as f64 / seconds.max(f64::EPSILON),
+            result.failed,
+            result.uploaded as f64 / result.delete_elapsed.as_secs_f64().max(f64::EPSILON)
+        );
+    }
+
+    Ok(())
+}
+
+/// Uploads one at a time through the client's rate limiter, then deletes
+/// with `delete_concurrency` workers sharing it, as runs do.
+async fn benchmark_level(
+    client: &ClaudeClient,
+    file_count: usize,
+    pause: u64,
+    delete_concurrency: usize,
+) -> LevelResult {
+    let mut uploaded = Vec::new();
+    let mut failed = 0;
+
+    let started = Instant::now();
+    for index in 0..file_count {
+        let file_name = format!("benchmark-{}ms-{}.txt", pause, index);
+        match client
+            .upload_doc(&file_name, synthetic_content(index))
+            .await
+        {
+            Ok(uuid) => uploaded.push(uuid),
+            Err(error) => {
+                println!("Upload of {} failed: {}", file_name, error);
+                failed += 1;
+            }
+        }
+    }
+    let upload_elapsed = started.elapsed();
+
+    // Leave the scratch project empty for the next level
+    let count = uploaded.len();
+    let queue = Arc::new(Mutex::new(VecDeque::from(uploaded)));
+    let started = Instant::now();
+    let workers: Vec<_> = (0..delete_concurrency)
+        .map(|_| {
+            let client = client.clone();
+            let queue = Arc::clone(&queue);
+            tokio::spawn(async move {
+                loop {
+                    let next = queue.lock().unwrap().pop_front();
+                    let Some(uuid) = next else {
+                        break;
+                    };
+                    if let Err(error) = client.delete_doc(&uuid).await {
+                        println!("Failed to clean up doc {}: {}", uuid, error);
+                    }
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        let _ = worker.await;
+    }
+
+    LevelResult {
+        upload_elapsed,
+        delete_elapsed: started.elapsed(),
+        uploaded: count,
+        failed,
+    }
+}
+
+fn synthetic_content(seed: usize) -> String {
+    let line = format!("synthetic benchmark line {}\n", seed);
+    line.repeat(SYNTHETIC_FILE_SIZE / line.len() + 1)
+}
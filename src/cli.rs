@@ -0,0 +1,79 @@
+//! Command line of the app. Without a subcommand the window opens; the
+//! subcommands run without it, for scripts and CI.
+
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(
+    name = "claude_uploader",
+    version,
+    about = "Upload folders to Claude.ai projects",
+    args_conflicts_with_subcommands = true
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Folder to open in the window, or in the window already running
+    #[arg(long)]
+    pub folder: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Upload a folder to the project of a copied curl request
+    Upload(UploadArgs),
+    /// Run the selection through the pipeline into a local directory
+    Export(ExportArgs),
+    /// Measure upload throughput at several pauses between requests
+    #[command(hide = true)]
+    Benchmark(BenchmarkArgs),
+}
+
+/// Which files of a folder to process.
+#[derive(Args)]
+pub struct Selection {
+    /// Folder to upload; `.claudekeep` applies with all its sections
+    #[arg(long)]
+    pub folder: PathBuf,
+    /// File listing the paths to upload, one per line relative to the
+    /// folder, or `-` for stdin
+    #[arg(long, value_name = "LIST_FILE")]
+    pub files: Option<String>,
+}
+
+#[derive(Args)]
+pub struct UploadArgs {
+    /// File holding the curl command copied from the project's docs request
+    #[arg(long)]
+    pub curl_file: PathBuf,
+    #[command(flatten)]
+    pub selection: Selection,
+}
+
+#[derive(Args)]
+pub struct ExportArgs {
+    /// Directory the docs are written to
+    #[arg(long)]
+    pub target: PathBuf,
+    #[command(flatten)]
+    pub selection: Selection,
+}
+
+#[derive(Args)]
+pub struct BenchmarkArgs {
+    /// File holding the curl command of a scratch project
+    pub curl_file: PathBuf,
+    /// Synthetic docs uploaded per pause
+    #[arg(default_value_t = 20)]
+    pub file_count: usize,
+    /// Pauses between requests to measure, in milliseconds
+    #[arg(default_values_t = [0, 100, 250, 500])]
+    pub pauses: Vec<u64>,
+    /// Parallel deletes when cleaning up after each pause
+    #[arg(long, default_value_t = 2)]
+    pub delete_concurrency: usize,
+    /// Retries when rate limited
+    #[arg(long, default_value_t = 5)]
+    pub retries: u32,
+}
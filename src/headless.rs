@@ -0,0 +1,198 @@
+//! `upload` command for scripts and CI: uploads a folder, or an explicit
+//! list of paths below it, without opening the window.
+//!
+//! Usage: `claude_uploader upload --curl-file <file> --folder <folder> [--files <list-file>|-]`
+//!
+//! `export --target <dir> --folder <folder> [--files ...]` runs the same
+//! selection through the pipeline but writes the docs into a local directory.
+//!
+//! With `--files -` the list is read from stdin, e.g.
+//! `git diff --name-only | claude_uploader upload --curl-file curl.txt --folder . --files -`.
+//! Exits with 0 when every file was uploaded or skipped, 1 when any failed
+//! and 2 when the run could not start.
+
+use crate::app::Settings;
+use crate::cli::{ExportArgs, Selection, UploadArgs};
+use crate::upload::{
+    ClaudeClient, DirectoryBackend, DiscoveredFile, DiscoveryScan, FileList, FileProcessor,
+    FilterOptions, PathFilter, PlanPolicy, ProjectBackend, RateLimiter, RunControl, UploadStatus,
+};
+use crate::utils::claude_keep::ClaudeKeepConfig;
+use crate::utils::curl_parser::CurlParser;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::Duration;
+
+const DISCOVERY_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+const EXIT_FAILED_FILES: i32 = 1;
+const EXIT_NOT_STARTED: i32 = 2;
+
+/// Runs the upload and returns the process exit code.
+pub fn run(args: UploadArgs) -> i32 {
+    exit_code(upload(args))
+}
+
+/// Runs the export and returns the process exit code.
+pub fn run_export(args: ExportArgs) -> i32 {
+    exit_code(export(args))
+}
+
+fn exit_code(result: Result<usize, String>) -> i32 {
+    match result {
+        Ok(0) => 0,
+        Ok(failed) => {
+            eprintln!("{} files failed to upload", failed);
+            EXIT_FAILED_FILES
+        }
+        Err(error) => {
+            eprintln!("{}", error);
+            EXIT_NOT_STARTED
+        }
+    }
+}
+
+/// Uploads the selected files and returns how many failed.
+fn upload(args: UploadArgs) -> Result<usize, String> {
+    let curl_text = fs::read_to_string(&args.curl_file)
+        .map_err(|e| format!("Failed to read {}: {}", args.curl_file.display(), e))?;
+    let mut parser = CurlParser::new();
+    parser.parse(&curl_text)?;
+    if PlanPolicy::load().is_required() {
+        return Err("Uploads require an approved plan, load one in the window".to_string());
+    }
+    let files = select_files(&args.selection)?;
+    println!("Uploading {} files", files.len());
+
+    let settings = Settings::default();
+    let rate_limiter = RateLimiter::default();
+    rate_limiter.configure(settings.pacing_policy());
+    let client = ClaudeClient::new(
+        parser.organization_id.unwrap(),
+        parser.project_id.unwrap(),
+        parser.headers.unwrap(),
+    )
+    .with_rate_limiter(rate_limiter);
+    Ok(process(client, files))
+}
+
+/// Writes the selected files into a directory and returns how many failed.
+fn export(args: ExportArgs) -> Result<usize, String> {
+    let files = select_files(&args.selection)?;
+    println!(
+        "Exporting {} files to {}",
+        files.len(),
+        args.target.display()
+    );
+
+    Ok(process(DirectoryBackend::new(args.target), files))
+}
+
+fn select_files(selection: &Selection) -> Result<Vec<DiscoveredFile>, String> {
+    let folder = &selection.folder;
+    if !folder.is_dir() {
+        return Err(format!("{} is not a folder", folder.display()));
+    }
+    let files = match &selection.files {
+        Some(source) => listed_files(folder, source)?,
+        None => discovered_files(folder),
+    };
+    if files.is_empty() {
+        return Err("No files to upload".to_string());
+    }
+    Ok(files)
+}
+
+/// Runs the files through the pipeline into `backend` and returns how many
+/// failed.
+fn process<B: ProjectBackend + 'static>(backend: B, files: Vec<DiscoveredFile>) -> usize {
+    let settings = Settings::default();
+    let control = RunControl::new();
+    let mut processor =
+        FileProcessor::new(backend, control.clone()).with_retry_policy(settings.retry_policy());
+    let preflight = settings.preflight_policy();
+
+    let (status_sender, status_receiver) = mpsc::channel();
+    let worker = std::thread::spawn(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            if let Err(error) = processor.check_session(preflight, &status_sender).await {
+                println!("Session check failed: {}", error);
+                processor.fail_all(&files, &error, &status_sender);
+                return;
+            }
+            processor.process_files(&files, &status_sender).await;
+        });
+    });
+
+    let mut failed = 0;
+    for status in status_receiver {
+        match &status.status {
+            UploadStatus::Success => match &status.warning {
+                Some(warning) => println!("⚠ {} - {}", status.display_name(), warning),
+                None => println!("✅ {}", status.display_name()),
+            },
+            UploadStatus::Skipped(reason) => println!("⏩ {} - {}", status.display_name(), reason),
+            UploadStatus::Error(error) => {
+                failed += 1;
+                println!("❌ {} - {}", status.display_name(), error);
+            }
+            // Nobody can paste a fresh session, so the run ends here
+            UploadStatus::WaitingForAuth => {
+                println!("🔑 Session expired, stopping the run");
+                control.stop();
+            }
+            UploadStatus::WaitingForNetwork => println!("📡 Offline, waiting for connection..."),
+            UploadStatus::Retrying {
+                attempt,
+                max_attempts,
+            } => println!(
+                "🔁 {} - Retrying {}/{}",
+                status.display_name(),
+                attempt,
+                max_attempts
+            ),
+            _ => {}
+        }
+    }
+    let _ = worker.join();
+
+    failed
+}
+
+fn listed_files(folder: &Path, source: &str) -> Result<Vec<DiscoveredFile>, String> {
+    let text = if source == "-" {
+        let mut text = String::new();
+        io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|e| format!("Failed to read the file list from stdin: {}", e))?;
+        text
+    } else {
+        fs::read_to_string(source).map_err(|e| format!("Failed to read {}: {}", source, e))?
+    };
+
+    let list = FileList::resolve(folder, &text);
+    for path in &list.missing {
+        println!("⏩ {} - Not a file in the folder", path);
+    }
+    Ok(list.files)
+}
+
+/// All files the window would pick up with every section and no extra rules.
+fn discovered_files(folder: &Path) -> Vec<DiscoveredFile> {
+    let path_filter = PathFilter::new(
+        folder.to_path_buf(),
+        ClaudeKeepConfig::from_file(folder),
+        Vec::new(),
+        FilterOptions::default(),
+    );
+    let mut scan = DiscoveryScan::start(path_filter);
+    loop {
+        if let Some(result) = scan.poll() {
+            return result.files;
+        }
+        std::thread::sleep(DISCOVERY_POLL_INTERVAL);
+    }
+}
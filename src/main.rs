@@ -1,20 +1,67 @@
 mod app;
+mod benchmark;
+mod cli;
+mod headless;
 mod upload;
 mod utils;
 
-use app::ClaudeUploader;
+use app::{ClaudeUploader, APP_TITLE};
+use clap::Parser;
+use cli::{Cli, Command};
+use utils::single_instance::{Claim, HandOff, SingleInstance};
 
 fn main() -> Result<(), eframe::Error> {
+    let cli = Cli::parse();
+    match cli.command {
+        Some(Command::Benchmark(args)) => {
+            if let Err(error) = benchmark::run(args) {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Command::Upload(args)) => std::process::exit(headless::run(args)),
+        Some(Command::Export(args)) => std::process::exit(headless::run_export(args)),
+        None => {}
+    }
+
+    let request = match &cli.folder {
+        // The running instance may have another working directory
+        Some(folder) => HandOff::OpenFolder(folder.canonicalize().unwrap_or(folder.clone())),
+        None => HandOff::Focus,
+    };
+    let instance = match SingleInstance::claim(&request) {
+        Claim::Primary(instance) => Some(instance),
+        Claim::HandedOff => {
+            println!("Handed over to the window already running");
+            return Ok(());
+        }
+        Claim::Unguarded => None,
+    };
+
     let options = eframe::NativeOptions {
         viewport: eframe::egui::ViewportBuilder::default()
             .with_inner_size([600.0, 600.0])
             .with_min_inner_size([400.0, 500.0]),
+        // The app restores the geometry itself, eframe's would keep the
+        // compact size when quitting in compact mode
+        persist_window: false,
         ..Default::default()
     };
 
     eframe::run_native(
-        "Claude.ai File Uploader",
+        APP_TITLE,
         options,
-        Box::new(|cc| Box::new(ClaudeUploader::new(cc))),
+        Box::new(move |cc| {
+            let mut uploader = ClaudeUploader::new(cc);
+            if let Some(instance) = instance {
+                let ctx = cc.egui_ctx.clone();
+                uploader = uploader.with_hand_offs(instance.serve(move || ctx.request_repaint()));
+            }
+            if let HandOff::OpenFolder(folder) = request {
+                uploader.open_folder(folder);
+            }
+            Box::new(uploader)
+        }),
     )
 }
@@ -0,0 +1,99 @@
+use crate::upload::content_cache::{ContentCache, ContentSummary};
+use crate::upload::types::DiscoveredFile;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+
+enum AnalysisEvent {
+    Analyzed(PathBuf, ContentSummary),
+    Finished,
+}
+
+/// Computes hashes and token estimates for the discovered files on a
+/// background thread, reusing the profile's content cache for files that
+/// did not change. Dropping the handle cancels the analysis.
+pub struct ContentAnalysis {
+    pub total: usize,
+    pub summaries: HashMap<PathBuf, ContentSummary>,
+    pub finished: bool,
+    cancelled: Arc<AtomicBool>,
+    receiver: Receiver<AnalysisEvent>,
+}
+
+impl ContentAnalysis {
+    pub fn start(files: Vec<DiscoveredFile>, profile: String) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let thread_cancelled = cancelled.clone();
+        let total = files.len();
+
+        std::thread::spawn(move || {
+            let mut cache = ContentCache::load(&profile);
+            let mut computed = 0;
+
+            for file in &files {
+                if thread_cancelled.load(Ordering::Relaxed) {
+                    return;
+                }
+
+                let summary = match cache.get(file) {
+                    Some(summary) => summary.clone(),
+                    None => match ContentSummary::of_file(&file.path) {
+                        Ok(summary) => {
+                            computed += 1;
+                            cache.insert(file, summary.clone());
+                            summary
+                        }
+                        Err(_) => continue,
+                    },
+                };
+
+                if sender
+                    .send(AnalysisEvent::Analyzed(file.path.clone(), summary))
+                    .is_err()
+                {
+                    return;
+                }
+            }
+
+            println!(
+                "Analyzed {} files, {} from cache",
+                files.len(),
+                files.len() - computed
+            );
+            cache.save(&profile);
+            let _ = sender.send(AnalysisEvent::Finished);
+        });
+
+        Self {
+            total,
+            summaries: HashMap::new(),
+            finished: false,
+            cancelled,
+            receiver,
+        }
+    }
+
+    pub fn poll(&mut self) {
+        while let Ok(event) = self.receiver.try_recv() {
+            match event {
+                AnalysisEvent::Analyzed(path, summary) => {
+                    self.summaries.insert(path, summary);
+                }
+                AnalysisEvent::Finished => self.finished = true,
+            }
+        }
+    }
+
+    pub fn total_tokens(&self) -> usize {
+        self.summaries.values().map(|summary| summary.tokens).sum()
+    }
+}
+
+impl Drop for ContentAnalysis {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::upload::client::ClaudeClient;
+use crate::upload::types::{ErrorKind, RemoteDoc, UploadError};
+use reqwest::header::HeaderMap;
+use std::fs;
+use std::future::Future;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Where uploaded docs end up. The upload pipeline only talks to this, so
+/// the same selection can be sent to a claude.ai project or elsewhere.
+pub trait ProjectBackend: Send + Sync {
+    /// Cheap check that the destination can be written, run before a run.
+    fn check_access(&self) -> impl Future<Output = Result<(), UploadError>> + Send;
+
+    fn list_docs(&self) -> impl Future<Output = Result<Vec<RemoteDoc>, UploadError>> + Send;
+
+    /// Stores a doc and returns its uuid.
+    fn upload_doc(
+        &self,
+        file_name: &str,
+        content: String,
+    ) -> impl Future<Output = Result<String, UploadError>> + Send;
+
+    fn delete_doc(&self, uuid: &str) -> impl Future<Output = Result<(), UploadError>> + Send;
+
+    /// Fresh credentials after the session expired mid-run. Destinations
+    /// without a session ignore them.
+    fn set_headers(&mut self, _headers: HeaderMap) {}
+}
+
+impl ProjectBackend for ClaudeClient {
+    fn check_access(&self) -> impl Future<Output = Result<(), UploadError>> + Send {
+        ClaudeClient::check_access(self)
+    }
+
+    fn list_docs(&self) -> impl Future<Output = Result<Vec<RemoteDoc>, UploadError>> + Send {
+        ClaudeClient::list_docs(self)
+    }
+
+    fn upload_doc(
+        &self,
+        file_name: &str,
+        content: String,
+    ) -> impl Future<Output = Result<String, UploadError>> + Send {
+        ClaudeClient::upload_doc(self, file_name, content)
+    }
+
+    fn delete_doc(&self, uuid: &str) -> impl Future<Output = Result<(), UploadError>> + Send {
+        ClaudeClient::delete_doc(self, uuid)
+    }
+
+    fn set_headers(&mut self, headers: HeaderMap) {
+        ClaudeClient::set_headers(self, headers);
+    }
+}
+
+/// Writes docs as files into a local directory instead of a project, e.g.
+/// to review exactly what a run would send. A doc's uuid is its file name.
+#[derive(Debug, Clone)]
+pub struct DirectoryBackend {
+    dir: PathBuf,
+}
+
+impl DirectoryBackend {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Doc names never leave the directory, and a name already taken gets a
+    /// numbered suffix, as two files may share a name.
+    fn free_path(&self, file_name: &str) -> Option<PathBuf> {
+        let name = Path::new(file_name).file_name()?;
+        let candidate = self.dir.join(name);
+        if !candidate.exists() {
+            return Some(candidate);
+        }
+
+        let name = Path::new(name);
+        let stem = name.file_stem()?.to_string_lossy();
+        let extension = name
+            .extension()
+            .map(|extension| format!(".{}", extension.to_string_lossy()))
+            .unwrap_or_default();
+        (2..)
+            .map(|index| self.dir.join(format!("{} ({}){}", stem, index, extension)))
+            .find(|candidate| !candidate.exists())
+    }
+}
+
+impl ProjectBackend for DirectoryBackend {
+    async fn check_access(&self) -> Result<(), UploadError> {
+        fs::create_dir_all(&self.dir).map_err(|e| io_error("Failed to create directory", e))
+    }
+
+    async fn list_docs(&self) -> Result<Vec<RemoteDoc>, UploadError> {
+        let entries =
+            fs::read_dir(&self.dir).map_err(|e| io_error("Failed to list directory", e))?;
+        let mut docs: Vec<RemoteDoc> = entries
+            .flatten()
+            .filter(|entry| entry.path().is_file())
+            .map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                RemoteDoc {
+                    uuid: name.clone(),
+                    file_name: name,
+                    content: fs::read_to_string(entry.path()).ok(),
+                    created_at: None,
+                }
+            })
+            .collect();
+        docs.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+        Ok(docs)
+    }
+
+    async fn upload_doc(&self, file_name: &str, content: String) -> Result<String, UploadError> {
+        let path = self.free_path(file_name).ok_or_else(|| {
+            UploadError::new(ErrorKind::Other, format!("Invalid doc name: {}", file_name))
+        })?;
+        fs::write(&path, content).map_err(|e| io_error("Failed to write doc", e))?;
+        Ok(path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string())
+    }
+
+    async fn delete_doc(&self, uuid: &str) -> Result<(), UploadError> {
+        let Some(name) = Path::new(uuid).file_name() else {
+            return Err(UploadError::new(
+                ErrorKind::Other,
+                format!("Invalid doc name: {}", uuid),
+            ));
+        };
+        fs::remove_file(self.dir.join(name)).map_err(|e| io_error("Failed to delete doc", e))
+    }
+}
+
+fn io_error(context: &str, e: io::Error) -> UploadError {
+    UploadError::new(ErrorKind::Other, format!("{}: {}", context, e))
+}
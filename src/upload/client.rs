@@ -0,0 +1,549 @@
+use crate::upload::connectivity;
+use crate::upload::rate_limit::RateLimiter;
+use crate::upload::recording::{Exchange, Fixtures};
+use crate::upload::types::{
+    ErrorKind, Permissions, RemoteDoc, RemoteOrganization, RemoteProject, UploadError,
+};
+use reqwest::header::HeaderMap;
+use reqwest::{Method, StatusCode};
+use serde::Deserialize;
+use serde_json::json;
+
+const API_BASE: &str = "https://claude.ai/api";
+
+/// Where a project lives in the API. Accounts with other URL shapes, e.g. an
+/// extra workspace segment, configure their own template.
+pub const DEFAULT_PROJECT_URL: &str =
+    "https://claude.ai/api/organizations/{organization}/projects/{project}";
+
+#[derive(Deserialize)]
+struct UploadResponse {
+    uuid: String,
+}
+
+#[derive(Deserialize)]
+struct ProjectResponse {
+    description: Option<String>,
+}
+
+/// Thin wrapper around the claude.ai project docs API.
+#[derive(Clone)]
+pub struct ClaudeClient {
+    http: reqwest::Client,
+    organization_id: String,
+    project_id: String,
+    headers: HeaderMap,
+    project_url: String,
+    permissions: Permissions,
+    /// Organization the session's profile was created for.
+    expected_organization: Option<String>,
+    rate_limiter: RateLimiter,
+    fixtures: Option<Fixtures>,
+}
+
+impl ClaudeClient {
+    pub fn new(organization_id: String, project_id: String, headers: HeaderMap) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            organization_id,
+            project_id,
+            headers,
+            project_url: DEFAULT_PROJECT_URL.to_string(),
+            permissions: Permissions::ALL,
+            expected_organization: None,
+            rate_limiter: RateLimiter::default(),
+            fixtures: Fixtures::from_env(),
+        }
+    }
+
+    /// Refuses requests that would modify the project beyond `permissions`.
+    /// Enforced here rather than in the UI so no code path can upload or
+    /// delete by accident.
+    pub fn with_permissions(mut self, permissions: Permissions) -> Self {
+        self.permissions = permissions;
+        self
+    }
+
+    /// Refuses requests to any organization but `organization_id`, the one
+    /// the session's profile was created for.
+    pub fn with_expected_organization(mut self, organization_id: Option<String>) -> Self {
+        self.expected_organization = organization_id;
+        self
+    }
+
+    /// Answers requests from `fixtures` instead of the environment's.
+    #[cfg(test)]
+    pub fn with_fixtures(mut self, fixtures: Fixtures) -> Self {
+        self.fixtures = Some(fixtures);
+        self
+    }
+
+    /// Paces uploads and deletes through `rate_limiter`, which may be shared
+    /// with other clients.
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    /// Builds request URLs from `template`, with `{organization}` and
+    /// `{project}` standing for the ids. An empty template keeps the default.
+    pub fn with_project_url(mut self, template: &str) -> Self {
+        let template = template.trim().trim_end_matches('/');
+        if !template.is_empty() {
+            self.project_url = template.to_string();
+        }
+        self
+    }
+
+    fn ensure_allowed(&self, allowed: bool, action: &str) -> Result<(), UploadError> {
+        if !allowed {
+            return Err(UploadError::new(
+                ErrorKind::NotPermitted,
+                format!("Refusing to {}: not permitted for this session", action),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Refuses to send a profile's session to an organization other than
+    /// the one the profile was created for. The session's own cookies can't
+    /// tell, switching organizations rewrites them. Sessions without a
+    /// profile organization are let through.
+    fn ensure_same_organization(&self) -> Result<(), UploadError> {
+        match &self.expected_organization {
+            Some(expected) if *expected != self.organization_id => Err(UploadError::new(
+                ErrorKind::NotPermitted,
+                format!(
+                    "Refusing to send the session of organization {} to organization {}",
+                    expected, self.organization_id
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn set_headers(&mut self, headers: HeaderMap) {
+        self.headers = headers;
+    }
+
+    fn project_url(&self) -> String {
+        self.project_url
+            .replace("{organization}", &self.organization_id)
+            .replace("{project}", &self.project_id)
+    }
+
+    fn docs_url(&self) -> String {
+        format!("{}/docs", self.project_url())
+    }
+
+    /// The project URL up to the organization segment, which lists the
+    /// account's organizations.
+    fn organizations_url(&self) -> String {
+        match self.project_url.rsplit_once("/{organization}") {
+            Some((base, _)) => base.to_string(),
+            None => format!("{}/organizations", API_BASE),
+        }
+    }
+
+    /// The project URL without its project segment, which lists the
+    /// organization's projects.
+    fn projects_url(&self) -> String {
+        match self.project_url.rsplit_once("/{project}") {
+            Some((base, _)) => base.replace("{organization}", &self.organization_id),
+            None => format!(
+                "{}/organizations/{}/projects",
+                API_BASE, self.organization_id
+            ),
+        }
+    }
+
+    pub async fn get_project_description(&self) -> Result<String, UploadError> {
+        let response = self
+            .send(
+                Method::GET,
+                &self.project_url(),
+                None,
+                "Failed to fetch project",
+            )
+            .await?;
+
+        if !response.status.is_success() {
+            return Err(UploadError::new(
+                ErrorKind::from_status(response.status.as_u16()),
+                format!("Fetching project failed with status: {}", response.status),
+            ));
+        }
+
+        serde_json::from_str::<ProjectResponse>(&response.body)
+            .map(|project| project.description.unwrap_or_default())
+            .map_err(|e| {
+                UploadError::new(ErrorKind::Other, format!("Failed to parse project: {}", e))
+            })
+    }
+
+    pub async fn update_project_description(&self, description: &str) -> Result<(), UploadError> {
+        self.ensure_allowed(self.permissions.upload, "update the project description")?;
+
+        let payload = json!({ "description": description });
+        let response = self
+            .send(
+                Method::PATCH,
+                &self.project_url(),
+                Some(&payload),
+                "Failed to update project",
+            )
+            .await?;
+
+        if response.status.is_success() {
+            Ok(())
+        } else {
+            Err(UploadError::new(
+                ErrorKind::from_status(response.status.as_u16()),
+                format!("Updating project failed with status: {}", response.status),
+            ))
+        }
+    }
+
+    pub async fn list_organizations(&self) -> Result<Vec<RemoteOrganization>, UploadError> {
+        let response = self
+            .send(
+                Method::GET,
+                &self.organizations_url(),
+                None,
+                "Failed to list organizations",
+            )
+            .await?;
+
+        if !response.status.is_success() {
+            return Err(UploadError::new(
+                ErrorKind::from_status(response.status.as_u16()),
+                format!(
+                    "Listing organizations failed with status: {}",
+                    response.status
+                ),
+            ));
+        }
+
+        serde_json::from_str::<Vec<RemoteOrganization>>(&response.body).map_err(|e| {
+            UploadError::new(
+                ErrorKind::Other,
+                format!("Failed to parse organizations listing: {}", e),
+            )
+        })
+    }
+
+    /// Projects of the organization, without archived ones.
+    pub async fn list_projects(&self) -> Result<Vec<RemoteProject>, UploadError> {
+        let response = self
+            .send(
+                Method::GET,
+                &self.projects_url(),
+                None,
+                "Failed to list projects",
+            )
+            .await?;
+
+        if !response.status.is_success() {
+            return Err(UploadError::new(
+                ErrorKind::from_status(response.status.as_u16()),
+                format!("Listing projects failed with status: {}", response.status),
+            ));
+        }
+
+        serde_json::from_str::<Vec<RemoteProject>>(&response.body)
+            .map(|projects| {
+                projects
+                    .into_iter()
+                    .filter(|project| project.archived_at.is_none())
+                    .collect()
+            })
+            .map_err(|e| {
+                UploadError::new(
+                    ErrorKind::Other,
+                    format!("Failed to parse projects listing: {}", e),
+                )
+            })
+    }
+
+    pub async fn list_docs(&self) -> Result<Vec<RemoteDoc>, UploadError> {
+        let response = self
+            .send(Method::GET, &self.docs_url(), None, "Failed to list docs")
+            .await?;
+
+        if !response.status.is_success() {
+            return Err(UploadError::new(
+                ErrorKind::from_status(response.status.as_u16()),
+                format!("Listing docs failed with status: {}", response.status),
+            ));
+        }
+
+        serde_json::from_str::<Vec<RemoteDoc>>(&response.body).map_err(|e| {
+            UploadError::new(
+                ErrorKind::Other,
+                format!("Failed to parse docs listing: {}", e),
+            )
+        })
+    }
+
+    pub async fn get_doc(&self, uuid: &str) -> Result<RemoteDoc, UploadError> {
+        let url = format!("{}/{}", self.docs_url(), uuid);
+        let response = self
+            .send(Method::GET, &url, None, "Failed to fetch doc")
+            .await?;
+
+        if !response.status.is_success() {
+            return Err(UploadError::new(
+                ErrorKind::from_status(response.status.as_u16()),
+                format!("Fetching doc failed with status: {}", response.status),
+            ));
+        }
+
+        serde_json::from_str::<RemoteDoc>(&response.body)
+            .map_err(|e| UploadError::new(ErrorKind::Other, format!("Failed to parse doc: {}", e)))
+    }
+
+    /// Cheap read-only request used to verify the session before a run.
+    pub async fn check_access(&self) -> Result<(), UploadError> {
+        let response = self
+            .send(
+                Method::GET,
+                &self.docs_url(),
+                None,
+                "Failed to reach claude.ai",
+            )
+            .await?;
+
+        if response.status.is_success() {
+            Ok(())
+        } else {
+            Err(UploadError::new(
+                ErrorKind::from_status(response.status.as_u16()),
+                format!("Session check failed with status: {}", response.status),
+            ))
+        }
+    }
+
+    /// Creates a doc and returns its uuid.
+    pub async fn upload_doc(
+        &self,
+        file_name: &str,
+        content: String,
+    ) -> Result<String, UploadError> {
+        self.ensure_allowed(self.permissions.upload, "upload")?;
+
+        let payload = json!({
+            "file_name": file_name,
+            "content": content
+        });
+
+        self.rate_limiter.run(|| self.send_upload(&payload)).await
+    }
+
+    async fn send_upload(&self, payload: &serde_json::Value) -> Result<String, UploadError> {
+        let response = self
+            .send(
+                Method::POST,
+                &self.docs_url(),
+                Some(payload),
+                "Failed to send request",
+            )
+            .await?;
+
+        match response.status.as_u16() {
+            200 | 201 => {
+                let upload_response = serde_json::from_str::<UploadResponse>(&response.body)
+                    .map_err(|e| {
+                        UploadError::new(
+                            ErrorKind::Other,
+                            format!("Failed to parse upload response: {}", e),
+                        )
+                    })?;
+                Ok(upload_response.uuid)
+            }
+            status_code => Err(UploadError::new(
+                ErrorKind::from_status(status_code),
+                format!("Upload failed with status: {}", status_code),
+            )),
+        }
+    }
+
+    pub async fn delete_doc(&self, uuid: &str) -> Result<(), UploadError> {
+        self.ensure_allowed(self.permissions.delete, "delete")?;
+
+        let url = format!("{}/{}", self.docs_url(), uuid);
+
+        self.rate_limiter.run(|| self.send_delete(&url)).await
+    }
+
+    async fn send_delete(&self, url: &str) -> Result<(), UploadError> {
+        let response = self
+            .send(Method::DELETE, url, None, "Failed to send delete request")
+            .await?;
+
+        if response.status.is_success() {
+            Ok(())
+        } else {
+            Err(UploadError::new(
+                ErrorKind::from_status(response.status.as_u16()),
+                format!("Failed to delete with status: {}", response.status),
+            ))
+        }
+    }
+
+    /// Sends a request, or answers it from the replayed fixtures. Recorded
+    /// exchanges go through [`Self::redact`] first.
+    async fn send(
+        &self,
+        method: Method,
+        url: &str,
+        body: Option<&serde_json::Value>,
+        context: &str,
+    ) -> Result<ApiResponse, UploadError> {
+        let path = self.redact(url.strip_prefix(API_BASE).unwrap_or(url));
+
+        if let Some(Fixtures::Replay(replay)) = &self.fixtures {
+            let exchange = replay.next(method.as_str(), &path)?;
+            let status = StatusCode::from_u16(exchange.status).map_err(|e| {
+                UploadError::new(ErrorKind::Other, format!("Invalid recorded status: {}", e))
+            })?;
+            return Ok(ApiResponse {
+                status,
+                body: exchange.response_body,
+            });
+        }
+
+        self.ensure_same_organization()?;
+        let mut request = self
+            .http
+            .request(method.clone(), url)
+            .headers(self.headers.clone());
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| request_error(context, e))?;
+        let status = response.status();
+        let body_text = response
+            .text()
+            .await
+            .map_err(|e| request_error(context, e))?;
+
+        if let Some(Fixtures::Record(recorder)) = &self.fixtures {
+            recorder.record(&Exchange {
+                method: method.as_str().to_string(),
+                path,
+                request_body: body.map(|body| {
+                    serde_json::from_str(&self.redact(&body.to_string())).unwrap_or_default()
+                }),
+                status: status.as_u16(),
+                response_body: self.redact(&body_text),
+            });
+        }
+
+        Ok(ApiResponse {
+            status,
+            body: body_text,
+        })
+    }
+
+    /// Replaces the organization and project ids with placeholders.
+    fn redact(&self, text: &str) -> String {
+        text.replace(&self.organization_id, "{organization}")
+            .replace(&self.project_id, "{project}")
+    }
+}
+
+struct ApiResponse {
+    status: StatusCode,
+    body: String,
+}
+
+pub(super) fn request_error(context: &str, error: reqwest::Error) -> UploadError {
+    let kind = if connectivity::is_offline_error(&error) {
+        ErrorKind::Offline
+    } else {
+        ErrorKind::Network
+    };
+    UploadError::new(kind, format!("{}: {}", context, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client(organization_id: &str) -> ClaudeClient {
+        ClaudeClient::new(
+            organization_id.to_string(),
+            "project".to_string(),
+            HeaderMap::new(),
+        )
+    }
+
+    #[test]
+    fn sends_to_the_profile_organization() {
+        let client = client("org-a").with_expected_organization(Some("org-a".to_string()));
+        assert!(client.ensure_same_organization().is_ok());
+    }
+
+    #[test]
+    fn refuses_other_organizations() {
+        let client = client("org-b").with_expected_organization(Some("org-a".to_string()));
+        let error = client.ensure_same_organization().unwrap_err();
+        assert_eq!(error.kind, ErrorKind::NotPermitted);
+    }
+
+    #[test]
+    fn refuses_other_organizations_even_with_a_rewritten_cookie() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            reqwest::header::COOKIE,
+            "sessionKey=abc; lastActiveOrg=org-b".parse().unwrap(),
+        );
+        let client = ClaudeClient::new("org-b".to_string(), "project".to_string(), headers)
+            .with_expected_organization(Some("org-a".to_string()));
+        assert!(client.ensure_same_organization().is_err());
+    }
+
+    #[test]
+    fn sessions_without_a_profile_organization_are_let_through() {
+        assert!(client("org-b").ensure_same_organization().is_ok());
+    }
+
+    fn replay(fixture: &str) -> ClaudeClient {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/upload/fixtures")
+            .join(fixture);
+        let replay = crate::upload::recording::Replay::load(&dir).unwrap();
+        client("org-a").with_fixtures(Fixtures::Replay(std::sync::Arc::new(replay)))
+    }
+
+    #[test]
+    fn replays_an_upload_listing_and_delete() {
+        let client = replay("docs_round_trip");
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let uuid = client
+                .upload_doc("notes.md", "# Notes\n".to_string())
+                .await
+                .unwrap();
+            assert_eq!(uuid, "doc-1");
+
+            let docs = client.list_docs().await.unwrap();
+            assert_eq!(docs.len(), 1);
+            assert_eq!(docs[0].uuid, "doc-1");
+            assert_eq!(docs[0].file_name, "notes.md");
+
+            client.delete_doc(&uuid).await.unwrap();
+        });
+    }
+
+    #[test]
+    fn replay_refuses_requests_out_of_order() {
+        let client = replay("docs_round_trip");
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let error = runtime.block_on(client.list_docs()).unwrap_err();
+        assert!(error.message.contains("Expected GET"));
+    }
+}
@@ -0,0 +1,21 @@
+use std::time::Duration;
+
+const PROBE_URL: &str = "https://claude.ai";
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Returns true when claude.ai answers at all; any HTTP status counts as
+/// reachable since only the transport matters here.
+pub async fn is_reachable() -> bool {
+    let client = match reqwest::Client::builder().timeout(PROBE_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(_) => return false,
+    };
+
+    client.head(PROBE_URL).send().await.is_ok()
+}
+
+/// Whether a request failed because the network is unavailable, as opposed
+/// to the server rejecting it.
+pub fn is_offline_error(error: &reqwest::Error) -> bool {
+    error.is_connect() || error.is_timeout()
+}
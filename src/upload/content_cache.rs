@@ -0,0 +1,149 @@
+use crate::upload::manifest::to_hex;
+use crate::upload::types::DiscoveredFile;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContentSummary {
+    pub sha256: String,
+    pub tokens: usize,
+}
+
+impl ContentSummary {
+    /// Hashes and counts the file in fixed-size chunks, so memory stays flat
+    /// however large the file is. Like `fs::read_to_string`, fails with
+    /// `InvalidData` if the file is not valid UTF-8.
+    pub fn of_file(path: &Path) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0; CHUNK_SIZE];
+        // Bytes of a UTF-8 sequence split across two reads, kept at the front
+        let mut pending = 0;
+        let mut chars = 0;
+
+        loop {
+            let read = file.read(&mut buffer[pending..])?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[pending..pending + read]);
+
+            let filled = pending + read;
+            let valid = match std::str::from_utf8(&buffer[..filled]) {
+                Ok(_) => filled,
+                Err(e) if e.error_len().is_none() => e.valid_up_to(),
+                Err(_) => return Err(invalid_utf8()),
+            };
+            chars += count_chars(&buffer[..valid]);
+
+            buffer.copy_within(valid..filled, 0);
+            pending = filled - valid;
+        }
+
+        if pending > 0 {
+            return Err(invalid_utf8());
+        }
+
+        Ok(Self {
+            sha256: to_hex(&hasher.finalize()),
+            tokens: estimate_tokens(chars),
+        })
+    }
+}
+
+/// Rough token count, about four characters per token for typical source.
+pub fn estimate_tokens(chars: usize) -> usize {
+    (chars + 3) / 4
+}
+
+/// Number of characters in valid UTF-8, i.e. bytes that start a character.
+fn count_chars(bytes: &[u8]) -> usize {
+    bytes.iter().filter(|byte| (**byte & 0xC0) != 0x80).count()
+}
+
+fn invalid_utf8() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "stream did not contain valid UTF-8",
+    )
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    modified: Option<SystemTime>,
+    size: u64,
+    summary: ContentSummary,
+}
+
+/// Hashes and token estimates of previously analyzed files, stored per
+/// profile. An entry is only reused while the file's mtime and size match.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContentCache {
+    entries: HashMap<String, CacheEntry>,
+}
+
+impl ContentCache {
+    pub fn load(profile: &str) -> Self {
+        Self::path(profile)
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, profile: &str) {
+        let Some(path) = Self::path(profile) else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        match serde_json::to_string(self) {
+            Ok(json) => {
+                if let Err(e) = fs::write(&path, json) {
+                    println!("Failed to write content cache {}: {}", path.display(), e);
+                }
+            }
+            Err(e) => println!("Failed to serialize content cache: {}", e),
+        }
+    }
+
+    fn path(profile: &str) -> Option<PathBuf> {
+        let file_name: String = profile
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        dirs::cache_dir().map(|dir| {
+            dir.join("claude-uploader")
+                .join(format!("{}.json", file_name))
+        })
+    }
+
+    pub fn get(&self, file: &DiscoveredFile) -> Option<&ContentSummary> {
+        self.entries
+            .get(&Self::key(file))
+            .filter(|entry| entry.modified == file.modified && entry.size == file.size)
+            .map(|entry| &entry.summary)
+    }
+
+    pub fn insert(&mut self, file: &DiscoveredFile, summary: ContentSummary) {
+        self.entries.insert(
+            Self::key(file),
+            CacheEntry {
+                modified: file.modified,
+                size: file.size,
+                summary,
+            },
+        );
+    }
+
+    fn key(file: &DiscoveredFile) -> String {
+        file.path.display().to_string()
+    }
+}
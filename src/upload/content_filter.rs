@@ -0,0 +1,117 @@
+use regex::Regex;
+use std::collections::HashMap;
+use std::fs::{self, Metadata};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone)]
+struct CachedMatch {
+    pattern: String,
+    modified: Option<SystemTime>,
+    size: u64,
+    matched: bool,
+}
+
+#[derive(Debug, Default)]
+struct CachedMatches {
+    include: HashMap<PathBuf, CachedMatch>,
+    exclude: HashMap<PathBuf, CachedMatch>,
+}
+
+/// Whether a file's content matched the include and exclude patterns,
+/// remembered across discoveries so a rescan only reads files that changed
+/// or were last checked against another pattern. Clones share their entries.
+#[derive(Debug, Clone, Default)]
+pub struct ContentMatchCache {
+    matches: Arc<Mutex<CachedMatches>>,
+}
+
+impl ContentMatchCache {
+    fn is_match(&self, regex: &Regex, exclude: bool, path: &Path, metadata: &Metadata) -> bool {
+        let modified = metadata.modified().ok();
+        let size = metadata.len();
+        {
+            let matches = self.matches.lock().unwrap();
+            let entries = if exclude {
+                &matches.exclude
+            } else {
+                &matches.include
+            };
+            if let Some(cached) = entries.get(path) {
+                if cached.pattern == regex.as_str()
+                    && cached.modified == modified
+                    && cached.size == size
+                {
+                    return cached.matched;
+                }
+            }
+        }
+
+        // Files that aren't text never match
+        let matched = fs::read_to_string(path)
+            .map(|content| regex.is_match(&content))
+            .unwrap_or(false);
+        let entry = CachedMatch {
+            pattern: regex.as_str().to_string(),
+            modified,
+            size,
+            matched,
+        };
+        let mut matches = self.matches.lock().unwrap();
+        let entries = if exclude {
+            &mut matches.exclude
+        } else {
+            &mut matches.include
+        };
+        entries.insert(path.to_path_buf(), entry);
+        matched
+    }
+}
+
+/// Regexes a file's content has to match, or must not match, to be
+/// uploaded, e.g. `pub fn` or `DO NOT UPLOAD`.
+#[derive(Debug, Clone, Default)]
+pub struct ContentFilter {
+    include: Option<Regex>,
+    exclude: Option<Regex>,
+    cache: ContentMatchCache,
+}
+
+impl ContentFilter {
+    /// Empty or invalid patterns are left out, see [`Self::check`].
+    pub fn new(include: &str, exclude: &str) -> Self {
+        Self {
+            include: Self::compile(include).and_then(Result::ok),
+            exclude: Self::compile(exclude).and_then(Result::ok),
+            cache: ContentMatchCache::default(),
+        }
+    }
+
+    pub fn with_cache(mut self, cache: ContentMatchCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Why `pattern` is not a valid regex, if it isn't.
+    pub fn check(pattern: &str) -> Option<String> {
+        Self::compile(pattern)?.err()
+    }
+
+    fn compile(pattern: &str) -> Option<Result<Regex, String>> {
+        let pattern = pattern.trim();
+        (!pattern.is_empty()).then(|| Regex::new(pattern).map_err(|e| e.to_string()))
+    }
+
+    pub fn accepts(&self, path: &Path, metadata: &Metadata) -> bool {
+        if let Some(include) = &self.include {
+            if !self.cache.is_match(include, false, path, metadata) {
+                return false;
+            }
+        }
+        match &self.exclude {
+            Some(exclude) => !self.cache.is_match(exclude, true, path, metadata),
+            None => true,
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use crate::upload::connectivity;
+use reqwest::header::HeaderMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const OFFLINE_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Shared between the UI and a running upload so the UI can steer the run
+/// while it is in progress.
+#[derive(Clone, Default)]
+pub struct RunControl {
+    inner: Arc<Mutex<ControlState>>,
+}
+
+#[derive(Default)]
+struct ControlState {
+    new_headers: Option<HeaderMap>,
+    stopped: bool,
+    cancelled: bool,
+}
+
+impl RunControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn provide_headers(&self, headers: HeaderMap) {
+        self.inner.lock().unwrap().new_headers = Some(headers);
+    }
+
+    pub fn stop(&self) {
+        self.inner.lock().unwrap().stopped = true;
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.inner.lock().unwrap().stopped
+    }
+
+    /// Stops the run and also abandons the upload in flight instead of
+    /// letting it finish.
+    pub fn cancel(&self) {
+        let mut state = self.inner.lock().unwrap();
+        state.stopped = true;
+        state.cancelled = true;
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.lock().unwrap().cancelled
+    }
+
+    /// Resolves once the run is cancelled, to race against a request.
+    pub async fn cancelled(&self) {
+        while !self.is_cancelled() {
+            tokio::time::sleep(CANCEL_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Probes connectivity until claude.ai is reachable again; returns false
+    /// if the run was stopped first.
+    pub async fn wait_for_connection(&self) -> bool {
+        loop {
+            if self.is_stopped() {
+                return false;
+            }
+            if connectivity::is_reachable().await {
+                return true;
+            }
+            tokio::time::sleep(OFFLINE_RETRY_INTERVAL).await;
+        }
+    }
+
+    /// Waits until fresh headers are provided, or returns `None` once the run is stopped.
+    pub async fn wait_for_headers(&self) -> Option<HeaderMap> {
+        loop {
+            {
+                let mut state = self.inner.lock().unwrap();
+                if state.stopped {
+                    return None;
+                }
+                if let Some(headers) = state.new_headers.take() {
+                    return Some(headers);
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(250)).await;
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use crate::upload::path_filter::{FileDecision, PathFilter};
+use crate::upload::sensitive::SensitiveFile;
+use crate::upload::types::{DiscoveredFile, FilterOptions};
+use crate::upload::vendored::VendoredFile;
+use crate::utils::git_info::GitInfo;
+use ignore::WalkState;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+
+const PROGRESS_INTERVAL: usize = 1000;
+
+/// Identifies the inputs a discovery was computed from; a cached result is
+/// only valid while the key stays the same.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoveryKey {
+    pub folder_path: String,
+    pub selected_sections: Vec<String>,
+    pub filter_options: FilterOptions,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DiscoveryResult {
+    pub files: Vec<DiscoveredFile>,
+    /// HEAD of the git repository the folder is in, if any.
+    pub git: Option<GitInfo>,
+    /// Supported files left out because they look like third-party code.
+    pub vendored: Vec<VendoredFile>,
+    /// Supported files held back by the sensitive deny-list.
+    pub sensitive: Vec<SensitiveFile>,
+}
+
+enum DiscoveryEvent {
+    Progress {
+        examined: usize,
+        found: Vec<DiscoveredFile>,
+        vendored: Vec<VendoredFile>,
+        sensitive: Vec<SensitiveFile>,
+    },
+    Finished {
+        git: Option<GitInfo>,
+    },
+}
+
+/// Handle to a discovery walk running on a background thread. Matches are
+/// streamed in batches from the parallel walker so the preview can fill up
+/// while the walk continues.
+/// Dropping the handle cancels the walk.
+pub struct DiscoveryScan {
+    pub examined: usize,
+    pub files: Vec<DiscoveredFile>,
+    pub vendored: Vec<VendoredFile>,
+    pub sensitive: Vec<SensitiveFile>,
+    cancelled: Arc<AtomicBool>,
+    receiver: Receiver<DiscoveryEvent>,
+}
+
+impl DiscoveryScan {
+    pub fn start(path_filter: PathFilter) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let thread_cancelled = cancelled.clone();
+
+        std::thread::spawn(move || scan(&path_filter, &thread_cancelled, &sender));
+
+        Self {
+            examined: 0,
+            files: Vec::new(),
+            vendored: Vec::new(),
+            sensitive: Vec::new(),
+            cancelled,
+            receiver,
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Applies pending progress and returns the final result once the walk is done.
+    pub fn poll(&mut self) -> Option<DiscoveryResult> {
+        while let Ok(event) = self.receiver.try_recv() {
+            match event {
+                DiscoveryEvent::Progress {
+                    examined,
+                    found,
+                    vendored,
+                    sensitive,
+                } => {
+                    // Workers report independently, so counts may arrive out of order
+                    self.examined = self.examined.max(examined);
+                    self.files.extend(found);
+                    self.vendored.extend(vendored);
+                    self.sensitive.extend(sensitive);
+                }
+                DiscoveryEvent::Finished { git } => {
+                    let mut files = std::mem::take(&mut self.files);
+                    files.sort_by(|a, b| a.path.cmp(&b.path));
+                    let mut vendored = std::mem::take(&mut self.vendored);
+                    vendored.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+                    let mut sensitive = std::mem::take(&mut self.sensitive);
+                    sensitive.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+                    return Some(DiscoveryResult {
+                        files,
+                        git,
+                        vendored,
+                        sensitive,
+                    });
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Drop for DiscoveryScan {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Per-worker buffer of matches, flushed to the UI in batches and once more
+/// when the worker finishes.
+struct Batch {
+    examined: Arc<AtomicUsize>,
+    found: Vec<DiscoveredFile>,
+    vendored: Vec<VendoredFile>,
+    sensitive: Vec<SensitiveFile>,
+    sender: Sender<DiscoveryEvent>,
+}
+
+impl Batch {
+    fn flush(&mut self) -> bool {
+        let event = DiscoveryEvent::Progress {
+            examined: self.examined.load(Ordering::Relaxed),
+            found: std::mem::take(&mut self.found),
+            vendored: std::mem::take(&mut self.vendored),
+            sensitive: std::mem::take(&mut self.sensitive),
+        };
+        self.sender.send(event).is_ok()
+    }
+}
+
+impl Drop for Batch {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+fn scan(path_filter: &PathFilter, cancelled: &Arc<AtomicBool>, sender: &Sender<DiscoveryEvent>) {
+    let examined = Arc::new(AtomicUsize::new(0));
+    let total_found = Arc::new(AtomicUsize::new(0));
+
+    path_filter.walk_parallel().run(|| {
+        let path_filter = path_filter.clone();
+        let cancelled = cancelled.clone();
+        let total_found = total_found.clone();
+        let mut batch = Batch {
+            examined: examined.clone(),
+            found: Vec::new(),
+            vendored: Vec::new(),
+            sensitive: Vec::new(),
+            sender: sender.clone(),
+        };
+
+        Box::new(move |entry| {
+            if cancelled.load(Ordering::Relaxed) {
+                return WalkState::Quit;
+            }
+
+            let count = batch.examined.fetch_add(1, Ordering::Relaxed) + 1;
+            if let Ok(entry) = entry {
+                let path = entry.path();
+                let relative_path = || {
+                    path_filter
+                        .relative_path(path)
+                        .to_string_lossy()
+                        .replace('\\', "/")
+                };
+                match path_filter.decide(path) {
+                    FileDecision::Upload(metadata) => {
+                        batch.found.push(DiscoveredFile::from_metadata(
+                            path_filter.root(),
+                            path,
+                            &metadata,
+                        ));
+                        total_found.fetch_add(1, Ordering::Relaxed);
+                    }
+                    FileDecision::Sensitive(pattern) => batch.sensitive.push(SensitiveFile {
+                        relative_path: relative_path(),
+                        pattern,
+                    }),
+                    FileDecision::Vendored(reason) => batch.vendored.push(VendoredFile {
+                        relative_path: relative_path(),
+                        reason,
+                    }),
+                    FileDecision::Skipped(_) => {}
+                }
+            }
+
+            let should_flush =
+                count % PROGRESS_INTERVAL == 0 || batch.found.len() >= PROGRESS_INTERVAL;
+            if should_flush && !batch.flush() {
+                return WalkState::Quit;
+            }
+            WalkState::Continue
+        })
+    });
+
+    if cancelled.load(Ordering::Relaxed) {
+        println!(
+            "Discovery cancelled after {} entries",
+            examined.load(Ordering::Relaxed)
+        );
+        return;
+    }
+
+    println!(
+        "Discovered {} supported files in {} ({} entries examined)",
+        total_found.load(Ordering::Relaxed),
+        path_filter.root().display(),
+        examined.load(Ordering::Relaxed)
+    );
+    let git = GitInfo::read(path_filter.root());
+    let _ = sender.send(DiscoveryEvent::Finished { git });
+}
@@ -0,0 +1,116 @@
+use crate::upload::manifest::sha256_hex;
+use unicode_normalization::UnicodeNormalization;
+
+/// Longest doc name sent, in characters, including a hash suffix.
+pub const MAX_DOC_NAME_CHARS: usize = 200;
+/// Extensions longer than this are truncated with the rest of the name.
+const MAX_EXTENSION_CHARS: usize = 16;
+const HASH_SUFFIX_CHARS: usize = 8;
+
+/// Turns a file's name into a doc name claude.ai accepts: NFC normalized,
+/// without control and invisible formatting characters, and shortened with
+/// a hash of the full name so long names that share a prefix stay distinct.
+pub struct DocName;
+
+impl DocName {
+    pub fn sanitize(name: &str) -> String {
+        let cleaned: String = name
+            .nfc()
+            .filter_map(|c| match c {
+                '\t' | '\n' | '\r' => Some(' '),
+                c if c.is_control() || Self::is_invisible(c) => None,
+                c => Some(c),
+            })
+            .collect();
+        let cleaned = cleaned.trim();
+        if cleaned.is_empty() {
+            return "untitled".to_string();
+        }
+        if cleaned.chars().count() <= MAX_DOC_NAME_CHARS {
+            return cleaned.to_string();
+        }
+
+        let (stem, extension) = match cleaned.rsplit_once('.') {
+            Some((stem, extension))
+                if !stem.is_empty() && extension.chars().count() <= MAX_EXTENSION_CHARS =>
+            {
+                (stem, format!(".{}", extension))
+            }
+            _ => (cleaned, String::new()),
+        };
+        let hash = &sha256_hex(name.as_bytes())[..HASH_SUFFIX_CHARS];
+        let keep = MAX_DOC_NAME_CHARS - extension.chars().count() - HASH_SUFFIX_CHARS - 1;
+        let stem: String = stem.chars().take(keep).collect();
+        format!("{}~{}{}", stem.trim_end(), hash, extension)
+    }
+
+    /// Zero width and bidirectional formatting characters, which can make a
+    /// name look like another one.
+    fn is_invisible(c: char) -> bool {
+        matches!(
+            c,
+            '\u{200B}'..='\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}' | '\u{FEFF}'
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn long_name(length: usize, suffix: &str) -> String {
+        format!("{}{}", "a".repeat(length - suffix.chars().count()), suffix)
+    }
+
+    #[test]
+    fn keeps_names_up_to_the_limit() {
+        let name = long_name(MAX_DOC_NAME_CHARS, ".md");
+        assert_eq!(DocName::sanitize(&name), name);
+
+        let longer = long_name(MAX_DOC_NAME_CHARS + 1, ".md");
+        let sanitized = DocName::sanitize(&longer);
+        assert_ne!(sanitized, longer);
+        assert_eq!(sanitized.chars().count(), MAX_DOC_NAME_CHARS);
+    }
+
+    #[test]
+    fn long_names_with_a_shared_prefix_stay_distinct() {
+        let first = DocName::sanitize(&long_name(300, "-first.md"));
+        let second = DocName::sanitize(&long_name(300, "-second.md"));
+        assert_ne!(first, second);
+        assert_eq!(first.chars().count(), MAX_DOC_NAME_CHARS);
+        assert_eq!(second.chars().count(), MAX_DOC_NAME_CHARS);
+    }
+
+    #[test]
+    fn shortening_keeps_the_extension() {
+        let sanitized = DocName::sanitize(&long_name(300, ".tsx"));
+        assert!(sanitized.ends_with(".tsx"));
+        let (stem, _) = sanitized.rsplit_once('.').unwrap();
+        let (_, hash) = stem.rsplit_once('~').unwrap();
+        assert_eq!(hash.len(), HASH_SUFFIX_CHARS);
+    }
+
+    #[test]
+    fn overlong_extensions_are_shortened_with_the_name() {
+        let extension = "x".repeat(MAX_EXTENSION_CHARS + 1);
+        let sanitized = DocName::sanitize(&long_name(300, &format!(".{}", extension)));
+        assert_eq!(sanitized.chars().count(), MAX_DOC_NAME_CHARS);
+        assert!(!sanitized.contains('.'));
+        let (_, hash) = sanitized.rsplit_once('~').unwrap();
+        assert_eq!(hash.len(), HASH_SUFFIX_CHARS);
+    }
+
+    #[test]
+    fn normalizes_to_nfc() {
+        assert_eq!(DocName::sanitize("cafe\u{301}.md"), "caf\u{e9}.md");
+    }
+
+    #[test]
+    fn drops_invisible_and_control_characters() {
+        assert_eq!(DocName::sanitize("\u{202E}"), "untitled");
+        assert_eq!(DocName::sanitize("  \u{200B}\u{FEFF} "), "untitled");
+        assert_eq!(DocName::sanitize("re\u{202E}dme.md"), "redme.md");
+        assert_eq!(DocName::sanitize("a\tb\u{7}.md"), "a b.md");
+    }
+}
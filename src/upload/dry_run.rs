@@ -0,0 +1,72 @@
+use crate::upload::path_filter::{FileDecision, PathFilter};
+use crate::upload::types::{DiscoveredFile, FileStatus, Operation, UploadStatus};
+use ignore::WalkState;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+
+/// Walks the folder with the same rules as a discovery, but records every
+/// file with what a run would do with it and why. Nothing is read beyond
+/// what the filters need and no request is made.
+pub struct DryRun;
+
+impl DryRun {
+    /// Walks on a background thread; the statuses arrive sorted by path
+    /// once the walk is done.
+    pub fn start(path_filter: PathFilter) -> Receiver<Vec<FileStatus>> {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let statuses = Self::walk(&path_filter);
+            println!(
+                "Dry run of {} listed {} files",
+                path_filter.root().display(),
+                statuses.len()
+            );
+            sender.send(statuses).unwrap_or_default();
+        });
+        receiver
+    }
+
+    fn walk(path_filter: &PathFilter) -> Vec<FileStatus> {
+        let statuses = Arc::new(Mutex::new(Vec::new()));
+        path_filter.walk_parallel().run(|| {
+            let path_filter = path_filter.clone();
+            let statuses = statuses.clone();
+            Box::new(move |entry| {
+                let Ok(entry) = entry else {
+                    return WalkState::Continue;
+                };
+                if !entry
+                    .file_type()
+                    .is_some_and(|file_type| file_type.is_file())
+                {
+                    return WalkState::Continue;
+                }
+                let path = entry.path();
+                let Some(file) = DiscoveredFile::from_path(path_filter.root(), path) else {
+                    return WalkState::Continue;
+                };
+
+                let status = match path_filter.decide(path) {
+                    FileDecision::Upload(_) => UploadStatus::Success,
+                    FileDecision::Sensitive(pattern) => {
+                        UploadStatus::Skipped(format!("Sensitive file ({})", pattern))
+                    }
+                    FileDecision::Vendored(reason) => {
+                        UploadStatus::Skipped(format!("Vendored code: {}", reason))
+                    }
+                    FileDecision::Skipped(reason) => UploadStatus::Skipped(reason.to_string()),
+                };
+                let status = FileStatus {
+                    operation: Operation::Preview,
+                    ..FileStatus::for_file(&file, status)
+                };
+                statuses.lock().unwrap().push(status);
+                WalkState::Continue
+            })
+        });
+
+        let mut statuses = std::mem::take(&mut *statuses.lock().unwrap());
+        statuses.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+        statuses
+    }
+}
@@ -0,0 +1,91 @@
+use crate::upload::types::DiscoveredFile;
+use std::path::{Component, Path};
+
+/// Files named in an explicit list, used instead of discovering them.
+pub struct FileList {
+    pub files: Vec<DiscoveredFile>,
+    /// Listed paths that are not files below the selected folder.
+    pub missing: Vec<String>,
+}
+
+impl FileList {
+    /// Resolves one path per line, relative to `root` or absolute below it.
+    /// Blank lines and `#` comments are ignored and duplicates kept once.
+    /// Paths with `..` count as missing, even if they lead back below `root`.
+    pub fn resolve(root: &Path, text: &str) -> Self {
+        let mut files: Vec<DiscoveredFile> = Vec::new();
+        let mut missing = Vec::new();
+
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let path = root.join(line.trim_start_matches("./"));
+            let below_root = path.strip_prefix(root).is_ok_and(|relative| {
+                relative
+                    .components()
+                    .all(|component| matches!(component, Component::Normal(_)))
+            });
+            let file = below_root
+                .then(|| DiscoveredFile::from_path(root, &path))
+                .flatten();
+            match file {
+                Some(file) if !files.iter().any(|f| f.path == file.path) => files.push(file),
+                Some(_) => {}
+                None => missing.push(line.to_string()),
+            }
+        }
+
+        Self { files, missing }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// A folder with `project/src/main.rs` and a `secret.txt` next to it.
+    fn folder() -> TempDir {
+        let folder = tempfile::tempdir().unwrap();
+        let dir = folder.path();
+        fs::create_dir_all(dir.join("project").join("src")).unwrap();
+        fs::write(
+            dir.join("project").join("src").join("main.rs"),
+            "fn main() {}",
+        )
+        .unwrap();
+        fs::write(dir.join("secret.txt"), "secret").unwrap();
+        folder
+    }
+
+    #[test]
+    fn resolves_relative_and_absolute_paths_below_the_root() {
+        let folder = folder();
+        let root = folder.path().join("project");
+        let absolute = root.join("src").join("main.rs");
+        let text = format!("# listed twice\n./src/main.rs\n{}\n", absolute.display());
+
+        let list = FileList::resolve(&root, &text);
+        assert_eq!(list.files.len(), 1);
+        assert_eq!(list.files[0].relative_path, "src/main.rs");
+        assert!(list.missing.is_empty());
+    }
+
+    #[test]
+    fn rejects_paths_leaving_the_root() {
+        let folder = folder();
+        let root = folder.path().join("project");
+        let absolute = folder.path().join("secret.txt");
+        let text = format!(
+            "../secret.txt\nsrc/../../secret.txt\nsrc/../src/main.rs\n{}\n",
+            absolute.display()
+        );
+
+        let list = FileList::resolve(&root, &text);
+        assert!(list.files.is_empty());
+        assert_eq!(list.missing.len(), 4);
+    }
+}
@@ -1,277 +1,711 @@
-use crate::upload::types::{FileStatus, UploadStatus, UploadedFile};
-use crate::utils::claude_keep::ClaudeKeepConfig;
-use ignore::Walk;
-use reqwest::header::HeaderMap;
-use serde::Deserialize;
-use serde_json::json;
+use crate::upload::backend::ProjectBackend;
+use crate::upload::client::ClaudeClient;
+use crate::upload::content_cache::ContentSummary;
+use crate::upload::control::RunControl;
+use crate::upload::doc_name::DocName;
+use crate::upload::manifest::{sha256_hex, RemoteManifest, MANIFEST_DOC_NAME};
+use crate::upload::plan::UploadPlan;
+use crate::upload::section_tag::SectionTag;
+use crate::upload::sensitive::SensitivePath;
+use crate::upload::sync_info::{SyncInfo, SYNC_INFO_DOC_NAME};
+use crate::upload::types::{
+    ConflictResolution, ContentThreshold, DiscoveredFile, ErrorKind, FileStatus, Operation,
+    PreflightPolicy, RetryPolicy, UploadError, UploadStatus, UploadedFile,
+};
+use crate::utils::git_info::GitInfo;
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::Sender;
+use std::time::{Instant, SystemTime};
 
-#[derive(Deserialize)]
-struct UploadResponse {
-    uuid: String,
-    file_name: String,
-}
+const STALE_WARNING: &str = "Content may be stale, the file changed during upload";
 
+/// Runs uploads against a [`ProjectBackend`], a claude.ai project unless
+/// another destination is given.
 #[derive(Clone)]
-pub struct FileProcessor {
-    folder_path: String,
-    organization_id: String,
-    project_id: String,
-    headers: HeaderMap,
-    keep_config: Option<ClaudeKeepConfig>,
-    selected_sections: Vec<String>,
+pub struct FileProcessor<B = ClaudeClient> {
+    client: B,
+    control: RunControl,
+    manifest_sync: bool,
+    prune_root: Option<PathBuf>,
+    conflict_resolutions: HashMap<String, ConflictResolution>,
+    sync_info: Option<SyncInfo>,
+    git: Option<GitInfo>,
+    min_content: ContentThreshold,
+    reupload_modified: bool,
+    approved_plan: Option<UploadPlan>,
+    section_tags: HashMap<String, String>,
+    retry: RetryPolicy,
+    confirmed_sensitive: Vec<String>,
 }
 
-impl FileProcessor {
-    pub fn new(
-        folder_path: String,
-        organization_id: String,
-        project_id: String,
-        headers: HeaderMap,
-        keep_config: Option<ClaudeKeepConfig>,
-        selected_sections: Vec<String>,
-    ) -> Self {
+impl<B: ProjectBackend> FileProcessor<B> {
+    pub fn new(client: B, control: RunControl) -> Self {
         Self {
-            folder_path,
-            organization_id,
-            project_id,
-            headers,
-            keep_config,
-            selected_sections,
+            client,
+            control,
+            manifest_sync: false,
+            prune_root: None,
+            conflict_resolutions: HashMap::new(),
+            sync_info: None,
+            git: None,
+            min_content: ContentThreshold::default(),
+            reupload_modified: false,
+            approved_plan: None,
+            section_tags: HashMap::new(),
+            retry: RetryPolicy::default(),
+            confirmed_sensitive: Vec::new(),
         }
     }
 
-    pub fn count_supported_files(&self) -> usize {
-        let mut count = 0;
-        for entry in Walk::new(&self.folder_path) {
-            if let Ok(entry) = entry {
-                if entry.path().is_file() && self.is_supported_file(entry.path()) {
-                    count += 1;
+    /// Skips files whose hash matches the manifest stored in the project and
+    /// keeps that manifest up to date after the run.
+    pub fn with_manifest_sync(mut self, enabled: bool) -> Self {
+        self.manifest_sync = enabled;
+        self
+    }
+
+    /// Deletes the docs of manifest entries whose file no longer exists
+    /// below `root`. Only takes effect with manifest sync.
+    pub fn with_prune_removed(mut self, root: Option<PathBuf>) -> Self {
+        self.prune_root = root;
+        self
+    }
+
+    /// Choices for files previously reported as conflicts, keyed by relative path.
+    pub fn with_conflict_resolutions(
+        mut self,
+        resolutions: HashMap<String, ConflictResolution>,
+    ) -> Self {
+        self.conflict_resolutions = resolutions;
+        self
+    }
+
+    /// Replaces the `SYNC_INFO.md` doc after a run that uploaded anything.
+    pub fn with_sync_info(mut self, sync_info: Option<SyncInfo>) -> Self {
+        self.sync_info = sync_info;
+        self
+    }
+
+    /// Git HEAD of the folder, stored in the manifest.
+    pub fn with_git(mut self, git: Option<GitInfo>) -> Self {
+        self.git = git;
+        self
+    }
+
+    pub fn with_min_content(mut self, min_content: ContentThreshold) -> Self {
+        self.min_content = min_content;
+        self
+    }
+
+    /// Uploads files that changed while they were being uploaded once more at
+    /// the end of the run, instead of only warning about them.
+    pub fn with_reupload_modified(mut self, enabled: bool) -> Self {
+        self.reupload_modified = enabled;
+        self
+    }
+
+    /// Refuses every file that is not in `plan` with the approved content.
+    pub fn with_approved_plan(mut self, plan: Option<UploadPlan>) -> Self {
+        self.approved_plan = plan;
+        self
+    }
+
+    /// Names docs `[section] relative/path` after the section of their file,
+    /// keyed by relative path. Files without a section keep their name.
+    pub fn with_section_tags(mut self, section_tags: HashMap<String, String>) -> Self {
+        self.section_tags = section_tags;
+        self
+    }
+
+    /// Tries uploads that failed with a network or server error again.
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Sensitive files the user confirmed uploading. Every other file on the
+    /// deny-list is refused, however it ended up in the run.
+    pub fn with_confirmed_sensitive(mut self, confirmed: Vec<String>) -> Self {
+        self.confirmed_sensitive = confirmed;
+        self
+    }
+
+    /// Verifies the session before any file is touched, repeating the check
+    /// for transient failures such as a brief network blip.
+    pub async fn check_session(
+        &self,
+        policy: PreflightPolicy,
+        status_sender: &Sender<FileStatus>,
+    ) -> Result<(), UploadError> {
+        let mut attempt = 1;
+        loop {
+            status_sender
+                .send(FileStatus::new(
+                    String::new(),
+                    UploadStatus::CheckingSession {
+                        attempt,
+                        max_attempts: policy.attempts,
+                    },
+                ))
+                .unwrap_or_default();
+
+            match self.client.check_access().await {
+                Ok(()) => return Ok(()),
+                Err(error) if error.kind.is_transient() && attempt < policy.attempts => {
+                    println!(
+                        "Session check attempt {}/{} failed: {}",
+                        attempt, policy.attempts, error
+                    );
+                    if self.control.is_stopped() {
+                        return Err(error);
+                    }
+                    tokio::time::sleep(policy.interval).await;
+                    attempt += 1;
                 }
+                Err(error) => return Err(error),
             }
         }
-        count
     }
 
-    pub async fn process_files(&self, status_sender: &Sender<FileStatus>) -> Vec<UploadedFile> {
+    /// Reports every file as failed with the pre-flight error so the run
+    /// completes and the failure summary can offer a retry.
+    pub fn fail_all(
+        &self,
+        files: &[DiscoveredFile],
+        error: &UploadError,
+        status_sender: &Sender<FileStatus>,
+    ) {
+        for file in files {
+            status_sender
+                .send(FileStatus::for_file(
+                    file,
+                    UploadStatus::Error(error.clone()),
+                ))
+                .unwrap_or_default();
+        }
+    }
+
+    /// Uploads `files` in order. When the session expires or the network drops
+    /// the run pauses on the failing file and resumes once the UI provides
+    /// fresh headers or connectivity returns.
+    pub async fn process_files(
+        &mut self,
+        files: &[DiscoveredFile],
+        status_sender: &Sender<FileStatus>,
+    ) -> Vec<UploadedFile> {
         let mut uploaded_files = Vec::new();
-        let mut files_to_process = Vec::new();
+        let mut remote_manifest = if self.manifest_sync {
+            self.load_remote_manifest().await
+        } else {
+            None
+        };
+        let mut manifest_changed = false;
+        let mut modified_during_upload = Vec::new();
 
-        for entry in Walk::new(&self.folder_path) {
-            if let Ok(entry) = entry {
-                let path = entry.path();
-                if path.is_file() && self.is_supported_file(path) {
-                    files_to_process.push(path.to_path_buf());
-                }
+        for file in files {
+            if self.control.is_stopped() {
+                let reason = if self.control.is_cancelled() {
+                    "Cancelled"
+                } else {
+                    "Run was stopped"
+                };
+                let status = UploadStatus::Skipped(reason.to_string());
+                status_sender
+                    .send(FileStatus::for_file(file, status))
+                    .unwrap_or_default();
+                continue;
             }
-        }
 
-        for file_path in files_to_process {
-            let file_name = file_path
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .to_string();
+            if let Some(pattern) =
+                SensitivePath::blocking_pattern(&file.relative_path, &self.confirmed_sensitive)
+            {
+                let error = UploadError::new(
+                    ErrorKind::NotPermitted,
+                    format!("Sensitive file ({}), confirm it to upload", pattern),
+                );
+                status_sender
+                    .send(FileStatus::for_file(file, UploadStatus::Error(error)))
+                    .unwrap_or_default();
+                continue;
+            }
 
             status_sender
-                .send(FileStatus {
-                    name: file_name.clone(),
-                    status: UploadStatus::Processing,
-                })
+                .send(FileStatus::for_file(file, UploadStatus::Processing))
                 .unwrap_or_default();
 
-            if let Ok(file) = self.upload_file(&file_path, status_sender).await {
-                if let Some(uploaded_file) = file {
-                    uploaded_files.push(uploaded_file);
+            // Hash by streaming first, so unchanged files are never loaded whole
+            let sha256 = match &remote_manifest {
+                Some(_) => match ContentSummary::of_file(&file.path) {
+                    Ok(summary) => Some(summary.sha256),
+                    Err(e) => {
+                        status_sender
+                            .send(FileStatus::for_file(
+                                file,
+                                UploadStatus::Error(read_error(e)),
+                            ))
+                            .unwrap_or_default();
+                        continue;
+                    }
+                },
+                None => None,
+            };
+
+            if let (Some(remote), Some(sha256)) = (&mut remote_manifest, &sha256) {
+                if remote.is_unchanged(&file.relative_path, &sha256) {
+                    let status = UploadStatus::Skipped("Unchanged since last sync".to_string());
+                    status_sender
+                        .send(FileStatus::for_file(file, status))
+                        .unwrap_or_default();
+                    continue;
+                }
+
+                if remote.is_remote_edited(&file.relative_path) {
+                    match self.conflict_resolutions.get(&file.relative_path) {
+                        Some(ConflictResolution::Overwrite) => {
+                            println!("Overwriting remote edits of {}", file.relative_path);
+                        }
+                        Some(ConflictResolution::KeepRemote) => {
+                            remote.accept_remote(&file.relative_path, sha256);
+                            manifest_changed = true;
+                            let status = UploadStatus::Skipped("Kept remote version".to_string());
+                            status_sender
+                                .send(FileStatus::for_file(file, status))
+                                .unwrap_or_default();
+                            continue;
+                        }
+                        None => {
+                            let error = UploadError::new(
+                                ErrorKind::Conflict,
+                                "Doc was edited in Claude since the last sync".to_string(),
+                            );
+                            status_sender
+                                .send(FileStatus::for_file(file, UploadStatus::Error(error)))
+                                .unwrap_or_default();
+                            continue;
+                        }
+                    }
+                }
+            }
+
+            let modified_before = modified_time(file);
+            let content = match fs::read_to_string(&file.path) {
+                Ok(content) => content,
+                Err(e) => {
+                    status_sender
+                        .send(FileStatus::for_file(
+                            file,
+                            UploadStatus::Error(read_error(e)),
+                        ))
+                        .unwrap_or_default();
+                    continue;
+                }
+            };
+            // Checked on the content actually sent, so a file edited after
+            // approval can't slip through
+            if let Some(plan) = &self.approved_plan {
+                let refusal = if !plan.covers(&file.relative_path) {
+                    Some("Not in the approved plan")
+                } else if !plan.approves(&file.relative_path, &sha256_hex(content.as_bytes())) {
+                    Some("Changed since the plan was approved")
+                } else {
+                    None
+                };
+                if let Some(refusal) = refusal {
+                    let error = UploadError::new(ErrorKind::NotPermitted, refusal.to_string());
+                    status_sender
+                        .send(FileStatus::for_file(file, UploadStatus::Error(error)))
+                        .unwrap_or_default();
+                    continue;
+                }
+            }
+            if self.min_content.is_below(&content) {
+                let status = UploadStatus::Skipped("Below minimum size".to_string());
+                status_sender
+                    .send(FileStatus::for_file(file, status))
+                    .unwrap_or_default();
+                continue;
+            }
+
+            let started = Instant::now();
+            let mut attempts = 0;
+            let mut retries = 0;
+            loop {
+                if self.control.is_cancelled() {
+                    let status = UploadStatus::Skipped("Cancelled".to_string());
+                    status_sender
+                        .send(
+                            FileStatus::for_file(file, status)
+                                .with_timing(started.elapsed(), attempts),
+                        )
+                        .unwrap_or_default();
+                    break;
                 }
+                attempts += 1;
+                // A cancelled request may still have reached the project, it
+                // just isn't waited for
+                let result = tokio::select! {
+                    result = self.upload_file(file, &content) => result,
+                    _ = self.control.cancelled() => {
+                        println!("Cancelled upload of {}", file.relative_path);
+                        continue;
+                    }
+                };
+                match result {
+                    Ok(uploaded_file) => {
+                        let stale = modified_time(file) != modified_before;
+                        // The new content was never approved
+                        if stale && self.reupload_modified && self.approved_plan.is_none() {
+                            // Reported once the re-upload at the end of the run is done
+                            println!(
+                                "{} changed during upload, uploading it again at the end",
+                                file.relative_path
+                            );
+                            modified_during_upload.push((file, uploaded_file, sha256));
+                            break;
+                        }
+
+                        let mut status = FileStatus::for_file(file, UploadStatus::Success)
+                            .with_timing(started.elapsed(), attempts);
+                        if stale {
+                            status = status.with_warning(STALE_WARNING);
+                        } else if let Some(renamed) = self.renamed_warning(file, &uploaded_file) {
+                            status = status.with_warning(renamed);
+                        }
+                        status_sender.send(status).unwrap_or_default();
+
+                        if let (Some(remote), Some(sha256)) = (&mut remote_manifest, &sha256) {
+                            let replaced =
+                                remote.record(&file.relative_path, sha256, &uploaded_file.uuid);
+                            if let Some(old_uuid) = replaced {
+                                self.delete_replaced_doc(&old_uuid).await;
+                            }
+                        }
+                        uploaded_files.push(uploaded_file);
+                    }
+                    Err(error) if error.kind == ErrorKind::SessionExpired => {
+                        println!(
+                            "Session expired while uploading {:?}, pausing run",
+                            file.path
+                        );
+                        status_sender
+                            .send(FileStatus::for_file(file, UploadStatus::WaitingForAuth))
+                            .unwrap_or_default();
+
+                        if let Some(headers) = self.control.wait_for_headers().await {
+                            println!("Received fresh session, resuming run");
+                            self.client.set_headers(headers);
+                            continue;
+                        }
+
+                        status_sender
+                            .send(
+                                FileStatus::for_file(file, UploadStatus::Error(error))
+                                    .with_timing(started.elapsed(), attempts),
+                            )
+                            .unwrap_or_default();
+                    }
+                    Err(error) if error.kind == ErrorKind::Offline => {
+                        println!("Connection lost while uploading {:?}, waiting", file.path);
+                        status_sender
+                            .send(FileStatus::for_file(file, UploadStatus::WaitingForNetwork))
+                            .unwrap_or_default();
+
+                        if self.control.wait_for_connection().await {
+                            println!("Connection restored, resuming run");
+                            continue;
+                        }
+
+                        status_sender
+                            .send(
+                                FileStatus::for_file(file, UploadStatus::Error(error))
+                                    .with_timing(started.elapsed(), attempts),
+                            )
+                            .unwrap_or_default();
+                    }
+                    Err(error)
+                        if matches!(error.kind, ErrorKind::Network | ErrorKind::Server)
+                            && retries + 1 < self.retry.attempts
+                            && !self.control.is_stopped() =>
+                    {
+                        retries += 1;
+                        let delay = self.retry.delay(retries);
+                        println!(
+                            "Uploading {} failed: {}, retrying in {:?}",
+                            file.relative_path, error, delay
+                        );
+                        let status = UploadStatus::Retrying {
+                            attempt: retries + 1,
+                            max_attempts: self.retry.attempts,
+                        };
+                        status_sender
+                            .send(FileStatus::for_file(file, status))
+                            .unwrap_or_default();
+                        tokio::select! {
+                            _ = tokio::time::sleep(delay) => {}
+                            _ = self.control.cancelled() => {}
+                        }
+                        continue;
+                    }
+                    Err(error) => {
+                        status_sender
+                            .send(
+                                FileStatus::for_file(file, UploadStatus::Error(error))
+                                    .with_timing(started.elapsed(), attempts),
+                            )
+                            .unwrap_or_default();
+                    }
+                }
+                break;
+            }
+        }
+
+        for (file, stale_doc, sha256) in modified_during_upload {
+            let uploaded_file = self
+                .reupload_modified_file(
+                    file,
+                    stale_doc,
+                    sha256,
+                    &mut remote_manifest,
+                    status_sender,
+                )
+                .await;
+            uploaded_files.push(uploaded_file);
+        }
+
+        if let (Some(remote), Some(root)) = (&mut remote_manifest, &self.prune_root) {
+            // A missing root is more likely an unmounted drive than a deletion
+            if root.is_dir() && !self.control.is_stopped() {
+                manifest_changed |= self.prune_removed(remote, root, status_sender).await;
+            }
+        }
+
+        if let Some(remote) = remote_manifest {
+            if manifest_changed || !uploaded_files.is_empty() {
+                self.save_remote_manifest(remote).await;
+            }
+        }
+
+        if let Some(sync_info) = &self.sync_info {
+            if !uploaded_files.is_empty() {
+                let sync_info = SyncInfo {
+                    files: files.len(),
+                    uploaded: uploaded_files.len(),
+                    ..sync_info.clone()
+                };
+                self.publish_sync_info(&sync_info).await;
             }
         }
 
         uploaded_files
     }
 
-    async fn upload_file(
+    /// Deletes the docs of files removed from the folder since the last
+    /// sync. Returns true if the manifest lost any entry.
+    async fn prune_removed(
         &self,
-        file_path: &Path,
+        remote: &mut RemoteManifest,
+        root: &Path,
         status_sender: &Sender<FileStatus>,
-    ) -> Result<Option<UploadedFile>, String> {
-        let file_name = file_path
-            .file_name()
-            .ok_or("Invalid filename")?
-            .to_str()
-            .ok_or("Invalid filename encoding")?
-            .to_string();
-
-        if !self.is_supported_file(file_path) {
+    ) -> bool {
+        let removed = remote.remove_missing(root);
+        for (relative_path, entry) in &removed {
+            println!("{} was removed, deleting its doc", relative_path);
+            let doc = UploadedFile {
+                name: relative_path.clone(),
+                uuid: entry.uuid.clone(),
+            };
+            let status = match self.client.delete_doc(&entry.uuid).await {
+                Ok(()) => UploadStatus::Success,
+                Err(error) => {
+                    println!("Failed to delete doc of {}: {}", relative_path, error);
+                    // Kept, so the next sync tries again
+                    remote
+                        .manifest
+                        .files
+                        .insert(relative_path.clone(), entry.clone());
+                    UploadStatus::Error(error)
+                }
+            };
             let status = FileStatus {
-                name: file_name,
-                status: UploadStatus::Skipped(
-                    "Not included in selected sections or unsupported type".to_string(),
-                ),
+                relative_path: Some(relative_path.clone()),
+                ..FileStatus::for_operation(&doc, Operation::Delete, status)
             };
             status_sender.send(status).unwrap_or_default();
-            return Ok(None);
         }
+        !removed.is_empty()
+    }
 
-        let content = match fs::read_to_string(file_path) {
-            Ok(content) => content,
-            Err(e) => {
-                let status = FileStatus {
-                    name: file_name.clone(),
-                    status: UploadStatus::Error(format!("Failed to read file: {}", e)),
-                };
-                status_sender.send(status).unwrap_or_default();
-                return Err(format!("Failed to read file: {}", e));
+    async fn load_remote_manifest(&self) -> Option<RemoteManifest> {
+        match self.client.list_docs().await {
+            Ok(docs) => {
+                let remote = RemoteManifest::from_docs(&docs);
+                println!(
+                    "Loaded remote manifest with {} entries",
+                    remote.manifest.files.len()
+                );
+                Some(remote)
             }
-        };
+            Err(error) => {
+                println!(
+                    "Could not load remote manifest, uploading everything: {}",
+                    error
+                );
+                None
+            }
+        }
+    }
 
-        let payload = json!({
-            "file_name": file_name.clone(),
-            "content": content
-        });
-
-        let client = reqwest::Client::new();
-        let url = format!(
-            "https://claude.ai/api/organizations/{}/projects/{}/docs",
-            self.organization_id, self.project_id
-        );
-
-        let response = client
-            .post(&url)
-            .headers(self.headers.clone())
-            .json(&payload)
-            .send()
+    async fn save_remote_manifest(&self, mut remote: RemoteManifest) {
+        remote.manifest.git = self.git.clone();
+        match self
+            .client
+            .upload_doc(MANIFEST_DOC_NAME, remote.manifest.to_json())
             .await
-            .map_err(|e| format!("Failed to send request: {}", e))?;
-
-        match response.status().as_u16() {
-            200 | 201 => match response.json::<UploadResponse>().await {
-                Ok(upload_response) => {
-                    let uploaded_file = UploadedFile {
-                        name: file_name.clone(),
-                        uuid: upload_response.uuid,
-                    };
-
-                    let status = FileStatus {
-                        name: file_name,
-                        status: UploadStatus::Success,
-                    };
-                    status_sender.send(status).unwrap_or_default();
-
-                    Ok(Some(uploaded_file))
+        {
+            Ok(_) => {
+                if let Some(old_uuid) = remote.doc_uuid {
+                    self.delete_replaced_doc(&old_uuid).await;
                 }
-                Err(e) => {
-                    let error_msg = format!("Failed to parse upload response: {}", e);
-                    let status = FileStatus {
-                        name: file_name,
-                        status: UploadStatus::Error(error_msg.clone()),
-                    };
-                    status_sender.send(status).unwrap_or_default();
-                    Ok(None)
-                }
-            },
-            status_code => {
-                let error_msg = format!("Upload failed with status: {}", status_code);
-                let status = FileStatus {
-                    name: file_name,
-                    status: UploadStatus::Error(error_msg),
-                };
-                status_sender.send(status).unwrap_or_default();
-                Ok(None)
             }
+            Err(error) => println!("Failed to update remote manifest: {}", error),
         }
     }
 
-    fn is_supported_file(&self, path: &Path) -> bool {
-        let ignored_paths = [
-            "node_modules",
-            ".nuxt",
-            ".output",
-            ".data",
-            ".nitro",
-            ".cache",
-            "dist",
-            "logs",
-            ".wallet-db",
-            ".fleet",
-            ".idea",
-        ];
-
-        // Check if file is in an ignored directory
-        if let Ok(canonical_path) = path.canonicalize() {
-            let path_str = canonical_path.to_string_lossy();
-            if ignored_paths
-                .iter()
-                .any(|ignored| path_str.contains(ignored))
-            {
-                return false;
+    /// Uploads the new summary first and only then deletes older ones, so
+    /// the project is never left without it.
+    async fn publish_sync_info(&self, sync_info: &SyncInfo) {
+        let previous = match self.client.list_docs().await {
+            Ok(docs) => docs
+                .into_iter()
+                .filter(|doc| doc.file_name == SYNC_INFO_DOC_NAME)
+                .map(|doc| doc.uuid)
+                .collect(),
+            Err(error) => {
+                println!("Could not list docs, keeping old sync info: {}", error);
+                Vec::new()
             }
+        };
+
+        let content = sync_info.to_markdown(SystemTime::now());
+        if let Err(error) = self.client.upload_doc(SYNC_INFO_DOC_NAME, content).await {
+            println!("Failed to upload {}: {}", SYNC_INFO_DOC_NAME, error);
+            return;
         }
+        for uuid in previous {
+            self.delete_replaced_doc(&uuid).await;
+        }
+    }
 
-        let ignored_files = [
-            "package-lock.json",
-            ".DS_Store",
-            ".env",
-            ".env.local",
-            ".env.development",
-            ".env.production",
-        ];
-
-        if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-            if ignored_files.contains(&file_name) {
-                return false;
+    /// Uploads the current content of a file that changed during its upload
+    /// and deletes the stale doc. If that fails the stale doc is kept and
+    /// reported with a warning.
+    async fn reupload_modified_file(
+        &self,
+        file: &DiscoveredFile,
+        stale_doc: UploadedFile,
+        stale_sha256: Option<String>,
+        remote_manifest: &mut Option<RemoteManifest>,
+        status_sender: &Sender<FileStatus>,
+    ) -> UploadedFile {
+        let started = Instant::now();
+        let result = match fs::read_to_string(&file.path) {
+            // Keeps the stale doc, which is still better than none
+            Ok(_) if self.control.is_cancelled() => Err(UploadError::new(
+                ErrorKind::Other,
+                "the run was cancelled".to_string(),
+            )),
+            Ok(content) => self.upload_file(file, &content).await,
+            Err(e) => Err(read_error(e)),
+        };
+
+        let (uploaded_file, sha256, status) = match result {
+            Ok(uploaded_file) => {
+                self.delete_replaced_doc(&stale_doc.uuid).await;
+                let sha256 = ContentSummary::of_file(&file.path)
+                    .ok()
+                    .map(|summary| summary.sha256);
+                let mut status = FileStatus::for_file(file, UploadStatus::Success);
+                if let Some(renamed) = self.renamed_warning(file, &uploaded_file) {
+                    status = status.with_warning(renamed);
+                }
+                (uploaded_file, sha256, status)
             }
-        }
+            Err(error) => {
+                println!("Re-upload of {} failed: {}", file.relative_path, error);
+                let status = FileStatus::for_file(file, UploadStatus::Success).with_warning(
+                    format!("{}; uploading it again failed: {}", STALE_WARNING, error),
+                );
+                (stale_doc, stale_sha256, status)
+            }
+        };
+        status_sender
+            .send(status.with_timing(started.elapsed(), 2))
+            .unwrap_or_default();
 
-        // Check against .claudekeep configuration
-        if let Some(config) = &self.keep_config {
-            if !config.should_include_file(path, &self.selected_sections) {
-                return false;
+        if let (Some(remote), Some(sha256)) = (remote_manifest.as_mut(), &sha256) {
+            let replaced = remote.record(&file.relative_path, sha256, &uploaded_file.uuid);
+            if let Some(old_uuid) = replaced {
+                self.delete_replaced_doc(&old_uuid).await;
             }
         }
+        uploaded_file
+    }
 
-        let supported_extensions = [
-            "html",
-            "css",
-            "js",
-            "jsx",
-            "ts",
-            "tsx",
-            "vue",
-            "svelte",
-            "py",
-            "pyw",
-            "pyx",
-            "pyi",
-            "rs",
-            "md",
-            "txt",
-            "json",
-            "yaml",
-            "yml",
-            "toml",
-            "xml",
-            "d.ts",
-            "gitignore",
-            "prettierrc",
-            "eslintrc",
-            "eslintignore",
-            "babelrc",
-            "browserslistrc",
-            "editorconfig",
-            "npmrc",
-        ];
-
-        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-            return supported_extensions.contains(&ext.to_lowercase().as_str());
+    async fn delete_replaced_doc(&self, uuid: &str) {
+        if let Err(error) = self.client.delete_doc(uuid).await {
+            println!("Failed to delete outdated doc {}: {}", uuid, error);
         }
+    }
 
-        if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-            return supported_extensions.contains(&name.to_lowercase().as_str());
+    /// Name of the doc for `file` before sanitizing.
+    fn doc_name(&self, file: &DiscoveredFile) -> String {
+        match self.section_tags.get(&file.relative_path) {
+            Some(section) => SectionTag::doc_name(section, &file.relative_path),
+            None => file.file_name(),
         }
+    }
+
+    /// Tells the name a doc got when sanitizing changed it, so the doc can
+    /// still be found.
+    fn renamed_warning(
+        &self,
+        file: &DiscoveredFile,
+        uploaded_file: &UploadedFile,
+    ) -> Option<String> {
+        (uploaded_file.name != self.doc_name(file))
+            .then(|| format!("Uploaded as \"{}\"", uploaded_file.name))
+    }
 
-        false
+    async fn upload_file(
+        &self,
+        file: &DiscoveredFile,
+        content: &str,
+    ) -> Result<UploadedFile, UploadError> {
+        let file_name = DocName::sanitize(&self.doc_name(file));
+        let uuid = self
+            .client
+            .upload_doc(&file_name, content.to_string())
+            .await?;
+
+        Ok(UploadedFile {
+            name: file_name,
+            uuid,
+        })
     }
 }
+
+fn modified_time(file: &DiscoveredFile) -> Option<SystemTime> {
+    fs::metadata(&file.path).and_then(|m| m.modified()).ok()
+}
+
+fn read_error(e: io::Error) -> UploadError {
+    let kind = if e.kind() == io::ErrorKind::InvalidData {
+        ErrorKind::InvalidEncoding
+    } else {
+        ErrorKind::Other
+    };
+    UploadError::new(kind, format!("Failed to read file: {}", e))
+}
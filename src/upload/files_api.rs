@@ -0,0 +1,209 @@
+use crate::upload::backend::ProjectBackend;
+use crate::upload::client::request_error;
+use crate::upload::rate_limit::RateLimiter;
+use crate::upload::types::{ErrorKind, Permissions, RemoteDoc, UploadError};
+use reqwest::multipart::{Form, Part};
+use reqwest::{RequestBuilder, StatusCode};
+use serde::Deserialize;
+
+const FILES_URL: &str = "https://api.anthropic.com/v1/files";
+const API_VERSION: &str = "2023-06-01";
+const FILES_BETA: &str = "files-api-2025-04-14";
+const PAGE_SIZE: usize = 1000;
+
+/// Environment variable the API key is read from when none was entered.
+pub const API_KEY_ENV: &str = "ANTHROPIC_API_KEY";
+
+#[derive(Deserialize)]
+struct ApiFile {
+    id: String,
+    filename: String,
+    #[serde(default)]
+    created_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FilesPage {
+    data: Vec<ApiFile>,
+    #[serde(default)]
+    has_more: bool,
+    #[serde(default)]
+    last_id: Option<String>,
+}
+
+/// Uploads to the official Anthropic Files API with an API key instead of a
+/// copied claude.ai session, so there is no session to expire. The files
+/// belong to the workspace of the key rather than to a project.
+#[derive(Clone)]
+pub struct FilesApiClient {
+    http: reqwest::Client,
+    api_key: String,
+    permissions: Permissions,
+    rate_limiter: RateLimiter,
+}
+
+impl FilesApiClient {
+    pub fn new(api_key: String) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            api_key: api_key.trim().to_string(),
+            permissions: Permissions::ALL,
+            rate_limiter: RateLimiter::default(),
+        }
+    }
+
+    pub fn with_permissions(mut self, permissions: Permissions) -> Self {
+        self.permissions = permissions;
+        self
+    }
+
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    fn authorized(&self, request: RequestBuilder) -> RequestBuilder {
+        request
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", API_VERSION)
+            .header("anthropic-beta", FILES_BETA)
+    }
+
+    fn ensure_allowed(&self, allowed: bool, action: &str) -> Result<(), UploadError> {
+        if !allowed {
+            return Err(UploadError::new(
+                ErrorKind::NotPermitted,
+                format!("Refusing to {}: not permitted for this profile", action),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn send(
+        &self,
+        request: RequestBuilder,
+        context: &str,
+    ) -> Result<(StatusCode, String), UploadError> {
+        let response = self
+            .authorized(request)
+            .send()
+            .await
+            .map_err(|e| request_error(context, e))?;
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| request_error(context, e))?;
+        Ok((status, body))
+    }
+
+    async fn send_upload(&self, file_name: &str, content: &str) -> Result<String, UploadError> {
+        let part = Part::text(content.to_string())
+            .file_name(file_name.to_string())
+            .mime_str("text/plain")
+            .map_err(|e| request_error("Failed to build upload", e))?;
+        let request = self
+            .http
+            .post(FILES_URL)
+            .multipart(Form::new().part("file", part));
+        let (status, body) = self.send(request, "Failed to send request").await?;
+
+        if !status.is_success() {
+            return Err(UploadError::new(
+                ErrorKind::from_status(status.as_u16()),
+                format!("Upload failed with status: {}", status),
+            ));
+        }
+        serde_json::from_str::<ApiFile>(&body)
+            .map(|file| file.id)
+            .map_err(|e| {
+                UploadError::new(
+                    ErrorKind::Other,
+                    format!("Failed to parse upload response: {}", e),
+                )
+            })
+    }
+
+    async fn send_delete(&self, id: &str) -> Result<(), UploadError> {
+        let request = self.http.delete(format!("{}/{}", FILES_URL, id));
+        let (status, _) = self.send(request, "Failed to send delete request").await?;
+
+        if status.is_success() {
+            Ok(())
+        } else {
+            Err(UploadError::new(
+                ErrorKind::from_status(status.as_u16()),
+                format!("Failed to delete with status: {}", status),
+            ))
+        }
+    }
+}
+
+impl ProjectBackend for FilesApiClient {
+    async fn check_access(&self) -> Result<(), UploadError> {
+        let request = self.http.get(FILES_URL).query(&[("limit", "1")]);
+        let (status, _) = self
+            .send(request, "Failed to reach the Anthropic API")
+            .await?;
+
+        if status.is_success() {
+            Ok(())
+        } else {
+            Err(UploadError::new(
+                ErrorKind::from_status(status.as_u16()),
+                format!("API key check failed with status: {}", status),
+            ))
+        }
+    }
+
+    /// Pages through all files of the workspace. The listing has no content.
+    async fn list_docs(&self) -> Result<Vec<RemoteDoc>, UploadError> {
+        let mut docs = Vec::new();
+        let mut after_id: Option<String> = None;
+        loop {
+            let mut request = self
+                .http
+                .get(FILES_URL)
+                .query(&[("limit", PAGE_SIZE.to_string())]);
+            if let Some(after_id) = &after_id {
+                request = request.query(&[("after_id", after_id)]);
+            }
+            let (status, body) = self.send(request, "Failed to list files").await?;
+            if !status.is_success() {
+                return Err(UploadError::new(
+                    ErrorKind::from_status(status.as_u16()),
+                    format!("Listing files failed with status: {}", status),
+                ));
+            }
+
+            let page = serde_json::from_str::<FilesPage>(&body).map_err(|e| {
+                UploadError::new(
+                    ErrorKind::Other,
+                    format!("Failed to parse files listing: {}", e),
+                )
+            })?;
+            docs.extend(page.data.into_iter().map(|file| RemoteDoc {
+                uuid: file.id,
+                file_name: file.filename,
+                content: None,
+                created_at: file.created_at,
+            }));
+            match page.last_id {
+                Some(last_id) if page.has_more => after_id = Some(last_id),
+                _ => return Ok(docs),
+            }
+        }
+    }
+
+    async fn upload_doc(&self, file_name: &str, content: String) -> Result<String, UploadError> {
+        self.ensure_allowed(self.permissions.upload, "upload")?;
+        self.rate_limiter
+            .run(|| self.send_upload(file_name, &content))
+            .await
+    }
+
+    async fn delete_doc(&self, uuid: &str) -> Result<(), UploadError> {
+        self.ensure_allowed(self.permissions.delete, "delete")?;
+        self.rate_limiter.run(|| self.send_delete(uuid)).await
+    }
+}
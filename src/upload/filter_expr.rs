@@ -0,0 +1,544 @@
+use std::path::Path;
+
+/// Fields a condition can test.
+const FIELDS: &str = "ext, name, path or size";
+/// Deepest nesting of `not` and parentheses. Each level is a recursive call,
+/// so without a limit a long enough expression overflows the stack.
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Op(Op),
+    Open,
+    Close,
+    OpenList,
+    CloseList,
+    Comma,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+impl Op {
+    fn symbol(&self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Contains => "~",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Ext,
+    Name,
+    Path,
+    Size,
+}
+
+impl Field {
+    fn parse(word: &str) -> Option<Self> {
+        match word.to_lowercase().as_str() {
+            "ext" => Some(Field::Ext),
+            "name" => Some(Field::Name),
+            "path" => Some(Field::Path),
+            "size" => Some(Field::Size),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    Text { field: Field, op: Op, value: String },
+    In { field: Field, values: Vec<String> },
+    Size { op: Op, bytes: u64 },
+}
+
+/// A per-file filter such as
+/// `ext in [rs, toml] and size < 200kb and not path ~ "tests/"`.
+///
+/// Conditions compare `ext`, `name` or `path` (relative, with `/`) using
+/// `=`, `!=`, `~` (contains) or `in [...]`, and `size` using `=`, `!=`, `<`,
+/// `<=`, `>` or `>=` with an optional `b`, `kb`, `mb` or `gb` unit. They
+/// combine with `and`, `or`, `not` and parentheses; `and` binds tighter.
+#[derive(Debug, Clone)]
+pub struct FilterExpr {
+    root: Node,
+}
+
+impl FilterExpr {
+    /// Parses `input`, or explains the first thing wrong with it.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            tokens: tokenize(input)?,
+            position: 0,
+            depth: 0,
+        };
+        if parser.tokens.is_empty() {
+            return Err("The expression is empty".to_string());
+        }
+        let root = parser.or()?;
+        if parser.position < parser.tokens.len() {
+            return Err("Unexpected text after the end of the expression".to_string());
+        }
+        Ok(Self { root })
+    }
+
+    pub fn matches(&self, relative_path: &str, size: u64, case_insensitive: bool) -> bool {
+        let path = Path::new(relative_path);
+        let file = FileFacts {
+            path: relative_path.to_string(),
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            ext: path
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            size,
+            case_insensitive,
+        };
+        file.eval(&self.root)
+    }
+}
+
+struct FileFacts {
+    path: String,
+    name: String,
+    ext: String,
+    size: u64,
+    case_insensitive: bool,
+}
+
+impl FileFacts {
+    fn eval(&self, node: &Node) -> bool {
+        match node {
+            Node::And(left, right) => self.eval(left) && self.eval(right),
+            Node::Or(left, right) => self.eval(left) || self.eval(right),
+            Node::Not(inner) => !self.eval(inner),
+            Node::Text { field, op, value } => {
+                let (actual, value) = (self.text(*field), self.normalize(*field, value));
+                match op {
+                    Op::Eq => actual == value,
+                    Op::Ne => actual != value,
+                    Op::Contains => actual.contains(&value),
+                    _ => false,
+                }
+            }
+            Node::In { field, values } => {
+                let actual = self.text(*field);
+                values
+                    .iter()
+                    .any(|value| actual == self.normalize(*field, value))
+            }
+            Node::Size { op, bytes } => match op {
+                Op::Eq => self.size == *bytes,
+                Op::Ne => self.size != *bytes,
+                Op::Lt => self.size < *bytes,
+                Op::Le => self.size <= *bytes,
+                Op::Gt => self.size > *bytes,
+                Op::Ge => self.size >= *bytes,
+                Op::Contains => false,
+            },
+        }
+    }
+
+    fn text(&self, field: Field) -> String {
+        let text = match field {
+            Field::Ext => &self.ext,
+            Field::Name => &self.name,
+            Field::Path | Field::Size => &self.path,
+        };
+        self.fold_case(text)
+    }
+
+    /// `ext = .rs` means the same as `ext = rs`.
+    fn normalize(&self, field: Field, value: &str) -> String {
+        let value = match field {
+            Field::Ext => value.trim_start_matches('.'),
+            _ => value,
+        };
+        self.fold_case(value)
+    }
+
+    fn fold_case(&self, text: &str) -> String {
+        if self.case_insensitive {
+            text.to_lowercase()
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '[' => Token::OpenList,
+            ']' => Token::CloseList,
+            ',' => Token::Comma,
+            '~' => Token::Op(Op::Contains),
+            '=' => {
+                chars.next_if_eq(&'=');
+                Token::Op(Op::Eq)
+            }
+            '!' => match chars.next() {
+                Some('=') => Token::Op(Op::Ne),
+                _ => return Err("Expected '=' after '!'".to_string()),
+            },
+            '<' => match chars.next_if_eq(&'=') {
+                Some(_) => Token::Op(Op::Le),
+                None => Token::Op(Op::Lt),
+            },
+            '>' => match chars.next_if_eq(&'=') {
+                Some(_) => Token::Op(Op::Ge),
+                None => Token::Op(Op::Gt),
+            },
+            '"' | '\'' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some(end) if end == c => break,
+                        Some(ch) => text.push(ch),
+                        None => return Err(format!("Missing closing {}", c)),
+                    }
+                }
+                Token::Text(text)
+            }
+            c => {
+                let mut word = c.to_string();
+                while let Some(ch) =
+                    chars.next_if(|ch| !ch.is_whitespace() && !"()[],~=!<>\"'".contains(*ch))
+                {
+                    word.push(ch);
+                }
+                Token::Word(word)
+            }
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    /// Consumes `keyword` if it comes next, in any case.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found =
+            matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword));
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn or(&mut self) -> Result<Node, String> {
+        let mut node = self.and()?;
+        while self.keyword("or") {
+            node = Node::Or(Box::new(node), Box::new(self.and()?));
+        }
+        Ok(node)
+    }
+
+    fn and(&mut self) -> Result<Node, String> {
+        let mut node = self.unary()?;
+        while self.keyword("and") {
+            node = Node::And(Box::new(node), Box::new(self.unary()?));
+        }
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> Result<Node, String> {
+        if self.keyword("not") {
+            return Ok(Node::Not(Box::new(self.nested(Self::unary)?)));
+        }
+        if self.peek() == Some(&Token::Open) {
+            self.position += 1;
+            let node = self.nested(Self::or)?;
+            if self.next() != Some(Token::Close) {
+                return Err("Missing ')'".to_string());
+            }
+            return Ok(node);
+        }
+        self.condition()
+    }
+
+    /// Parses one more level of nesting with `parse`.
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Node, String>) -> Result<Node, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(format!(
+                "The expression nests deeper than {} levels",
+                MAX_DEPTH
+            ));
+        }
+        self.depth += 1;
+        let node = parse(self);
+        self.depth -= 1;
+        node
+    }
+
+    fn condition(&mut self) -> Result<Node, String> {
+        let field = match self.next() {
+            Some(Token::Word(word)) => Field::parse(&word)
+                .ok_or_else(|| format!("Unknown field '{}', use {}", word, FIELDS))?,
+            _ => return Err(format!("Expected {}", FIELDS)),
+        };
+
+        if self.keyword("in") {
+            return self.list(field);
+        }
+        let Some(Token::Op(op)) = self.next() else {
+            return Err("Expected =, !=, <, <=, >, >=, ~ or in after a field".to_string());
+        };
+        let value = self.value()?;
+
+        match (field, op) {
+            (Field::Size, Op::Contains) => Err("'~' does not work with size".to_string()),
+            (Field::Size, _) => Ok(Node::Size {
+                op,
+                bytes: parse_size(&value)?,
+            }),
+            (_, Op::Lt | Op::Le | Op::Gt | Op::Ge) => {
+                Err(format!("'{}' only works with size", op.symbol()))
+            }
+            _ => Ok(Node::Text { field, op, value }),
+        }
+    }
+
+    fn list(&mut self, field: Field) -> Result<Node, String> {
+        if field == Field::Size {
+            return Err("'in' does not work with size".to_string());
+        }
+        if self.next() != Some(Token::OpenList) {
+            return Err("Expected '[' after 'in'".to_string());
+        }
+        let mut values = Vec::new();
+        loop {
+            values.push(self.value()?);
+            match self.next() {
+                Some(Token::Comma) => continue,
+                Some(Token::CloseList) => break,
+                _ => return Err("Expected ',' or ']' in the list".to_string()),
+            }
+        }
+        Ok(Node::In { field, values })
+    }
+
+    fn value(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Word(value)) | Some(Token::Text(value)) => Ok(value),
+            _ => Err("Expected a value".to_string()),
+        }
+    }
+}
+
+/// Bytes in sizes like `512`, `200kb` or `1.5mb`.
+fn parse_size(value: &str) -> Result<u64, String> {
+    let lower = value.to_lowercase();
+    let split = lower
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(lower.len());
+    let (number, unit) = lower.split_at(split);
+    let multiplier: u64 = match unit {
+        "" | "b" => 1,
+        "k" | "kb" => 1024,
+        "m" | "mb" => 1024 * 1024,
+        "g" | "gb" => 1024 * 1024 * 1024,
+        _ => return Err(format!("'{}' is not a size, e.g. 200kb", value)),
+    };
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("'{}' is not a size, e.g. 200kb", value))?;
+    Ok((number * multiplier as f64) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(expr: &str, relative_path: &str, size: u64) -> bool {
+        FilterExpr::parse(expr)
+            .unwrap()
+            .matches(relative_path, size, false)
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let expr = "ext = md or ext = rs and size > 1kb";
+        assert!(matches(expr, "notes.md", 10));
+        assert!(!matches(expr, "main.rs", 10));
+        assert!(matches(expr, "main.rs", 2048));
+
+        let grouped = "(ext = md or ext = rs) and size > 1kb";
+        assert!(!matches(grouped, "notes.md", 10));
+        assert!(matches(grouped, "notes.md", 2048));
+    }
+
+    #[test]
+    fn not_negates_the_next_condition() {
+        assert!(matches("not ext = md", "main.rs", 0));
+        assert!(!matches("not ext = md", "notes.md", 0));
+        assert!(matches("not not ext = md", "notes.md", 0));
+        // Only the condition right after it, not the whole `and`
+        assert!(!matches("not ext = md and size < 1kb", "notes.md", 2048));
+        assert!(!matches("not (ext = rs and size < 1kb)", "main.rs", 10));
+    }
+
+    #[test]
+    fn in_matches_any_listed_value() {
+        let expr = "ext in [rs, toml]";
+        assert!(matches(expr, "src/main.rs", 0));
+        assert!(matches(expr, "Cargo.toml", 0));
+        assert!(!matches(expr, "README.md", 0));
+        assert!(matches("name in ['a b.md', x.md]", "docs/a b.md", 0));
+    }
+
+    #[test]
+    fn extensions_match_with_or_without_a_dot() {
+        assert!(matches("ext = .rs", "main.rs", 0));
+        assert!(matches("ext = rs", "main.rs", 0));
+        assert!(matches("ext in [.rs, .toml]", "Cargo.toml", 0));
+        assert!(matches("ext != .rs", "notes.md", 0));
+    }
+
+    #[test]
+    fn paths_and_names_compare_as_text() {
+        assert!(matches("path ~ \"tests/\"", "crate/tests/api.rs", 0));
+        assert!(!matches("path ~ \"tests/\"", "crate/src/api.rs", 0));
+        assert!(matches("name = api.rs", "crate/tests/api.rs", 0));
+        assert!(!matches("name = tests", "crate/tests/api.rs", 0));
+    }
+
+    #[test]
+    fn sizes_take_units() {
+        assert!(matches("size >= 1.5mb", "a.bin", 1_572_864));
+        assert!(!matches("size >= 1.5mb", "a.bin", 1_572_863));
+        assert!(matches("size = 200kb", "a.bin", 204_800));
+        assert!(matches("size = 200k", "a.bin", 204_800));
+        assert!(matches("size < 512", "a.bin", 511));
+        assert!(matches("size <= 512b", "a.bin", 512));
+        assert!(matches("size > 1GB", "a.bin", 1_073_741_825));
+        assert!(matches("size != 1MB", "a.bin", 0));
+    }
+
+    #[test]
+    fn values_fold_case_when_asked() {
+        let expr = FilterExpr::parse("ext = RS and name ~ Main").unwrap();
+        assert!(expr.matches("src/main.RS", 0, true));
+        assert!(!expr.matches("src/main.rs", 0, false));
+        assert!(expr.matches("src/Main.RS", 0, false));
+    }
+
+    #[test]
+    fn keywords_and_fields_ignore_case() {
+        assert!(matches(
+            "NOT EXT = md AND Size < 1KB Or Name In [x]",
+            "main.rs",
+            10
+        ));
+        assert!(matches("ext IN [rs]", "main.rs", 0));
+    }
+
+    #[test]
+    fn explains_what_is_wrong() {
+        let cases = [
+            ("", "The expression is empty"),
+            ("  ", "The expression is empty"),
+            (
+                "ext = rs )",
+                "Unexpected text after the end of the expression",
+            ),
+            ("(ext = rs", "Missing ')'"),
+            (
+                "colour = red",
+                "Unknown field 'colour', use ext, name, path or size",
+            ),
+            ("= rs", "Expected ext, name, path or size"),
+            ("ext = rs or", "Expected ext, name, path or size"),
+            (
+                "ext rs",
+                "Expected =, !=, <, <=, >, >=, ~ or in after a field",
+            ),
+            ("ext =", "Expected a value"),
+            ("ext ! rs", "Expected '=' after '!'"),
+            ("name = \"a", "Missing closing \""),
+            ("name = 'a", "Missing closing '"),
+            ("size ~ 1kb", "'~' does not work with size"),
+            ("size in [1kb]", "'in' does not work with size"),
+            ("name < a", "'<' only works with size"),
+            ("path >= a", "'>=' only works with size"),
+            ("ext in rs", "Expected '[' after 'in'"),
+            ("ext in [rs toml]", "Expected ',' or ']' in the list"),
+            ("ext in [rs,", "Expected a value"),
+            ("size = big", "'big' is not a size, e.g. 200kb"),
+            ("size = 1.2.3kb", "'1.2.3kb' is not a size, e.g. 200kb"),
+            ("size = 2tb", "'2tb' is not a size, e.g. 200kb"),
+        ];
+        for (input, message) in cases {
+            assert_eq!(
+                FilterExpr::parse(input).unwrap_err(),
+                message,
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn refuses_deep_nesting_instead_of_overflowing() {
+        let error = "The expression nests deeper than 64 levels";
+
+        let nots = format!("{}ext = rs", "not ".repeat(10_000));
+        assert_eq!(FilterExpr::parse(&nots).unwrap_err(), error);
+        let parens = format!("{}ext = rs{}", "(".repeat(10_000), ")".repeat(10_000));
+        assert_eq!(FilterExpr::parse(&parens).unwrap_err(), error);
+
+        let deepest = format!(
+            "{}ext = rs{}",
+            "(not ".repeat(MAX_DEPTH / 2),
+            ")".repeat(MAX_DEPTH / 2)
+        );
+        assert!(FilterExpr::parse(&deepest).is_ok());
+        let nots = format!("{}ext = rs", "not ".repeat(MAX_DEPTH + 1));
+        assert!(FilterExpr::parse(&nots).is_err());
+    }
+}
@@ -0,0 +1,139 @@
+use crate::upload::types::RemoteDoc;
+use crate::utils::git_info::GitInfo;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+pub const MANIFEST_DOC_NAME: &str = ".claude-uploader-manifest.json";
+
+/// Records which doc holds which file version. It is stored as a doc in the
+/// project itself, so every machine syncing to the project sees the same
+/// state regardless of what it uploaded locally.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    pub files: BTreeMap<String, ManifestEntry>,
+    /// Commit the last sync was made from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<GitInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub sha256: String,
+    pub uuid: String,
+}
+
+/// The manifest as found in the project, with the uuid of the doc holding it.
+pub struct RemoteManifest {
+    pub manifest: Manifest,
+    pub doc_uuid: Option<String>,
+    /// Content hash of every listed doc, keyed by uuid.
+    remote_hashes: HashMap<String, String>,
+}
+
+impl RemoteManifest {
+    /// Reads the manifest from a docs listing. Entries pointing at docs that
+    /// no longer exist are dropped, so deleted docs get uploaded again.
+    pub fn from_docs(docs: &[RemoteDoc]) -> Self {
+        let manifest_doc = docs.iter().find(|doc| doc.file_name == MANIFEST_DOC_NAME);
+
+        let mut manifest = manifest_doc
+            .and_then(|doc| doc.content.as_deref())
+            .and_then(|content| serde_json::from_str::<Manifest>(content).ok())
+            .unwrap_or_default();
+
+        manifest
+            .files
+            .retain(|_, entry| docs.iter().any(|doc| doc.uuid == entry.uuid));
+
+        let remote_hashes = docs
+            .iter()
+            .filter_map(|doc| {
+                let content = doc.content.as_deref()?;
+                Some((doc.uuid.clone(), sha256_hex(content.as_bytes())))
+            })
+            .collect();
+
+        Self {
+            manifest,
+            doc_uuid: manifest_doc.map(|doc| doc.uuid.clone()),
+            remote_hashes,
+        }
+    }
+
+    /// Stores the new version of a file and returns the uuid of the doc it
+    /// replaces, if any.
+    pub fn record(&mut self, relative_path: &str, sha256: &str, uuid: &str) -> Option<String> {
+        let previous = self.manifest.files.insert(
+            relative_path.to_string(),
+            ManifestEntry {
+                sha256: sha256.to_string(),
+                uuid: uuid.to_string(),
+            },
+        );
+        previous.map(|entry| entry.uuid).filter(|old| old != uuid)
+    }
+
+    /// Marks the local version as synced while keeping the existing doc.
+    pub fn accept_remote(&mut self, relative_path: &str, sha256: &str) {
+        if let Some(entry) = self.manifest.files.get_mut(relative_path) {
+            entry.sha256 = sha256.to_string();
+        }
+    }
+
+    /// True if the doc recorded for this path no longer holds the content we
+    /// uploaded, i.e. someone edited it in the Claude UI since the last sync.
+    pub fn is_remote_edited(&self, relative_path: &str) -> bool {
+        self.manifest
+            .files
+            .get(relative_path)
+            .and_then(|entry| {
+                self.remote_hashes
+                    .get(&entry.uuid)
+                    .map(|remote| remote != &entry.sha256)
+            })
+            .unwrap_or(false)
+    }
+
+    /// Drops the entries whose file no longer exists below `root` and
+    /// returns them, so their docs can be deleted.
+    pub fn remove_missing(&mut self, root: &Path) -> Vec<(String, ManifestEntry)> {
+        let missing: Vec<String> = self
+            .manifest
+            .files
+            .keys()
+            .filter(|relative_path| !root.join(relative_path).is_file())
+            .cloned()
+            .collect();
+        missing
+            .into_iter()
+            .filter_map(|relative_path| {
+                let entry = self.manifest.files.remove(&relative_path)?;
+                Some((relative_path, entry))
+            })
+            .collect()
+    }
+
+    pub fn is_unchanged(&self, relative_path: &str, sha256: &str) -> bool {
+        self.manifest
+            .files
+            .get(relative_path)
+            .map(|entry| entry.sha256 == sha256)
+            .unwrap_or(false)
+    }
+}
+
+impl Manifest {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+pub fn sha256_hex(content: &[u8]) -> String {
+    to_hex(&Sha256::digest(content))
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -1,5 +1,61 @@
+mod analysis;
+mod backend;
+mod client;
+mod connectivity;
+mod content_cache;
+mod content_filter;
+mod control;
+mod discovery;
+mod doc_name;
+mod dry_run;
+mod file_list;
 mod file_processor;
+mod files_api;
+mod filter_expr;
+mod manifest;
+mod path_filter;
+mod plan;
+mod presets;
+mod rate_limit;
+mod recording;
+mod report;
+mod section_tag;
+mod sensitive;
+mod smart_rules;
+mod status_batch;
+mod sync_info;
+mod sync_note;
 mod types;
+mod vendored;
 
+pub use analysis::ContentAnalysis;
+pub use backend::{DirectoryBackend, ProjectBackend};
+pub use client::{ClaudeClient, DEFAULT_PROJECT_URL};
+pub use content_cache::estimate_tokens;
+pub use content_filter::{ContentFilter, ContentMatchCache};
+pub use control::RunControl;
+pub use discovery::{DiscoveryKey, DiscoveryResult, DiscoveryScan};
+pub use doc_name::DocName;
+pub use dry_run::DryRun;
+pub use file_list::FileList;
 pub use file_processor::FileProcessor;
-pub use types::{FileStatus, UploadStatus, UploadedFile};
+pub use files_api::{FilesApiClient, API_KEY_ENV};
+pub use filter_expr::FilterExpr;
+pub use path_filter::PathFilter;
+pub use plan::{PlanPolicy, UploadPlan};
+pub use presets::Preset;
+pub use rate_limit::RateLimiter;
+#[cfg(test)]
+pub use recording::{Fixtures, Replay};
+pub use report::{ReportExclusion, ReportFile, SelectionReport};
+pub use section_tag::SectionTag;
+pub use sensitive::{SensitiveFile, SensitivePath};
+pub use smart_rules::{SmartRule, SMART_RULES};
+pub use status_batch::StatusBatch;
+pub use sync_info::SyncInfo;
+pub use sync_note::SyncNote;
+pub use types::{
+    ConflictResolution, DiscoveredFile, ErrorKind, FileStatus, FilterOptions, Operation,
+    PacingPolicy, Permissions, PreflightPolicy, RemoteDoc, RemoteOrganization, RemoteProject,
+    RetryPolicy, UploadError, UploadStatus, UploadedFile,
+};
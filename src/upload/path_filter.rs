@@ -0,0 +1,500 @@
+use crate::upload::content_filter::{ContentFilter, ContentMatchCache};
+use crate::upload::filter_expr::FilterExpr;
+use crate::upload::sensitive::SensitivePath;
+use crate::upload::smart_rules::SmartRule;
+use crate::upload::types::FilterOptions;
+use crate::upload::vendored::VendoredCode;
+use crate::utils::claude_keep::ClaudeKeepConfig;
+use ignore::{WalkBuilder, WalkParallel};
+use std::fs::{self, Metadata};
+use std::path::{Component, Path, PathBuf};
+
+const IGNORED_DIRS: [&str; 11] = [
+    "node_modules",
+    ".nuxt",
+    ".output",
+    ".data",
+    ".nitro",
+    ".cache",
+    "dist",
+    "logs",
+    ".wallet-db",
+    ".fleet",
+    ".idea",
+];
+
+const IGNORED_FILES: [&str; 6] = [
+    "package-lock.json",
+    ".DS_Store",
+    ".env",
+    ".env.local",
+    ".env.development",
+    ".env.production",
+];
+
+const SUPPORTED_EXTENSIONS: [&str; 29] = [
+    "html",
+    "css",
+    "js",
+    "jsx",
+    "ts",
+    "tsx",
+    "vue",
+    "svelte",
+    "py",
+    "pyw",
+    "pyx",
+    "pyi",
+    "rs",
+    "md",
+    "txt",
+    "json",
+    "yaml",
+    "yml",
+    "toml",
+    "xml",
+    "d.ts",
+    "gitignore",
+    "prettierrc",
+    "eslintrc",
+    "eslintignore",
+    "babelrc",
+    "browserslistrc",
+    "editorconfig",
+    "npmrc",
+];
+
+/// What discovery does with a file, and why.
+#[derive(Debug)]
+pub enum FileDecision {
+    Upload(Metadata),
+    /// Held back by this sensitive deny-list pattern.
+    Sensitive(&'static str),
+    /// Left out as vendored code for this reason.
+    Vendored(&'static str),
+    Skipped(&'static str),
+}
+
+/// Decides which files below the walk root are eligible for upload.
+///
+/// The path rules work on the path relative to `root`, compared component by
+/// component, so nothing outside the selected folder influences the result.
+/// Only the expression, content and vendored checks look at the file itself.
+#[derive(Debug, Clone)]
+pub struct PathFilter {
+    root: PathBuf,
+    keep_config: Option<ClaudeKeepConfig>,
+    selected_sections: Vec<String>,
+    options: FilterOptions,
+    /// `None` when the options have no expression, or an invalid one.
+    expression: Option<FilterExpr>,
+    content: ContentFilter,
+}
+
+impl PathFilter {
+    pub fn new(
+        root: PathBuf,
+        keep_config: Option<ClaudeKeepConfig>,
+        selected_sections: Vec<String>,
+        options: FilterOptions,
+    ) -> Self {
+        let expression = Some(options.expression.trim())
+            .filter(|expression| !expression.is_empty())
+            .and_then(|expression| FilterExpr::parse(expression).ok());
+        let content = ContentFilter::new(&options.content_include, &options.content_exclude);
+        Self {
+            root,
+            keep_config,
+            selected_sections,
+            options,
+            expression,
+            content,
+        }
+    }
+
+    /// Reuses content matches of earlier discoveries sharing `cache`.
+    pub fn with_content_cache(mut self, cache: ContentMatchCache) -> Self {
+        self.content = self.content.with_cache(cache);
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn walk_parallel(&self) -> WalkParallel {
+        let path_filter = self.clone();
+
+        WalkBuilder::new(&self.root)
+            .ignore_case_insensitive(self.options.case_insensitive)
+            .max_depth(self.options.max_depth)
+            .filter_entry(move |entry| {
+                // Prune ignored directories below the root instead of walking into them
+                let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+                entry.depth() == 0
+                    || !is_dir
+                    || !path_filter.is_ignored_dir_name(&entry.file_name().to_string_lossy())
+            })
+            .build_parallel()
+    }
+
+    pub fn relative_path<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(&self.root).unwrap_or(path)
+    }
+
+    pub fn is_ignored_dir_name(&self, name: &str) -> bool {
+        IGNORED_DIRS
+            .iter()
+            .any(|ignored| self.options.names_match(ignored, name))
+    }
+
+    /// What discovery does with the entry at `path`. The rules that only
+    /// look at the path are checked before the file is touched.
+    pub fn decide(&self, path: &Path) -> FileDecision {
+        if let Some(reason) = self.skip_reason(path) {
+            return FileDecision::Skipped(reason);
+        }
+        let metadata = match fs::metadata(path) {
+            Ok(metadata) if metadata.is_file() => metadata,
+            Ok(_) => return FileDecision::Skipped("Not a file"),
+            Err(_) => return FileDecision::Skipped("Could not be read"),
+        };
+        if !self.matches_expression(path, metadata.len()) {
+            return FileDecision::Skipped("Does not match the filter expression");
+        }
+        if !self.matches_content(path, &metadata) {
+            return FileDecision::Skipped("Left out by the content filters");
+        }
+        if let Some(pattern) = self.sensitive_pattern(path) {
+            return FileDecision::Sensitive(pattern);
+        }
+        if let Some(reason) = self.vendored_reason(path) {
+            return FileDecision::Vendored(reason);
+        }
+        FileDecision::Upload(metadata)
+    }
+
+    /// Why the path alone rules a file out, if it does.
+    fn skip_reason(&self, path: &Path) -> Option<&'static str> {
+        let relative_path = self.relative_path(path);
+
+        if self.is_in_ignored_dir(relative_path) {
+            return Some("In an ignored directory");
+        }
+
+        let file_name = match relative_path.file_name().and_then(|n| n.to_str()) {
+            Some(file_name) => file_name,
+            None => return Some("Name is not valid UTF-8"),
+        };
+
+        if IGNORED_FILES
+            .iter()
+            .any(|ignored| self.options.names_match(ignored, file_name))
+        {
+            return Some("Ignored file name");
+        }
+
+        let matches = |rule: &str| {
+            ClaudeKeepConfig::pattern_matches(rule, relative_path, self.options.case_insensitive)
+        };
+        if self.options.ignore_rules().any(matches) {
+            return Some("Matches an ignore rule");
+        }
+        if SmartRule::patterns(&self.options).any(matches) {
+            return Some("Skipped by a smart rule");
+        }
+        let mut include_rules = self.options.include_rules().peekable();
+        if include_rules.peek().is_some() && !include_rules.any(matches) {
+            return Some("Not matched by the include rules");
+        }
+
+        // Check against .claudekeep configuration
+        if let Some(config) = &self.keep_config {
+            if !config.should_include_file(
+                relative_path,
+                &self.selected_sections,
+                self.options.case_insensitive,
+            ) {
+                return Some("Not in the selected .claudekeep sections");
+            }
+        }
+
+        if !self.has_supported_extension(relative_path) {
+            return Some("Unsupported or excluded extension");
+        }
+        None
+    }
+
+    /// Whether a supported file of `size` bytes passes the filter expression.
+    fn matches_expression(&self, path: &Path, size: u64) -> bool {
+        let Some(expression) = &self.expression else {
+            return true;
+        };
+        let relative_path = self
+            .relative_path(path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        expression.matches(&relative_path, size, self.options.case_insensitive)
+    }
+
+    /// Whether the content of a supported file passes the content filters.
+    /// Reads the file unless no filter is set or the result is cached.
+    fn matches_content(&self, path: &Path, metadata: &Metadata) -> bool {
+        self.content.accepts(path, metadata)
+    }
+
+    /// The deny-list pattern holding back a supported file, unless the user
+    /// confirmed uploading it.
+    fn sensitive_pattern(&self, path: &Path) -> Option<&'static str> {
+        let relative_path = self
+            .relative_path(path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        SensitivePath::blocking_pattern(&relative_path, &self.options.confirmed_sensitive)
+    }
+
+    /// Why a supported file is left out as vendored code, unless vendored
+    /// code is included.
+    fn vendored_reason(&self, path: &Path) -> Option<&'static str> {
+        if self.options.include_vendored {
+            return None;
+        }
+        VendoredCode::reason(self.relative_path(path), path)
+    }
+
+    fn is_in_ignored_dir(&self, relative_path: &Path) -> bool {
+        relative_path
+            .parent()
+            .map(|parent| {
+                parent.components().any(|component| match component {
+                    Component::Normal(name) => name
+                        .to_str()
+                        .map(|name| self.is_ignored_dir_name(name))
+                        .unwrap_or(false),
+                    _ => false,
+                })
+            })
+            .unwrap_or(false)
+    }
+
+    fn has_supported_extension(&self, path: &Path) -> bool {
+        let candidate = match path.extension().or_else(|| path.file_name()) {
+            Some(candidate) => candidate,
+            None => return false,
+        };
+
+        let Some(candidate) = candidate.to_str() else {
+            return false;
+        };
+
+        // Extensions match regardless of case on every platform, the case
+        // sensitivity option is about paths only
+        if self
+            .options
+            .excluded_extensions()
+            .any(|excluded| excluded.eq_ignore_ascii_case(candidate))
+        {
+            return false;
+        }
+
+        SUPPORTED_EXTENSIONS
+            .iter()
+            .copied()
+            .chain(self.options.extra_extensions())
+            .any(|supported| supported.eq_ignore_ascii_case(candidate))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn filter(options: FilterOptions) -> PathFilter {
+        PathFilter::new(PathBuf::from("/project"), None, Vec::new(), options)
+    }
+
+    fn case_sensitive() -> FilterOptions {
+        FilterOptions {
+            case_insensitive: false,
+            ..FilterOptions::default()
+        }
+    }
+
+    #[test]
+    fn extensions_match_regardless_of_case() {
+        let filter = filter(case_sensitive());
+        assert_eq!(filter.skip_reason(Path::new("/project/README.MD")), None);
+        assert_eq!(filter.skip_reason(Path::new("/project/src/App.JS")), None);
+    }
+
+    #[test]
+    fn excluded_extensions_match_regardless_of_case() {
+        let filter = filter(FilterOptions {
+            excluded_extensions: "json".to_string(),
+            ..case_sensitive()
+        });
+        assert_eq!(
+            filter.skip_reason(Path::new("/project/DATA.JSON")),
+            Some("Unsupported or excluded extension")
+        );
+    }
+
+    fn skip_reason(filter: &PathFilter, relative_path: &str) -> Option<&'static str> {
+        filter.skip_reason(&Path::new("/project").join(relative_path))
+    }
+
+    #[test]
+    fn path_rules_give_their_reason() {
+        let filter = filter(FilterOptions {
+            ignore_rules: "drafts/".to_string(),
+            smart_rules: vec!["Lock files".to_string()],
+            ..case_sensitive()
+        });
+        assert_eq!(skip_reason(&filter, "src/main.rs"), None);
+        assert_eq!(
+            skip_reason(&filter, "web/node_modules/react/index.js"),
+            Some("In an ignored directory")
+        );
+        assert_eq!(
+            skip_reason(&filter, "web/package-lock.json"),
+            Some("Ignored file name")
+        );
+        assert_eq!(
+            skip_reason(&filter, "drafts/notes.md"),
+            Some("Matches an ignore rule")
+        );
+        assert_eq!(
+            skip_reason(&filter, "Cargo.lock"),
+            Some("Skipped by a smart rule")
+        );
+        assert_eq!(
+            skip_reason(&filter, "logo.png"),
+            Some("Unsupported or excluded extension")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn names_that_are_not_utf8_are_skipped() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new("/project").join(OsStr::from_bytes(b"notes-\xff.md"));
+        assert_eq!(
+            filter(case_sensitive()).skip_reason(&path),
+            Some("Name is not valid UTF-8")
+        );
+    }
+
+    #[test]
+    fn include_rules_skip_everything_else() {
+        let filter = filter(FilterOptions {
+            include_rules: "src/".to_string(),
+            ..case_sensitive()
+        });
+        assert_eq!(skip_reason(&filter, "src/lib.rs"), None);
+        assert_eq!(
+            skip_reason(&filter, "README.md"),
+            Some("Not matched by the include rules")
+        );
+    }
+
+    #[test]
+    fn claudekeep_sections_skip_everything_else() {
+        let filter = PathFilter::new(
+            PathBuf::from("/project"),
+            Some(ClaudeKeepConfig::parse(
+                "backend:\n*.rs\nfrontend:\n*.vue\n",
+            )),
+            vec!["backend".to_string()],
+            case_sensitive(),
+        );
+        assert_eq!(skip_reason(&filter, "src/lib.rs"), None);
+        assert_eq!(
+            skip_reason(&filter, "web/App.vue"),
+            Some("Not in the selected .claudekeep sections")
+        );
+    }
+
+    /// A folder with a source file, a secret, a vendored script, a large
+    /// file and a directory named like a file.
+    fn folder() -> TempDir {
+        let folder = tempfile::tempdir().unwrap();
+        let dir = folder.path();
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(dir.join("vendor")).unwrap();
+        fs::create_dir_all(dir.join("notes.md")).unwrap();
+        fs::write(dir.join("src").join("main.rs"), "fn main() {}").unwrap();
+        fs::write(dir.join("src").join("todo.md"), "TODO: everything").unwrap();
+        fs::write(dir.join("credentials.json"), "{}").unwrap();
+        fs::write(dir.join("vendor").join("lib.js"), "export {};").unwrap();
+        fs::write(dir.join("data.txt"), "x".repeat(4096)).unwrap();
+        folder
+    }
+
+    fn decide(dir: &Path, options: FilterOptions, relative_path: &str) -> FileDecision {
+        PathFilter::new(dir.to_path_buf(), None, Vec::new(), options)
+            .decide(&dir.join(relative_path))
+    }
+
+    #[test]
+    fn file_checks_give_their_reason() {
+        let folder = folder();
+        let dir = folder.path();
+        let options = FilterOptions {
+            expression: "size < 1kb".to_string(),
+            content_exclude: "TODO".to_string(),
+            ..case_sensitive()
+        };
+
+        assert!(matches!(
+            decide(dir, options.clone(), "src/main.rs"),
+            FileDecision::Upload(_)
+        ));
+        assert!(matches!(
+            decide(dir, options.clone(), "notes.md"),
+            FileDecision::Skipped("Not a file")
+        ));
+        assert!(matches!(
+            decide(dir, options.clone(), "missing.md"),
+            FileDecision::Skipped("Could not be read")
+        ));
+        assert!(matches!(
+            decide(dir, options.clone(), "data.txt"),
+            FileDecision::Skipped("Does not match the filter expression")
+        ));
+        assert!(matches!(
+            decide(dir, options.clone(), "src/todo.md"),
+            FileDecision::Skipped("Left out by the content filters")
+        ));
+        assert!(matches!(
+            decide(dir, options.clone(), "credentials.json"),
+            FileDecision::Sensitive("credentials.json")
+        ));
+        assert!(matches!(
+            decide(dir, options, "vendor/lib.js"),
+            FileDecision::Vendored("In a vendor directory")
+        ));
+    }
+
+    #[test]
+    fn confirmed_and_vendored_files_can_be_uploaded() {
+        let folder = folder();
+        let dir = folder.path();
+        let options = FilterOptions {
+            include_vendored: true,
+            confirmed_sensitive: vec!["credentials.json".to_string()],
+            ..case_sensitive()
+        };
+
+        assert!(matches!(
+            decide(dir, options.clone(), "credentials.json"),
+            FileDecision::Upload(_)
+        ));
+        assert!(matches!(
+            decide(dir, options, "vendor/lib.js"),
+            FileDecision::Upload(_)
+        ));
+    }
+}
@@ -0,0 +1,276 @@
+use crate::upload::content_cache::ContentSummary;
+use crate::upload::file_list::FileList;
+use crate::upload::manifest::to_hex;
+use crate::upload::types::DiscoveredFile;
+use crate::utils::git_info::GitInfo;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const POLICY_FILE_NAME: &str = "plan_policy.json";
+/// Message the approval key signs to lock the plan requirement.
+const POLICY_LOCK_LABEL: &[u8] = b"claude-uploader: require approved plans";
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanFile {
+    pub relative_path: String,
+    pub sha256: String,
+    pub size: u64,
+}
+
+/// A selection frozen for approval: exactly these files with exactly this
+/// content, to this project. Signed with a key shared between the person
+/// freezing the plan and the one executing it, so an edited plan is refused.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadPlan {
+    pub created_at: SystemTime,
+    pub folder_name: String,
+    pub git: Option<GitInfo>,
+    pub organization_id: Option<String>,
+    pub project_id: Option<String>,
+    pub files: Vec<PlanFile>,
+    /// HMAC-SHA256 over the plan with an empty signature, hex encoded.
+    #[serde(default)]
+    pub signature: String,
+}
+
+impl UploadPlan {
+    /// Hashes `files` as they are now.
+    pub fn freeze(
+        folder_name: String,
+        git: Option<GitInfo>,
+        organization_id: Option<String>,
+        project_id: Option<String>,
+        files: &[DiscoveredFile],
+    ) -> Result<Self, String> {
+        let files = files
+            .iter()
+            .map(|file| {
+                ContentSummary::of_file(&file.path)
+                    .map(|summary| PlanFile {
+                        relative_path: file.relative_path.clone(),
+                        sha256: summary.sha256,
+                        size: file.size,
+                    })
+                    .map_err(|e| format!("Failed to read {}: {}", file.relative_path, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            created_at: SystemTime::now(),
+            folder_name,
+            git,
+            organization_id,
+            project_id,
+            files,
+            signature: String::new(),
+        })
+    }
+
+    pub fn save(&mut self, path: &Path, key: &str) -> Result<(), String> {
+        self.signature = to_hex(&self.mac(key)?.finalize().into_bytes());
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize plan: {}", e))?;
+        fs::write(path, json).map_err(|e| format!("Failed to write plan: {}", e))
+    }
+
+    /// Reads a plan and checks its signature against `key`.
+    pub fn load(path: &Path, key: &str) -> Result<Self, String> {
+        let json = fs::read_to_string(path).map_err(|e| format!("Failed to read plan: {}", e))?;
+        let plan: Self =
+            serde_json::from_str(&json).map_err(|e| format!("Not a valid plan: {}", e))?;
+        let signature = from_hex(&plan.signature).unwrap_or_default();
+        plan.mac(key)?.verify_slice(&signature).map_err(|_| {
+            "The plan was changed after it was approved, or the key is wrong".to_string()
+        })?;
+        Ok(plan)
+    }
+
+    /// HMAC-SHA256 under `key` over the plan with an empty signature.
+    fn mac(&self, key: &str) -> Result<HmacSha256, String> {
+        let unsigned = Self {
+            signature: String::new(),
+            ..self.clone()
+        };
+        let json = serde_json::to_vec(&unsigned)
+            .map_err(|e| format!("Failed to serialize plan: {}", e))?;
+        let mut mac = keyed_mac(key)?;
+        mac.update(&json);
+        Ok(mac)
+    }
+
+    /// The planned files below `root`, as for an explicit file list.
+    pub fn resolve(&self, root: &Path) -> FileList {
+        let paths: Vec<&str> = self
+            .files
+            .iter()
+            .map(|file| file.relative_path.as_str())
+            .collect();
+        FileList::resolve(root, &paths.join("\n"))
+    }
+
+    /// Whether the plan covers this file with this content.
+    pub fn approves(&self, relative_path: &str, sha256: &str) -> bool {
+        self.files
+            .iter()
+            .any(|file| file.relative_path == relative_path && file.sha256 == sha256)
+    }
+
+    pub fn covers(&self, relative_path: &str) -> bool {
+        self.files
+            .iter()
+            .any(|file| file.relative_path == relative_path)
+    }
+}
+
+/// Whether uploads need an approved plan, kept in the data directory for
+/// the window and headless uploads alike. The requirement is locked with
+/// the approval key, so whoever runs uploads can't lift it without that key.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlanPolicy {
+    /// HMAC of a fixed label under the approval key, hex encoded. Set while
+    /// approved plans are required.
+    lock: Option<String>,
+}
+
+impl PlanPolicy {
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or("No data directory to keep the plan policy in")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("Failed to save plan policy: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize plan policy: {}", e))?;
+        fs::write(path, json).map_err(|e| format!("Failed to save plan policy: {}", e))
+    }
+
+    fn path() -> Option<PathBuf> {
+        dirs::data_local_dir().map(|dir| dir.join("claude-uploader").join(POLICY_FILE_NAME))
+    }
+
+    pub fn is_required(&self) -> bool {
+        self.lock.is_some()
+    }
+
+    pub fn require(&mut self, key: &str) -> Result<(), String> {
+        let mut mac = keyed_mac(key)?;
+        mac.update(POLICY_LOCK_LABEL);
+        self.lock = Some(to_hex(&mac.finalize().into_bytes()));
+        Ok(())
+    }
+
+    /// Stops requiring approved plans, given the key they were locked with.
+    pub fn lift(&mut self, key: &str) -> Result<(), String> {
+        let Some(lock) = &self.lock else {
+            return Ok(());
+        };
+        let mut mac = keyed_mac(key)?;
+        mac.update(POLICY_LOCK_LABEL);
+        mac.verify_slice(&from_hex(lock).unwrap_or_default())
+            .map_err(|_| {
+                "Only the approval key the requirement was set with lifts it".to_string()
+            })?;
+        self.lock = None;
+        Ok(())
+    }
+}
+
+fn keyed_mac(key: &str) -> Result<HmacSha256, String> {
+    if key.is_empty() {
+        return Err("Enter the approval key first".to_string());
+    }
+    HmacSha256::new_from_slice(key.as_bytes()).map_err(|e| e.to_string())
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan() -> UploadPlan {
+        UploadPlan {
+            created_at: SystemTime::UNIX_EPOCH,
+            folder_name: "project".to_string(),
+            git: None,
+            organization_id: Some("org".to_string()),
+            project_id: Some("project".to_string()),
+            files: vec![PlanFile {
+                relative_path: "src/main.rs".to_string(),
+                sha256: "ab".repeat(32),
+                size: 12,
+            }],
+            signature: String::new(),
+        }
+    }
+
+    fn temp_path(dir: &tempfile::TempDir) -> std::path::PathBuf {
+        dir.path().join("plan.json")
+    }
+
+    #[test]
+    fn signed_plan_loads_with_its_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = temp_path(&dir);
+        plan().save(&path, "secret").unwrap();
+        let loaded = UploadPlan::load(&path, "secret").unwrap();
+        assert_eq!(loaded.files, plan().files);
+    }
+
+    #[test]
+    fn edited_plan_or_wrong_key_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = temp_path(&dir);
+        plan().save(&path, "secret").unwrap();
+        assert!(UploadPlan::load(&path, "other").is_err());
+
+        let edited = fs::read_to_string(&path)
+            .unwrap()
+            .replace("src/main.rs", "src/evil.rs");
+        fs::write(&path, edited).unwrap();
+        assert!(UploadPlan::load(&path, "secret").is_err());
+    }
+
+    #[test]
+    fn signature_is_standard_hmac_sha256() {
+        // RFC 4231 test case 2
+        let mut mac = keyed_mac("Jefe").unwrap();
+        mac.update(b"what do ya want for nothing?");
+        assert_eq!(
+            to_hex(&mac.finalize().into_bytes()),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn requirement_is_lifted_only_with_its_key() {
+        let mut policy = PlanPolicy::default();
+        policy.require("secret").unwrap();
+        assert!(policy.is_required());
+
+        assert!(policy.lift("other").is_err());
+        assert!(policy.is_required());
+        assert!(policy.lift("").is_err());
+
+        policy.lift("secret").unwrap();
+        assert!(!policy.is_required());
+    }
+}
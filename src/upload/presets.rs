@@ -0,0 +1,81 @@
+use crate::upload::types::FilterOptions;
+use std::path::Path;
+
+/// Filter rules for a common kind of project, offered when its marker
+/// files are found in the selected folder.
+pub struct Preset {
+    pub name: &'static str,
+    /// Files in the folder root that identify the project type.
+    markers: &'static [&'static str],
+    include_rules: &'static [&'static str],
+    ignore_rules: &'static [&'static str],
+    extra_extensions: &'static [&'static str],
+}
+
+const PRESETS: [Preset; 4] = [
+    Preset {
+        name: "Rust crate",
+        markers: &["Cargo.toml"],
+        include_rules: &["/src/", "/Cargo.toml", "/README.md", "/build.rs"],
+        ignore_rules: &["/target/", "tests/fixtures/"],
+        extra_extensions: &[],
+    },
+    Preset {
+        name: "Next.js",
+        markers: &["next.config.js", "next.config.mjs", "next.config.ts"],
+        include_rules: &[
+            "/app/",
+            "/pages/",
+            "/components/",
+            "/lib/",
+            "/src/",
+            "/package.json",
+            "/tsconfig.json",
+            "/next.config.*",
+        ],
+        ignore_rules: &["/.next/", "/out/", "*.min.js"],
+        extra_extensions: &["mjs", "cjs"],
+    },
+    Preset {
+        name: "Django",
+        markers: &["manage.py"],
+        include_rules: &[
+            "*.py",
+            "templates/",
+            "/requirements.txt",
+            "/pyproject.toml",
+            "/README.md",
+        ],
+        ignore_rules: &["migrations/", "staticfiles/", "__pycache__/"],
+        extra_extensions: &[],
+    },
+    Preset {
+        name: "Flutter",
+        markers: &["pubspec.yaml"],
+        include_rules: &["/lib/", "/pubspec.yaml", "/README.md"],
+        ignore_rules: &["/build/", ".dart_tool/", "*.g.dart", "*.freezed.dart"],
+        extra_extensions: &["dart"],
+    },
+];
+
+impl Preset {
+    /// The presets whose marker files exist in `folder_path`.
+    pub fn detect(folder_path: &Path) -> Vec<&'static Preset> {
+        PRESETS
+            .iter()
+            .filter(|preset| {
+                preset
+                    .markers
+                    .iter()
+                    .any(|marker| folder_path.join(marker).is_file())
+            })
+            .collect()
+    }
+
+    /// Replaces the rule lists of `options` with the preset's.
+    pub fn apply(&self, options: &mut FilterOptions) {
+        options.include_rules = self.include_rules.join("\n");
+        options.ignore_rules = self.ignore_rules.join("\n");
+        options.extra_extensions = self.extra_extensions.join(", ");
+    }
+}
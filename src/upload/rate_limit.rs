@@ -0,0 +1,92 @@
+use crate::upload::types::{ErrorKind, PacingPolicy, UploadError};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Spaces out modifying requests and backs off when claude.ai answers with
+/// 429. Clones share their state, so the upload and delete phases of a run
+/// draw from the same budget.
+#[derive(Clone)]
+pub struct RateLimiter {
+    inner: Arc<Mutex<LimiterState>>,
+}
+
+struct LimiterState {
+    policy: PacingPolicy,
+    next_slot: Instant,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(PacingPolicy::default())
+    }
+}
+
+impl RateLimiter {
+    pub fn new(policy: PacingPolicy) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(LimiterState {
+                policy,
+                next_slot: Instant::now(),
+            })),
+        }
+    }
+
+    pub fn configure(&self, policy: PacingPolicy) {
+        self.inner.lock().unwrap().policy = policy;
+    }
+
+    /// Runs `request`, retrying it with exponential backoff while the server
+    /// keeps rate limiting us.
+    pub async fn run<T, F, Fut>(&self, mut request: F) -> Result<T, UploadError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, UploadError>>,
+    {
+        let mut backoff = INITIAL_BACKOFF;
+        let mut retries = 0;
+        loop {
+            self.wait_for_slot().await;
+
+            match request().await {
+                Err(error)
+                    if error.kind == ErrorKind::RateLimited && retries < self.max_retries() =>
+                {
+                    retries += 1;
+                    println!(
+                        "Rate limited, backing off for {:?} (retry {}/{})",
+                        backoff,
+                        retries,
+                        self.max_retries()
+                    );
+                    self.hold_off(backoff);
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn max_retries(&self) -> u32 {
+        self.inner.lock().unwrap().policy.rate_limit_retries
+    }
+
+    async fn wait_for_slot(&self) {
+        let slot = {
+            let mut state = self.inner.lock().unwrap();
+            let slot = state.next_slot.max(Instant::now());
+            state.next_slot = slot + state.policy.request_interval;
+            slot
+        };
+        tokio::time::sleep_until(slot.into()).await;
+    }
+
+    /// Pushes the next slot back for everyone sharing this limiter.
+    fn hold_off(&self, delay: Duration) {
+        let mut state = self.inner.lock().unwrap();
+        state.next_slot = state.next_slot.max(Instant::now() + delay);
+    }
+}
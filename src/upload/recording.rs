@@ -0,0 +1,137 @@
+use crate::upload::types::{ErrorKind, UploadError};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Directory to write request/response fixtures into.
+const RECORD_ENV: &str = "CLAUDE_UPLOADER_RECORD";
+/// Directory of fixtures to answer requests from instead of the network.
+const REPLAY_ENV: &str = "CLAUDE_UPLOADER_REPLAY";
+
+/// One request/response pair as stored in a fixture file. Headers are never
+/// stored, and the organization and project ids are replaced by
+/// placeholders, so fixtures hold no credentials.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    pub method: String,
+    pub path: String,
+    #[serde(default)]
+    pub request_body: Option<serde_json::Value>,
+    pub status: u16,
+    pub response_body: String,
+}
+
+/// Records or replays the API traffic of every client in the process,
+/// depending on the environment.
+#[derive(Debug, Clone)]
+pub enum Fixtures {
+    Record(Arc<Recorder>),
+    Replay(Arc<Replay>),
+}
+
+impl Fixtures {
+    /// Set up once from `CLAUDE_UPLOADER_RECORD` or `CLAUDE_UPLOADER_REPLAY`
+    /// and shared by all clients, so recordings are numbered in order.
+    pub fn from_env() -> Option<Fixtures> {
+        static FIXTURES: OnceLock<Option<Fixtures>> = OnceLock::new();
+        FIXTURES
+            .get_or_init(|| {
+                if let Some(dir) = std::env::var_os(REPLAY_ENV) {
+                    match Replay::load(Path::new(&dir)) {
+                        Ok(replay) => {
+                            println!("Replaying API fixtures from {:?}", dir);
+                            return Some(Fixtures::Replay(Arc::new(replay)));
+                        }
+                        Err(e) => println!("Failed to load fixtures from {:?}: {}", dir, e),
+                    }
+                }
+                std::env::var_os(RECORD_ENV).map(|dir| {
+                    println!("Recording API fixtures to {:?}", dir);
+                    Fixtures::Record(Arc::new(Recorder::new(PathBuf::from(dir))))
+                })
+            })
+            .clone()
+    }
+}
+
+#[derive(Debug)]
+pub struct Recorder {
+    dir: PathBuf,
+    next: AtomicUsize,
+}
+
+impl Recorder {
+    fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn record(&self, exchange: &Exchange) {
+        let index = self.next.fetch_add(1, Ordering::SeqCst);
+        let path = self.dir.join(format!(
+            "{:04}-{}.json",
+            index,
+            exchange.method.to_lowercase()
+        ));
+
+        let result = fs::create_dir_all(&self.dir).and_then(|_| {
+            let json = serde_json::to_string_pretty(exchange)?;
+            fs::write(&path, json)
+        });
+        if let Err(e) = result {
+            println!("Failed to record {:?}: {}", path, e);
+        }
+    }
+}
+
+/// Answers requests with recorded exchanges, in the order they were
+/// recorded. The client tests replay the recordings in `src/upload/fixtures`.
+#[derive(Debug)]
+pub struct Replay {
+    exchanges: Mutex<VecDeque<Exchange>>,
+}
+
+impl Replay {
+    pub fn load(dir: &Path) -> std::io::Result<Self> {
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().map(|ext| ext == "json").unwrap_or(false))
+            .collect();
+        paths.sort();
+
+        let mut exchanges = VecDeque::new();
+        for path in paths {
+            let json = fs::read_to_string(&path)?;
+            exchanges.push_back(serde_json::from_str(&json)?);
+        }
+        Ok(Self {
+            exchanges: Mutex::new(exchanges),
+        })
+    }
+
+    /// The next recorded exchange, which must be for the same request.
+    pub fn next(&self, method: &str, path: &str) -> Result<Exchange, UploadError> {
+        let exchange = self.exchanges.lock().unwrap().pop_front().ok_or_else(|| {
+            UploadError::new(
+                ErrorKind::Other,
+                format!("No recorded response left for {} {}", method, path),
+            )
+        })?;
+
+        if exchange.method != method || exchange.path != path {
+            return Err(UploadError::new(
+                ErrorKind::Other,
+                format!(
+                    "Expected {} {} but the recording has {} {}",
+                    method, path, exchange.method, exchange.path
+                ),
+            ));
+        }
+        Ok(exchange)
+    }
+}
@@ -0,0 +1,153 @@
+use crate::upload::sync_info::SyncInfo;
+use crate::utils::file_size::FileSizeUtils;
+use crate::utils::number_format::NumberFormat;
+use crate::utils::time_format::TimeFormat;
+use std::time::SystemTime;
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+    h1{font-size:1.4em}ul{list-style:none;padding-left:1.2em}\
+    .meta{color:#777;font-size:.9em;margin-left:.6em}\
+    .failed{color:#c33}table{border-collapse:collapse}\
+    td{padding:2px 12px 2px 0;vertical-align:top}";
+
+/// A selected file, with how its upload ended if a run covered it.
+#[derive(Debug, Clone)]
+pub struct ReportFile {
+    pub relative_path: String,
+    pub size: u64,
+    pub tokens: Option<usize>,
+    pub outcome: Option<String>,
+    pub failed: bool,
+}
+
+/// A file that was left out, and why.
+#[derive(Debug, Clone)]
+pub struct ReportExclusion {
+    pub relative_path: String,
+    pub reason: String,
+}
+
+/// Standalone HTML summary of what would be or was uploaded, for a reviewer
+/// who has to approve what leaves the machine. Holds paths and sizes only,
+/// never file content.
+#[derive(Debug, Clone)]
+pub struct SelectionReport {
+    pub info: SyncInfo,
+    pub files: Vec<ReportFile>,
+    pub exclusions: Vec<ReportExclusion>,
+}
+
+impl SelectionReport {
+    pub fn to_html(&self, generated_at: SystemTime) -> String {
+        let total_size: u64 = self.files.iter().map(|file| file.size).sum();
+        let total_tokens: usize = self.files.iter().filter_map(|file| file.tokens).sum();
+
+        let mut html = String::from("<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\">");
+        html.push_str(&format!(
+            "<title>Upload selection: {}</title><style>{}</style></head><body>\n",
+            escape(&self.info.folder_name),
+            STYLE
+        ));
+        html.push_str(&format!(
+            "<h1>Upload selection: {}</h1>\n<table>\n",
+            escape(&self.info.folder_name)
+        ));
+        let mut row = |label: &str, value: String| {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td></tr>\n",
+                label,
+                escape(&value)
+            ));
+        };
+        row("Generated", TimeFormat::timestamp(generated_at));
+        if let Some(git) = &self.info.git {
+            row("Git commit", git.label());
+        }
+        row(
+            "Files",
+            format!(
+                "{}, {}, ~{} tokens",
+                NumberFormat::thousands(self.files.len()),
+                FileSizeUtils::format_size(total_size),
+                NumberFormat::thousands(total_tokens)
+            ),
+        );
+        if !self.info.selected_sections.is_empty() {
+            row("Sections", self.info.selected_sections.join(", "));
+        }
+        if !self.info.excluded_sections.is_empty() {
+            row("Excluded sections", self.info.excluded_sections.join(", "));
+        }
+        html.push_str("</table>\n<h2>Files</h2>\n");
+
+        let mut files: Vec<&ReportFile> = self.files.iter().collect();
+        files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+        push_tree(&mut html, &files);
+
+        if !self.exclusions.is_empty() {
+            html.push_str("<h2>Left out</h2>\n<table>\n");
+            for exclusion in &self.exclusions {
+                html.push_str(&format!(
+                    "<tr><td>{}</td><td class=\"meta\">{}</td></tr>\n",
+                    escape(&exclusion.relative_path),
+                    escape(&exclusion.reason)
+                ));
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("</body></html>\n");
+        html
+    }
+}
+
+/// Nested lists following the directories of the sorted `files`.
+fn push_tree(html: &mut String, files: &[&ReportFile]) {
+    let mut open_dirs: Vec<&str> = Vec::new();
+    html.push_str("<ul>\n");
+
+    for file in files {
+        let mut components: Vec<&str> = file.relative_path.split('/').collect();
+        let file_name = components.pop().unwrap_or_default();
+
+        let shared = open_dirs
+            .iter()
+            .zip(&components)
+            .take_while(|(open, dir)| open == dir)
+            .count();
+        for _ in shared..open_dirs.len() {
+            html.push_str("</ul></li>\n");
+        }
+        open_dirs.truncate(shared);
+        for dir in &components[shared..] {
+            html.push_str(&format!("<li>📁 {}/<ul>\n", escape(dir)));
+            open_dirs.push(*dir);
+        }
+
+        let mut meta = FileSizeUtils::format_size(file.size);
+        if let Some(tokens) = file.tokens {
+            meta.push_str(&format!(" · ~{} tokens", NumberFormat::thousands(tokens)));
+        }
+        if let Some(outcome) = &file.outcome {
+            meta.push_str(&format!(" · {}", outcome));
+        }
+        html.push_str(&format!(
+            "<li{}>{}<span class=\"meta\">{}</span></li>\n",
+            if file.failed { " class=\"failed\"" } else { "" },
+            escape(file_name),
+            escape(&meta)
+        ));
+    }
+
+    for _ in &open_dirs {
+        html.push_str("</ul></li>\n");
+    }
+    html.push_str("</ul>\n");
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
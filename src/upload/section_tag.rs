@@ -0,0 +1,15 @@
+/// Doc names of the form `[section] path/to/file`, so conversations can
+/// refer to a `.claudekeep` section and the project browser can filter by it.
+pub struct SectionTag;
+
+impl SectionTag {
+    pub fn doc_name(section: &str, relative_path: &str) -> String {
+        format!("[{}] {}", section, relative_path)
+    }
+
+    /// The section a tagged doc name starts with.
+    pub fn parse(doc_name: &str) -> Option<&str> {
+        let (section, _) = doc_name.strip_prefix('[')?.split_once("] ")?;
+        (!section.is_empty()).then_some(section)
+    }
+}
@@ -0,0 +1,105 @@
+use crate::utils::claude_keep::ClaudeKeepConfig;
+use std::path::Path;
+
+/// Files that almost certainly hold secrets, in `.claudekeep` syntax. They
+/// are never uploaded, whatever the extension lists, sections or filter
+/// rules say, unless the user confirms each one.
+pub const SENSITIVE_PATTERNS: [&str; 22] = [
+    "*.pem",
+    "*.key",
+    "*.p12",
+    "*.pfx",
+    "*.jks",
+    "*.keystore",
+    "*.kdbx",
+    "*.tfstate",
+    "id_rsa*",
+    "id_dsa*",
+    "id_ecdsa*",
+    "id_ed25519*",
+    ".ssh/",
+    ".aws/",
+    ".gnupg/",
+    ".docker/config.json",
+    ".env",
+    ".env.*",
+    ".netrc",
+    ".pgpass",
+    "credentials.json",
+    "service-account*.json",
+];
+
+/// A discovered file held back because it is on the deny-list.
+#[derive(Debug, Clone)]
+pub struct SensitiveFile {
+    pub relative_path: String,
+    pub pattern: &'static str,
+}
+
+pub struct SensitivePath;
+
+impl SensitivePath {
+    /// The deny-list pattern `relative_path` matches, in any letter case.
+    pub fn pattern(relative_path: &str) -> Option<&'static str> {
+        let relative_path = Path::new(relative_path);
+        SENSITIVE_PATTERNS
+            .iter()
+            .copied()
+            .find(|pattern| ClaudeKeepConfig::pattern_matches(pattern, relative_path, true))
+    }
+
+    /// The pattern that blocks `relative_path`, unless the user confirmed
+    /// uploading it.
+    pub fn blocking_pattern(relative_path: &str, confirmed: &[String]) -> Option<&'static str> {
+        if confirmed.iter().any(|path| path == relative_path) {
+            return None;
+        }
+        Self::pattern(relative_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_secrets_at_any_depth_and_case() {
+        let cases = [
+            ("config/.aws/credentials", Some(".aws/")),
+            (".env", Some(".env")),
+            ("web/.env", Some(".env")),
+            (".env.local", Some(".env.*")),
+            ("sub/id_rsa.pub", Some("id_rsa*")),
+            ("certs/KEY.PEM", Some("*.pem")),
+            ("environment.md", None),
+            ("src/env.rs", None),
+            ("docs/keys.md", None),
+        ];
+        for (relative_path, pattern) in cases {
+            assert_eq!(
+                SensitivePath::pattern(relative_path),
+                pattern,
+                "{}",
+                relative_path
+            );
+        }
+    }
+
+    #[test]
+    fn only_confirmed_paths_pass() {
+        let confirmed = vec!["deploy/.env".to_string()];
+        assert_eq!(
+            SensitivePath::blocking_pattern("deploy/.env", &confirmed),
+            None
+        );
+        assert_eq!(
+            SensitivePath::blocking_pattern(".env", &confirmed),
+            Some(".env")
+        );
+        assert_eq!(
+            SensitivePath::blocking_pattern("app/.env", &[]),
+            Some(".env")
+        );
+        assert_eq!(SensitivePath::blocking_pattern("README.md", &[]), None);
+    }
+}
@@ -0,0 +1,53 @@
+use crate::upload::types::FilterOptions;
+
+/// A built-in group of ignore patterns for common noise that can be
+/// switched on without writing the globs by hand.
+pub struct SmartRule {
+    pub name: &'static str,
+    pub description: &'static str,
+    patterns: &'static [&'static str],
+}
+
+pub const SMART_RULES: [SmartRule; 4] = [
+    SmartRule {
+        name: "Snapshots",
+        description: "Jest and insta snapshot files",
+        patterns: &["__snapshots__/", "*.snap", "*.snap.new"],
+    },
+    SmartRule {
+        name: "Test fixtures",
+        description: "fixtures/ and __fixtures__/ folders",
+        patterns: &["fixtures/", "__fixtures__/"],
+    },
+    SmartRule {
+        name: "Test data",
+        description: "testdata/ and test_data/ folders",
+        patterns: &["testdata/", "test_data/"],
+    },
+    SmartRule {
+        name: "Lock files",
+        description: "Cargo.lock, yarn.lock, pnpm-lock.yaml and other lock files",
+        patterns: &["*.lock", "pnpm-lock.yaml", "npm-shrinkwrap.json"],
+    },
+];
+
+impl SmartRule {
+    pub fn is_enabled(&self, options: &FilterOptions) -> bool {
+        options.smart_rules.iter().any(|name| name == self.name)
+    }
+
+    pub fn set_enabled(&self, options: &mut FilterOptions, enabled: bool) {
+        options.smart_rules.retain(|name| name != self.name);
+        if enabled {
+            options.smart_rules.push(self.name.to_string());
+        }
+    }
+
+    /// Ignore patterns of the rules enabled in `options`.
+    pub fn patterns(options: &FilterOptions) -> impl Iterator<Item = &'static str> + '_ {
+        SMART_RULES
+            .iter()
+            .filter(|rule| rule.is_enabled(options))
+            .flat_map(|rule| rule.patterns.iter().copied())
+    }
+}
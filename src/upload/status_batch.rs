@@ -0,0 +1,56 @@
+use crate::upload::types::FileStatus;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+
+/// Longest a status event waits for others before they are passed on.
+pub const STATUS_BATCH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Coalesces the status events of a run, so a worker reporting thousands of
+/// files a second costs the UI a handful of messages per frame.
+pub struct StatusBatch;
+
+impl StatusBatch {
+    /// A sender for the worker and the receiver of its events in batches.
+    /// Every event is passed on, in order, at most an interval late; the
+    /// rest follows as soon as all senders are dropped.
+    pub fn channel() -> (Sender<FileStatus>, Receiver<Vec<FileStatus>>) {
+        let (sender, events) = mpsc::channel();
+        let (batch_sender, batches) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            let mut batch = Vec::new();
+            let mut deadline: Option<Instant> = None;
+            loop {
+                let event = match deadline {
+                    Some(deadline) => {
+                        events.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                    }
+                    None => events.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                };
+                let disconnected = match event {
+                    Ok(status) => {
+                        batch.push(status);
+                        deadline.get_or_insert_with(|| Instant::now() + STATUS_BATCH_INTERVAL);
+                        false
+                    }
+                    Err(RecvTimeoutError::Timeout) => false,
+                    Err(RecvTimeoutError::Disconnected) => true,
+                };
+
+                // Checked after every event too, a steady stream never times out
+                let due = deadline.is_some_and(|deadline| Instant::now() >= deadline);
+                if (due || disconnected) && !batch.is_empty() {
+                    deadline = None;
+                    if batch_sender.send(std::mem::take(&mut batch)).is_err() {
+                        return;
+                    }
+                }
+                if disconnected {
+                    return;
+                }
+            }
+        });
+
+        (sender, batches)
+    }
+}
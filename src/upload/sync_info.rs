@@ -0,0 +1,53 @@
+use crate::utils::git_info::GitInfo;
+use crate::utils::time_format::TimeFormat;
+use std::time::SystemTime;
+
+pub const SYNC_INFO_DOC_NAME: &str = "SYNC_INFO.md";
+
+/// Contents of the `SYNC_INFO.md` doc refreshed after every run, so the model
+/// itself can tell how current the project knowledge is.
+#[derive(Debug, Clone)]
+pub struct SyncInfo {
+    pub folder_name: String,
+    pub git: Option<GitInfo>,
+    pub selected_sections: Vec<String>,
+    pub excluded_sections: Vec<String>,
+    /// Files selected for the run and how many of them were uploaded.
+    pub files: usize,
+    pub uploaded: usize,
+}
+
+impl SyncInfo {
+    pub fn to_markdown(&self, synced_at: SystemTime) -> String {
+        let mut markdown = String::from("# Sync info\n\n");
+        markdown.push_str(&format!(
+            "- Last synced: {}\n",
+            TimeFormat::timestamp(synced_at)
+        ));
+        markdown.push_str(&format!("- Source folder: {}\n", self.folder_name));
+        if let Some(git) = &self.git {
+            markdown.push_str(&format!("- Git commit: {}\n", git.label()));
+        }
+        markdown.push_str(&format!(
+            "- Files: {} selected, {} uploaded in this run\n",
+            self.files, self.uploaded
+        ));
+        if !self.selected_sections.is_empty() {
+            markdown.push_str(&format!(
+                "- Sections: {}\n",
+                self.selected_sections.join(", ")
+            ));
+        }
+        if !self.excluded_sections.is_empty() {
+            markdown.push_str(&format!(
+                "- Excluded sections: {}\n",
+                self.excluded_sections.join(", ")
+            ));
+        }
+        markdown.push_str(
+            "\nFiles not listed in the project were left out on purpose or did not \
+            exist at this commit.\n",
+        );
+        markdown
+    }
+}
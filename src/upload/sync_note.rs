@@ -0,0 +1,41 @@
+use crate::utils::git_info::GitInfo;
+use crate::utils::time_format::TimeFormat;
+use std::time::SystemTime;
+
+const SYNC_NOTE_PREFIX: &str = "Last synced from ";
+
+/// A line in the project description telling when and from which commit
+/// the docs were last synced.
+pub struct SyncNote;
+
+impl SyncNote {
+    pub fn line(folder_name: &str, git: Option<&GitInfo>, time: SystemTime) -> String {
+        let source = match git {
+            Some(git) => git.label(),
+            None => folder_name.to_string(),
+        };
+        format!(
+            "{}{} on {} by claude-uploader",
+            SYNC_NOTE_PREFIX,
+            source,
+            TimeFormat::date(time)
+        )
+    }
+
+    /// `description` with its previous sync note replaced by `note`, or with
+    /// `note` appended as the last line.
+    pub fn apply(description: &str, note: &str) -> String {
+        let mut lines: Vec<&str> = description
+            .lines()
+            .filter(|line| !line.starts_with(SYNC_NOTE_PREFIX))
+            .collect();
+        while lines.last().is_some_and(|line| line.trim().is_empty()) {
+            lines.pop();
+        }
+
+        if lines.is_empty() {
+            return note.to_string();
+        }
+        format!("{}\n\n{}", lines.join("\n"), note)
+    }
+}
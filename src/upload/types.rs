@@ -1,19 +1,502 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
 #[derive(Debug, Clone)]
 pub enum UploadStatus {
     Processing,
+    WaitingForAuth,
+    WaitingForNetwork,
+    CheckingSession {
+        attempt: u32,
+        max_attempts: u32,
+    },
+    /// Waiting to send the file again after a transient failure.
+    Retrying {
+        attempt: u32,
+        max_attempts: u32,
+    },
     Success,
-    Error(String),
+    Error(UploadError),
     Skipped(String),
 }
 
+impl UploadStatus {
+    /// Whether this is the outcome for the file rather than a step on the way.
+    pub fn is_final(&self) -> bool {
+        matches!(self, Self::Success | Self::Error(_) | Self::Skipped(_))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ErrorKind {
+    SessionExpired,
+    TooLarge,
+    InvalidEncoding,
+    RateLimited,
+    Offline,
+    Network,
+    Server,
+    Conflict,
+    NotPermitted,
+    Other,
+}
+
+impl ErrorKind {
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            401 | 403 => Self::SessionExpired,
+            413 => Self::TooLarge,
+            429 => Self::RateLimited,
+            500..=599 => Self::Server,
+            _ => Self::Other,
+        }
+    }
+
+    /// Errors that may disappear on their own if the request is repeated.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Self::RateLimited | Self::Offline | Self::Network | Self::Server
+        )
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::SessionExpired => "session expired",
+            Self::TooLarge => "too large",
+            Self::InvalidEncoding => "invalid encoding",
+            Self::RateLimited => "rate limited",
+            Self::Offline => "offline",
+            Self::Network => "network error",
+            Self::Server => "server error",
+            Self::Conflict => "edited in Claude since the last sync",
+            Self::NotPermitted => "not permitted",
+            Self::Other => "other error",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UploadError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl UploadError {
+    pub fn new(kind: ErrorKind, message: String) -> Self {
+        Self { kind, message }
+    }
+}
+
+impl fmt::Display for UploadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// What a status event refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Operation {
+    #[default]
+    Upload,
+    Verify,
+    Delete,
+    /// Outcome of a dry run, nothing was sent.
+    Preview,
+}
+
 #[derive(Debug, Clone)]
 pub struct FileStatus {
     pub name: String,
     pub status: UploadStatus,
+    pub operation: Operation,
+    pub path: Option<PathBuf>,
+    /// Path below the selected folder, to tell apart files with the same name.
+    pub relative_path: Option<String>,
+    pub size: Option<u64>,
+    pub modified: Option<SystemTime>,
+    /// How long the request took, for finished uploads.
+    pub duration: Option<Duration>,
+    /// Upload requests made for the file, counting resumes after a pause.
+    pub attempts: u32,
+    /// Caveat about an otherwise successful upload.
+    pub warning: Option<String>,
+}
+
+impl FileStatus {
+    pub fn new(name: String, status: UploadStatus) -> Self {
+        Self {
+            name,
+            status,
+            operation: Operation::Upload,
+            path: None,
+            relative_path: None,
+            size: None,
+            modified: None,
+            duration: None,
+            attempts: 0,
+            warning: None,
+        }
+    }
+
+    pub fn for_file(file: &DiscoveredFile, status: UploadStatus) -> Self {
+        Self {
+            name: file.file_name(),
+            status,
+            operation: Operation::Upload,
+            path: Some(file.path.clone()),
+            relative_path: Some(file.relative_path.clone()),
+            size: Some(file.size),
+            modified: file.modified,
+            duration: None,
+            attempts: 0,
+            warning: None,
+        }
+    }
+
+    /// The relative path for local files, the doc name otherwise.
+    pub fn display_name(&self) -> &str {
+        self.relative_path.as_deref().unwrap_or(&self.name)
+    }
+
+    pub fn with_timing(mut self, duration: Duration, attempts: u32) -> Self {
+        self.duration = Some(duration);
+        self.attempts = attempts;
+        self
+    }
+
+    pub fn with_warning(mut self, warning: impl Into<String>) -> Self {
+        self.warning = Some(warning.into());
+        self
+    }
+
+    /// Status of a doc that was already uploaded, e.g. while verifying or
+    /// deleting it.
+    pub fn for_operation(file: &UploadedFile, operation: Operation, status: UploadStatus) -> Self {
+        Self {
+            operation,
+            ..Self::new(file.name.clone(), status)
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
+pub struct DiscoveredFile {
+    pub path: PathBuf,
+    /// Path below the selected folder with `/` separators, stable across machines.
+    pub relative_path: String,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+impl DiscoveredFile {
+    pub fn from_path(root: &Path, path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        if !metadata.is_file() {
+            return None;
+        }
+
+        Some(Self::from_metadata(root, path, &metadata))
+    }
+
+    pub fn from_metadata(root: &Path, path: &Path, metadata: &fs::Metadata) -> Self {
+        let relative_path = path
+            .strip_prefix(root)
+            .unwrap_or(path)
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+
+        Self {
+            path: path.to_path_buf(),
+            relative_path,
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+        }
+    }
+
+    pub fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UploadedFile {
     pub name: String,
     pub uuid: String,
 }
+
+/// An organization the session's account belongs to.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RemoteOrganization {
+    pub uuid: String,
+    pub name: String,
+}
+
+/// A project as listed for an organization.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RemoteProject {
+    pub uuid: String,
+    pub name: String,
+    #[serde(default)]
+    pub archived_at: Option<String>,
+}
+
+/// A doc as listed by the project docs endpoint.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RemoteDoc {
+    pub uuid: String,
+    pub file_name: String,
+    #[serde(default)]
+    pub content: Option<String>,
+    /// ISO 8601 timestamp, so it sorts chronologically as a string.
+    #[serde(default)]
+    pub created_at: Option<String>,
+}
+
+impl RemoteDoc {
+    /// Size of the content in bytes, if the listing included it.
+    pub fn size(&self) -> Option<u64> {
+        self.content.as_ref().map(|content| content.len() as u64)
+    }
+
+    /// Creation time as "YYYY-MM-DD HH:MM".
+    pub fn created_label(&self) -> Option<String> {
+        let created_at = self.created_at.as_deref()?;
+        Some(
+            created_at
+                .chars()
+                .take(16)
+                .collect::<String>()
+                .replace('T', " "),
+        )
+    }
+}
+
+/// Which modifying requests the client may send.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Permissions {
+    pub upload: bool,
+    pub delete: bool,
+}
+
+impl Permissions {
+    pub const ALL: Self = Self {
+        upload: true,
+        delete: true,
+    };
+
+    pub const READ_ONLY: Self = Self {
+        upload: false,
+        delete: false,
+    };
+}
+
+impl Default for Permissions {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+/// How to handle a file whose doc was edited in the Claude UI after the last
+/// sync. Skipping needs no request and is handled in the UI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictResolution {
+    /// Leave the remote doc alone and treat the local version as synced.
+    KeepRemote,
+    Overwrite,
+}
+
+/// Pacing for requests that modify the project.
+#[derive(Debug, Clone, Copy)]
+pub struct PacingPolicy {
+    pub request_interval: Duration,
+    pub delete_concurrency: usize,
+    pub rate_limit_retries: u32,
+}
+
+impl Default for PacingPolicy {
+    fn default() -> Self {
+        Self {
+            request_interval: Duration::ZERO,
+            delete_concurrency: 1,
+            rate_limit_retries: 0,
+        }
+    }
+}
+
+/// How often the pre-flight session check is repeated before a run is
+/// declared failed.
+#[derive(Debug, Clone, Copy)]
+pub struct PreflightPolicy {
+    pub attempts: u32,
+    pub interval: Duration,
+}
+
+/// How often an upload that failed with a network or server error is tried
+/// in total, waiting twice as long before each retry as before the last.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub attempts: u32,
+    pub initial_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 1,
+            initial_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RetryPolicy {
+    const MAX_DELAY: Duration = Duration::from_secs(60);
+
+    /// Wait before the `retry`th retry, with up to half of it added as
+    /// jitter so parallel runs don't retry in lockstep.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let base = self
+            .initial_delay
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(Self::MAX_DELAY);
+        let nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|elapsed| elapsed.subsec_nanos())
+            .unwrap_or_default();
+        let jitter_ms = base.as_millis() as u64 / 2;
+        base + Duration::from_millis(nanos as u64 % (jitter_ms + 1))
+    }
+}
+
+/// Files with less content than this are skipped at upload, e.g. one-line
+/// `index.ts` re-exports that would waste a doc slot. Zero turns a check off.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContentThreshold {
+    pub min_bytes: usize,
+    /// Characters other than whitespace.
+    pub min_chars: usize,
+}
+
+impl ContentThreshold {
+    pub fn is_below(&self, content: &str) -> bool {
+        if content.len() < self.min_bytes {
+            return true;
+        }
+        self.min_chars > 0
+            && content
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .take(self.min_chars)
+                .count()
+                < self.min_chars
+    }
+}
+
+/// Which files discovery picks up, on top of the built-in rules.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilterOptions {
+    /// Match paths, rules and ignored names regardless of case. Extensions
+    /// always do.
+    pub case_insensitive: bool,
+    /// Comma-separated extensions to upload in addition to the built-in ones.
+    pub extra_extensions: String,
+    /// Comma-separated extensions never to upload.
+    pub excluded_extensions: String,
+    /// One `.claudekeep`-style pattern per line; when any are given, only
+    /// matching files are uploaded.
+    pub include_rules: String,
+    /// One `.claudekeep`-style pattern per line; matching files are skipped.
+    pub ignore_rules: String,
+    /// How many directory levels below the folder to walk; files directly in
+    /// the folder are at level 1.
+    pub max_depth: Option<usize>,
+    /// Names of the enabled built-in smart rules.
+    pub smart_rules: Vec<String>,
+    pub min_content: ContentThreshold,
+    /// Upload files that look like vendored third-party code instead of
+    /// leaving them out.
+    pub include_vendored: bool,
+    /// A [`FilterExpr`](crate::upload::FilterExpr) every file has to match;
+    /// empty matches all files.
+    pub expression: String,
+    /// Regex a file's content has to match; empty uploads any content.
+    pub content_include: String,
+    /// Regex that leaves out files whose content matches it.
+    pub content_exclude: String,
+    /// Relative paths on the sensitive deny-list that the user confirmed
+    /// uploading anyway.
+    pub confirmed_sensitive: Vec<String>,
+}
+
+impl Default for FilterOptions {
+    fn default() -> Self {
+        Self {
+            case_insensitive: cfg!(windows),
+            extra_extensions: String::new(),
+            excluded_extensions: String::new(),
+            include_rules: String::new(),
+            ignore_rules: String::new(),
+            max_depth: None,
+            smart_rules: Vec::new(),
+            min_content: ContentThreshold::default(),
+            include_vendored: false,
+            expression: String::new(),
+            content_include: String::new(),
+            content_exclude: String::new(),
+            confirmed_sensitive: Vec::new(),
+        }
+    }
+}
+
+impl FilterOptions {
+    pub fn extra_extensions(&self) -> impl Iterator<Item = &str> {
+        Self::extension_list(&self.extra_extensions)
+    }
+
+    pub fn excluded_extensions(&self) -> impl Iterator<Item = &str> {
+        Self::extension_list(&self.excluded_extensions)
+    }
+
+    pub fn include_rules(&self) -> impl Iterator<Item = &str> {
+        Self::rule_list(&self.include_rules)
+    }
+
+    pub fn ignore_rules(&self) -> impl Iterator<Item = &str> {
+        Self::rule_list(&self.ignore_rules)
+    }
+
+    fn rule_list(rules: &str) -> impl Iterator<Item = &str> {
+        rules
+            .lines()
+            .map(str::trim)
+            .filter(|rule| !rule.is_empty() && !rule.starts_with('#'))
+    }
+
+    fn extension_list(list: &str) -> impl Iterator<Item = &str> {
+        list.split(',')
+            .map(|extension| extension.trim().trim_start_matches('.'))
+            .filter(|extension| !extension.is_empty())
+    }
+
+    pub fn names_match(&self, a: &str, b: &str) -> bool {
+        if self.case_insensitive {
+            a.eq_ignore_ascii_case(b)
+        } else {
+            a == b
+        }
+    }
+}
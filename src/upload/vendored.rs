@@ -0,0 +1,75 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::{Component, Path};
+
+const VENDOR_DIRS: [&str; 5] = [
+    "vendor",
+    "vendors",
+    "third_party",
+    "third-party",
+    "bower_components",
+];
+
+const BUNDLE_SUFFIXES: [&str; 4] = [".min.js", ".min.mjs", ".min.css", ".bundle.js"];
+
+/// Only scripts and stylesheets are checked for minified or bundled content.
+const SNIFFED_EXTENSIONS: [&str; 4] = ["js", "mjs", "cjs", "css"];
+
+const SNIFF_BYTES: u64 = 4096;
+
+/// Hand-written code rarely has lines this long; minifiers emit little else.
+const MINIFIED_LINE_LENGTH: usize = 500;
+
+/// A discovered file that looks like third-party code.
+#[derive(Debug, Clone)]
+pub struct VendoredFile {
+    pub relative_path: String,
+    pub reason: &'static str,
+}
+
+pub struct VendoredCode;
+
+impl VendoredCode {
+    /// Why the file at `path` looks vendored, or `None` if it seems to be the
+    /// project's own. Scripts and stylesheets have their first few KB read.
+    pub fn reason(relative_path: &Path, path: &Path) -> Option<&'static str> {
+        let in_vendor_dir = relative_path.parent().is_some_and(|parent| {
+            parent.components().any(|component| match component {
+                Component::Normal(name) => name
+                    .to_str()
+                    .is_some_and(|name| VENDOR_DIRS.contains(&name.to_ascii_lowercase().as_str())),
+                _ => false,
+            })
+        });
+        if in_vendor_dir {
+            return Some("In a vendor directory");
+        }
+
+        let file_name = relative_path.file_name()?.to_str()?.to_ascii_lowercase();
+        if BUNDLE_SUFFIXES
+            .iter()
+            .any(|suffix| file_name.ends_with(suffix))
+        {
+            return Some("Minified bundle");
+        }
+
+        let extension = relative_path.extension()?.to_str()?.to_ascii_lowercase();
+        if !SNIFFED_EXTENSIONS.contains(&extension.as_str()) {
+            return None;
+        }
+        let mut head = String::new();
+        File::open(path)
+            .ok()?
+            .take(SNIFF_BYTES)
+            .read_to_string(&mut head)
+            .ok()?;
+
+        if head.trim_start().starts_with("/*!") || head.contains("@license") {
+            Some("Bundled library with a license banner")
+        } else if head.lines().any(|line| line.len() > MINIFIED_LINE_LENGTH) {
+            Some("Looks minified")
+        } else {
+            None
+        }
+    }
+}
@@ -1,13 +1,12 @@
-use glob::Pattern;
+use glob::{MatchOptions, Pattern};
 use std::collections::HashMap;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
 #[derive(Debug, Default, Clone)]
 pub struct ClaudeKeepConfig {
     pub sections: Vec<String>,
     pub patterns: HashMap<String, Vec<String>>,
-    folder_path: PathBuf,
 }
 
 impl ClaudeKeepConfig {
@@ -22,10 +21,17 @@ impl ClaudeKeepConfig {
         let content = fs::read_to_string(keep_path).ok()?;
         println!("File content:\n{}", content);
 
+        let config = Self::parse(&content);
+        println!("Final config: {:?}", config);
+        Some(config)
+    }
+
+    /// Reads `section:` headers, each followed by one pattern per line.
+    /// Patterns before the first header belong to no section and are dropped.
+    pub fn parse(content: &str) -> Self {
         let mut config = ClaudeKeepConfig {
             sections: Vec::new(),
             patterns: HashMap::new(),
-            folder_path: folder_path.to_path_buf(),
         };
 
         let mut current_section = String::new();
@@ -50,12 +56,16 @@ impl ClaudeKeepConfig {
             }
         }
 
-        println!("Final config: {:?}", config);
-        Some(config)
+        config
     }
 
-    pub fn should_include_file(&self, file_path: &Path, selected_sections: &[String]) -> bool {
-        // println!("Checking file: {:?}", file_path);
+    pub fn should_include_file(
+        &self,
+        relative_path: &Path,
+        selected_sections: &[String],
+        case_insensitive: bool,
+    ) -> bool {
+        // println!("Checking file: {:?}", relative_path);
         // println!("Selected sections: {:?}", selected_sections);
 
         if selected_sections.is_empty() {
@@ -63,39 +73,14 @@ impl ClaudeKeepConfig {
             return true;
         }
 
-        let relative_path = if let Ok(canonical_path) = file_path.canonicalize() {
-            if let Ok(relative) = canonical_path.strip_prefix(&self.folder_path) {
-                // println!("Relative path: {:?}", relative);
-                relative.to_path_buf()
-            } else {
-                // println!("Failed to create relative path");
-                return false;
-            }
-        } else {
-            // println!("Failed to canonicalize path");
-            return false;
-        };
-
         for section in selected_sections {
             // println!("Checking section: {}", section);
             if let Some(patterns) = self.patterns.get(section) {
                 for pattern in patterns {
                     // println!("Trying pattern: {}", pattern);
-                    let processed_pattern = if pattern.starts_with("**/") {
-                        pattern.to_string()
-                    } else {
-                        format!("**/{}", pattern)
-                    };
-                    // println!("Processed pattern: {}", processed_pattern);
-
-                    if let Ok(glob_pattern) = Pattern::new(&processed_pattern) {
-                        if glob_pattern.matches_path(&relative_path) {
-                            // println!("✅ Matched!");
-                            return true;
-                        }
-                        // println!("❌ No match");
-                    } else {
-                        // println!("Invalid pattern: {}", pattern);
+                    if Self::pattern_matches(pattern, relative_path, case_insensitive) {
+                        // println!("✅ Matched!");
+                        return true;
                     }
                 }
             }
@@ -104,4 +89,170 @@ impl ClaudeKeepConfig {
         // println!("No patterns matched for file");
         false
     }
+
+    /// Matches a single `.claudekeep` pattern against a path relative to the folder root.
+    ///
+    /// - `*.vue` / `components/**` match at any depth (implicit `**/` prefix)
+    /// - `/README.md` is anchored to the folder root
+    /// - `src/` matches every file below any directory named `src`
+    /// - `*.{ts,tsx}` expands to one pattern per alternative
+    ///
+    /// `*` and `?` also match across `/`, as they always have, so
+    /// `components/*` covers nested files too.
+    pub fn pattern_matches(pattern: &str, relative_path: &Path, case_insensitive: bool) -> bool {
+        let options = MatchOptions {
+            case_sensitive: !case_insensitive,
+            require_literal_separator: false,
+            require_literal_leading_dot: false,
+        };
+
+        Self::expand_braces(pattern.trim()).iter().any(|expanded| {
+            Pattern::new(&Self::to_glob(expanded))
+                .map(|glob_pattern| glob_pattern.matches_path_with(relative_path, options))
+                .unwrap_or(false)
+        })
+    }
+
+    fn to_glob(pattern: &str) -> String {
+        let (anchored, pattern) = match pattern.strip_prefix('/') {
+            Some(rest) => (true, rest),
+            None => (false, pattern),
+        };
+        let (directory, pattern) = match pattern.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, pattern),
+        };
+
+        let mut glob = if anchored || pattern.starts_with("**/") {
+            pattern.to_string()
+        } else {
+            format!("**/{}", pattern)
+        };
+        if directory {
+            glob.push_str("/**");
+        }
+        glob
+    }
+
+    fn expand_braces(pattern: &str) -> Vec<String> {
+        let Some(open) = pattern.find('{') else {
+            return vec![pattern.to_string()];
+        };
+
+        let mut depth = 0;
+        let mut close = None;
+        let mut alternatives = Vec::new();
+        let mut start = open + 1;
+        for (idx, ch) in pattern[open..].char_indices() {
+            let idx = open + idx;
+            match ch {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        alternatives.push(&pattern[start..idx]);
+                        close = Some(idx);
+                        break;
+                    }
+                }
+                ',' if depth == 1 => {
+                    alternatives.push(&pattern[start..idx]);
+                    start = idx + 1;
+                }
+                _ => {}
+            }
+        }
+
+        // Unbalanced braces are treated literally
+        let Some(close) = close else {
+            return vec![pattern.to_string()];
+        };
+
+        let prefix = &pattern[..open];
+        let suffix = &pattern[close + 1..];
+        alternatives
+            .into_iter()
+            .flat_map(|alternative| {
+                Self::expand_braces(&format!("{}{}{}", prefix, alternative, suffix))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        ClaudeKeepConfig::pattern_matches(pattern, Path::new(path), false)
+    }
+
+    #[test]
+    fn parses_sections_in_order() {
+        let config = ClaudeKeepConfig::parse(
+            "stray.rs\n\nfrontend:\n  *.vue\n  components/\nbackend:\n*.rs\n",
+        );
+        assert_eq!(config.sections, vec!["frontend", "backend"]);
+        assert_eq!(config.patterns["frontend"], vec!["*.vue", "components/"]);
+        assert_eq!(config.patterns["backend"], vec!["*.rs"]);
+    }
+
+    #[test]
+    fn parses_empty_sections() {
+        let config = ClaudeKeepConfig::parse("docs:\nfrontend:\n*.vue\n");
+        assert_eq!(config.sections, vec!["docs", "frontend"]);
+        assert!(config.patterns["docs"].is_empty());
+    }
+
+    #[test]
+    fn patterns_match_at_any_depth() {
+        assert!(matches("*.vue", "App.vue"));
+        assert!(matches("*.vue", "src/components/App.vue"));
+        assert!(!matches("*.vue", "src/App.ts"));
+    }
+
+    #[test]
+    fn star_matches_nested_files() {
+        assert!(matches("components/*", "components/Button.vue"));
+        assert!(matches("components/*", "components/forms/Input.vue"));
+        assert!(matches("src/*.rs", "src/app/mod.rs"));
+    }
+
+    #[test]
+    fn leading_slash_anchors_to_the_root() {
+        assert!(matches("/README.md", "README.md"));
+        assert!(!matches("/README.md", "docs/README.md"));
+        assert!(matches("README.md", "docs/README.md"));
+    }
+
+    #[test]
+    fn trailing_slash_matches_directories() {
+        assert!(matches("src/", "src/main.rs"));
+        assert!(matches("src/", "crates/core/src/lib.rs"));
+        assert!(!matches("src/", "src.rs"));
+    }
+
+    #[test]
+    fn braces_expand_to_alternatives() {
+        assert!(matches("*.{ts,tsx}", "src/index.ts"));
+        assert!(matches("*.{ts,tsx}", "src/App.tsx"));
+        assert!(!matches("*.{ts,tsx}", "src/App.js"));
+        assert!(matches("*.{ts", "a/b.{ts"));
+    }
+
+    #[test]
+    fn case_sensitivity_follows_the_option() {
+        let path = Path::new("src/App.VUE");
+        assert!(!ClaudeKeepConfig::pattern_matches("*.vue", path, false));
+        assert!(ClaudeKeepConfig::pattern_matches("*.vue", path, true));
+    }
+
+    #[test]
+    fn no_selected_sections_include_everything() {
+        let config = ClaudeKeepConfig::parse("frontend:\n*.vue\nbackend:\n*.rs\n");
+        let path = Path::new("src/main.rs");
+        assert!(config.should_include_file(path, &[], false));
+        assert!(config.should_include_file(path, &["backend".to_string()], false));
+        assert!(!config.should_include_file(path, &["frontend".to_string()], false));
+    }
 }
@@ -0,0 +1,117 @@
+use std::fs;
+use std::path::Path;
+
+const SOURCE_DIRS: [&str; 8] = ["src", "lib", "app", "server", "api", "pkg", "cmd", "crates"];
+const FRONTEND_DIRS: [&str; 7] = [
+    "components",
+    "pages",
+    "layouts",
+    "composables",
+    "stores",
+    "public",
+    "styles",
+];
+const TEST_DIRS: [&str; 5] = ["tests", "test", "__tests__", "spec", "e2e"];
+const DOC_DIRS: [&str; 3] = ["docs", "doc", "documentation"];
+const CONFIG_FILES: [&str; 12] = [
+    "package.json",
+    "tsconfig.json",
+    "Cargo.toml",
+    "pyproject.toml",
+    "requirements.txt",
+    "go.mod",
+    "nuxt.config.ts",
+    "next.config.js",
+    "vite.config.ts",
+    "svelte.config.js",
+    "docker-compose.yml",
+    "Dockerfile",
+];
+
+pub struct ClaudeKeepTemplate;
+
+impl ClaudeKeepTemplate {
+    /// A starter `.claudekeep` for `folder_path`, with one section per kind
+    /// of top-level content found: source, frontend, tests, docs and config.
+    pub fn generate(folder_path: &Path) -> String {
+        let has_dir = |name: &&str| folder_path.join(name).is_dir();
+        let has_file = |name: &&str| folder_path.join(name).is_file();
+
+        let mut sections: Vec<(&str, Vec<String>)> = Vec::new();
+
+        let source: Vec<String> = SOURCE_DIRS
+            .iter()
+            .filter(has_dir)
+            .map(|dir| format!("/{}/", dir))
+            .collect();
+        if !source.is_empty() {
+            sections.push(("source", source));
+        }
+
+        let frontend: Vec<String> = FRONTEND_DIRS
+            .iter()
+            .filter(has_dir)
+            .map(|dir| format!("/{}/", dir))
+            .collect();
+        if !frontend.is_empty() {
+            sections.push(("frontend", frontend));
+        }
+
+        let tests: Vec<String> = TEST_DIRS
+            .iter()
+            .filter(has_dir)
+            .map(|dir| format!("{}/", dir))
+            .chain(["*.test.*".to_string(), "*.spec.*".to_string()])
+            .collect();
+        sections.push(("tests", tests));
+
+        let mut docs: Vec<String> = DOC_DIRS
+            .iter()
+            .filter(has_dir)
+            .map(|dir| format!("/{}/", dir))
+            .collect();
+        docs.push("*.md".to_string());
+        sections.push(("docs", docs));
+
+        let config: Vec<String> = CONFIG_FILES
+            .iter()
+            .filter(has_file)
+            .map(|file| format!("/{}", file))
+            .collect();
+        if !config.is_empty() {
+            sections.push(("config", config));
+        }
+
+        // Fall back to a single catch-all section for flat folders
+        if sections
+            .iter()
+            .all(|(name, _)| matches!(*name, "tests" | "docs"))
+        {
+            sections.insert(0, ("all", vec!["**".to_string()]));
+        }
+
+        let mut template = String::new();
+        for (name, patterns) in sections {
+            if !template.is_empty() {
+                template.push('\n');
+            }
+            template.push_str(&format!("{}:\n", name));
+            for pattern in patterns {
+                template.push_str(&format!("{}\n", pattern));
+            }
+        }
+        template
+    }
+
+    /// Writes the generated template, refusing to replace an existing file.
+    pub fn write(folder_path: &Path) -> std::io::Result<()> {
+        let path = folder_path.join(".claudekeep");
+        if path.exists() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                ".claudekeep already exists",
+            ));
+        }
+        fs::write(path, Self::generate(folder_path))
+    }
+}
@@ -1,11 +1,43 @@
-use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
-use std::str::FromStr;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, COOKIE};
+use std::iter::Peekable;
+use std::str::{Chars, FromStr};
+
+/// curl options whose value is the next word.
+const OPTIONS_WITH_VALUE: [&str; 22] = [
+    "-X",
+    "--request",
+    "-H",
+    "--header",
+    "-b",
+    "--cookie",
+    "-d",
+    "--data",
+    "--data-raw",
+    "--data-binary",
+    "--data-ascii",
+    "--data-urlencode",
+    "-A",
+    "--user-agent",
+    "-e",
+    "--referer",
+    "-u",
+    "--user",
+    "-o",
+    "--output",
+    "-m",
+    "--max-time",
+];
 
 #[derive(Clone, Default)]
 pub struct CurlParser {
     pub headers: Option<HeaderMap>,
     pub organization_id: Option<String>,
     pub project_id: Option<String>,
+    /// Project picked in the app, used instead of the one in the request URL.
+    pub project_override: Option<String>,
+    /// Organization picked in the app, used instead of the one in the
+    /// request URL and the `lastActiveOrg` cookie.
+    pub organization_override: Option<String>,
 }
 
 impl CurlParser {
@@ -14,57 +46,63 @@ impl CurlParser {
     }
 
     pub fn parse(&mut self, curl_text: &str) -> Result<(), String> {
-        // Extract organization ID
-        let org_id = curl_text
-            .find("/organizations/")
-            .and_then(|start_idx| {
-                let start = start_idx + "/organizations/".len();
-                let remaining = &curl_text[start..];
-                remaining
-                    .find('/')
-                    .map(|end_idx| remaining[..end_idx].to_string())
-            })
-            .ok_or("Could not find organization ID in curl command".to_string())?;
-
-        // Extract project ID
-        let proj_id = curl_text
-            .find("/projects/")
-            .and_then(|start_idx| {
-                let start = start_idx + "/projects/".len();
-                let remaining = &curl_text[start..];
-                remaining
-                    .find('/')
-                    .map(|end_idx| remaining[..end_idx].to_string())
-            })
-            .ok_or("Could not find project ID in curl command".to_string())?;
-
-        // Extract headers
-        let mut headers = HeaderMap::new();
-        for line in curl_text.lines() {
-            if !line.starts_with("  -H '") {
-                continue;
-            }
+        let words = Self::shell_words(curl_text);
+
+        // Only the request URL counts, ids elsewhere (e.g. in a referer
+        // header for another project) are ignored
+        let url =
+            Self::request_url(&words).ok_or("Could not find the request URL in curl command")?;
+        let org_id = match &self.organization_override {
+            Some(organization_id) => organization_id.clone(),
+            None => Self::path_segment_after(url, "organizations")
+                .ok_or("Could not find organization ID in curl command".to_string())?,
+        };
+        let proj_id = match &self.project_override {
+            Some(project_id) => project_id.clone(),
+            None => Self::path_segment_after(url, "projects")
+                .ok_or("Could not find project ID in curl command".to_string())?,
+        };
 
-            let content = line
-                .trim_start_matches("  -H '")
-                .trim_end_matches('\'')
-                .to_string();
+        // The ids end up in URLs and headers, so anything but a plain id is rejected
+        if !Self::is_valid_id(&org_id) {
+            return Err("Organization ID in curl command is not valid".to_string());
+        }
+        if !Self::is_valid_id(&proj_id) {
+            return Err("Project ID in curl command is not valid".to_string());
+        }
 
-            let parts: Vec<&str> = content.split(": ").collect();
-            if parts.len() != 2 {
+        // Extract headers from the shell words, so quoting and line
+        // continuations are handled like the shell would
+        let mut headers = HeaderMap::new();
+        let mut words = words.iter();
+        while let Some(word) = words.next() {
+            let header = match word.as_str() {
+                "-H" | "--header" => words.next().cloned(),
+                "-b" | "--cookie" => words.next().map(|cookie| format!("cookie: {}", cookie)),
+                _ => None,
+            };
+            let Some(header) = header else {
                 continue;
-            }
+            };
 
-            let key = parts[0].to_lowercase();
-            let value = parts[1];
+            // Values may contain colons themselves, e.g. base64 cookie payloads
+            let Some((key, value)) = header.split_once(':') else {
+                continue;
+            };
+            let key = key.trim().to_lowercase();
+            let value = Self::unfold(value);
 
             if let Ok(header_name) = HeaderName::from_str(&key) {
-                if let Ok(header_value) = HeaderValue::from_str(value) {
+                if let Ok(header_value) = HeaderValue::from_str(&value) {
                     headers.insert(header_name, header_value);
                 }
             }
         }
 
+        if self.organization_override.is_some() {
+            Self::set_active_organization(&mut headers, &org_id);
+        }
+
         // Add essential headers
         headers.insert(
             HeaderName::from_static("content-type"),
@@ -76,7 +114,8 @@ impl CurlParser {
         );
         headers.insert(
             HeaderName::from_static("referer"),
-            HeaderValue::from_str(&format!("https://claude.ai/project/{}", proj_id)).unwrap(),
+            HeaderValue::from_str(&format!("https://claude.ai/project/{}", proj_id))
+                .map_err(|e| format!("Invalid project ID: {}", e))?,
         );
 
         self.organization_id = Some(org_id);
@@ -85,4 +124,316 @@ impl CurlParser {
 
         Ok(())
     }
+
+    /// Points the `lastActiveOrg` cookie at `organization_id`, like claude.ai
+    /// does when switching organizations. Other cookies are kept.
+    fn set_active_organization(headers: &mut HeaderMap, organization_id: &str) {
+        let Some(cookies) = headers.get(COOKIE).and_then(|value| value.to_str().ok()) else {
+            return;
+        };
+        let cookies = cookies
+            .split(';')
+            .map(
+                |cookie| match cookie.trim().strip_prefix("lastActiveOrg=") {
+                    Some(_) => format!("lastActiveOrg={}", organization_id),
+                    None => cookie.trim().to_string(),
+                },
+            )
+            .collect::<Vec<_>>()
+            .join("; ");
+        if let Ok(value) = HeaderValue::from_str(&cookies) {
+            headers.insert(COOKIE, value);
+        }
+    }
+
+    /// Applies `Name: value` lines on top of `headers`, replacing any header
+    /// of the same name. A line without a value (`Name:`) removes the header.
+    /// Returns the lines that are not valid headers.
+    pub fn override_headers(headers: &mut HeaderMap, overrides: &str) -> Vec<String> {
+        let mut invalid = Vec::new();
+        for line in overrides.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once(':') else {
+                invalid.push(line.to_string());
+                continue;
+            };
+            let Ok(name) = HeaderName::from_str(&key.trim().to_lowercase()) else {
+                invalid.push(line.to_string());
+                continue;
+            };
+
+            let value = value.trim();
+            if value.is_empty() {
+                headers.remove(name);
+                continue;
+            }
+            match HeaderValue::from_str(value) {
+                Ok(value) => {
+                    headers.insert(name, value);
+                }
+                Err(_) => invalid.push(line.to_string()),
+            }
+        }
+        invalid
+    }
+
+    /// A hint for the user when the command requests something other than
+    /// the project docs endpoint, e.g. a conversation.
+    pub fn endpoint_warning(curl_text: &str) -> Option<String> {
+        let words = Self::shell_words(curl_text);
+        let url = Self::request_url(&words)?;
+        let path = url.split(['?', '#']).next().unwrap_or(url);
+        let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+
+        // Some accounts have extra segments, e.g. a workspace, between the ids
+        let is_docs = segments.contains(&"organizations")
+            && matches!(segments.as_slice(), [.., "projects", _, "docs"]);
+        if is_docs {
+            return None;
+        }
+
+        let request = if path.contains("conversation") {
+            "a chat conversation request"
+        } else {
+            "not the project docs request"
+        };
+        Some(format!(
+            "This is {} ({}). In the Network tab, filter for \"docs\" and copy the request \
+            ending in /projects/<project-id>/docs instead.",
+            request,
+            path.split("/api").nth(1).unwrap_or(path)
+        ))
+    }
+
+    /// The URL the command requests: the `--url` argument, or else the
+    /// first word after `curl` that is not an option or an option's value.
+    fn request_url(words: &[String]) -> Option<&str> {
+        let start = words
+            .iter()
+            .position(|word| word.ends_with("curl") || word.ends_with("curl.exe"))
+            .map(|index| index + 1)
+            .unwrap_or(0);
+        let mut words = words[start..].iter();
+        while let Some(word) = words.next() {
+            if word == "--url" {
+                return words.next().map(String::as_str);
+            }
+            if OPTIONS_WITH_VALUE.contains(&word.as_str()) {
+                words.next();
+                continue;
+            }
+            if !word.starts_with('-') {
+                return Some(word);
+            }
+        }
+        None
+    }
+
+    /// The path segment following `name`, e.g. the id in
+    /// `/organizations/<id>/...`.
+    fn path_segment_after(url: &str, name: &str) -> Option<String> {
+        let path = url.split(['?', '#']).next().unwrap_or(url);
+        let mut segments = path.split('/');
+        segments.find(|segment| *segment == name)?;
+        segments
+            .next()
+            .filter(|segment| !segment.is_empty())
+            .map(str::to_string)
+    }
+
+    /// Splits a POSIX shell command line into words. Handles single and
+    /// double quotes, backslash escapes, `$'...'` strings and line
+    /// continuations, which is what "Copy as cURL" produces.
+    fn shell_words(text: &str) -> Vec<String> {
+        let mut words = Vec::new();
+        let mut word = String::new();
+        let mut in_word = false;
+        let mut chars = text.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    // Line continuation
+                    Some('\n') => {}
+                    Some('\r') if chars.peek() == Some(&'\n') => {
+                        chars.next();
+                    }
+                    Some(escaped) => {
+                        word.push(escaped);
+                        in_word = true;
+                    }
+                    None => {}
+                },
+                '\'' => {
+                    in_word = true;
+                    for c in chars.by_ref() {
+                        if c == '\'' {
+                            break;
+                        }
+                        word.push(c);
+                    }
+                }
+                '"' => {
+                    in_word = true;
+                    while let Some(c) = chars.next() {
+                        match c {
+                            '"' => break,
+                            '\\' => match chars.next() {
+                                Some(escaped @ ('"' | '\\' | '$' | '`')) => word.push(escaped),
+                                Some('\n') => {}
+                                Some(other) => {
+                                    word.push('\\');
+                                    word.push(other);
+                                }
+                                None => word.push('\\'),
+                            },
+                            _ => word.push(c),
+                        }
+                    }
+                }
+                '$' if chars.peek() == Some(&'\'') => {
+                    chars.next();
+                    in_word = true;
+                    Self::ansi_c_quoted(&mut chars, &mut word);
+                }
+                c if c.is_whitespace() => {
+                    if in_word {
+                        words.push(std::mem::take(&mut word));
+                        in_word = false;
+                    }
+                }
+                _ => {
+                    word.push(c);
+                    in_word = true;
+                }
+            }
+        }
+
+        if in_word {
+            words.push(word);
+        }
+        words
+    }
+
+    /// Reads the rest of a `$'...'` string, resolving its escapes.
+    fn ansi_c_quoted(chars: &mut Peekable<Chars>, word: &mut String) {
+        while let Some(c) = chars.next() {
+            match c {
+                '\'' => return,
+                '\\' => match chars.next() {
+                    Some('n') => word.push('\n'),
+                    Some('r') => word.push('\r'),
+                    Some('t') => word.push('\t'),
+                    Some('x') => {
+                        let hex: String =
+                            std::iter::from_fn(|| chars.next_if(|c| c.is_ascii_hexdigit()))
+                                .take(2)
+                                .collect();
+                        if let Some(c) = u8::from_str_radix(&hex, 16).ok().map(char::from) {
+                            word.push(c);
+                        }
+                    }
+                    Some(other) => word.push(other),
+                    None => {}
+                },
+                _ => word.push(c),
+            }
+        }
+    }
+
+    /// Joins a header value folded over several lines into one line.
+    fn unfold(value: &str) -> String {
+        value
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn is_valid_id(id: &str) -> bool {
+        !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Fragments that stress the shell word splitting.
+    fn curl_fragment() -> impl Strategy<Value = String> {
+        prop_oneof![
+            Just("'".to_string()),
+            Just("\"".to_string()),
+            Just("\\".to_string()),
+            Just("$'".to_string()),
+            Just("\\x".to_string()),
+            Just("\\\n".to_string()),
+            Just("\r\n".to_string()),
+            Just(" -H ".to_string()),
+            Just(" -b ".to_string()),
+            Just("/organizations/".to_string()),
+            Just("/projects/".to_string()),
+            "\\PC{0,8}",
+            any::<String>(),
+        ]
+    }
+
+    fn id() -> impl Strategy<Value = String> {
+        "[A-Za-z0-9-]{1,40}"
+    }
+
+    proptest! {
+        #[test]
+        fn arbitrary_input_never_panics(text in any::<String>()) {
+            let _ = CurlParser::new().parse(&text);
+            let _ = CurlParser::endpoint_warning(&text);
+        }
+
+        #[test]
+        fn malformed_quoting_never_panics(
+            fragments in prop::collection::vec(curl_fragment(), 0..24)
+        ) {
+            let text = format!("curl {}", fragments.concat());
+            let _ = CurlParser::new().parse(&text);
+            let _ = CurlParser::endpoint_warning(&text);
+        }
+
+        #[test]
+        fn valid_curls_round_trip_their_ids(
+            organization_id in id(),
+            project_id in id(),
+            cookie in "[A-Za-z0-9=:+/]{0,40}",
+            quote in prop_oneof![Just("'"), Just("\"")],
+            separator in prop_oneof![Just(" "), Just(" \\\n  "), Just(" \\\r\n  ")],
+        ) {
+            let text = format!(
+                "curl {q}https://claude.ai/api/organizations/{o}/projects/{p}/docs{q}{s}\
+                 -H {q}cookie: sessionKey={c}{q}{s}-H {q}accept: */*{q}",
+                q = quote,
+                s = separator,
+                o = organization_id,
+                p = project_id,
+                c = cookie,
+            );
+
+            let mut parser = CurlParser::new();
+            prop_assert!(parser.parse(&text).is_ok());
+            prop_assert_eq!(parser.organization_id, Some(organization_id));
+            prop_assert_eq!(parser.project_id.clone(), Some(project_id.clone()));
+
+            let headers = parser.headers.unwrap();
+            prop_assert_eq!(
+                headers.get(COOKIE).and_then(|value| value.to_str().ok()),
+                Some(format!("sessionKey={}", cookie).as_str())
+            );
+            prop_assert_eq!(
+                headers.get("referer").and_then(|value| value.to_str().ok()),
+                Some(format!("https://claude.ai/project/{}", project_id).as_str())
+            );
+        }
+    }
 }
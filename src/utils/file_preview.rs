@@ -0,0 +1,47 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Bytes read from each end of a file for a preview.
+const PREVIEW_BYTES: u64 = 4 * 1024;
+
+pub struct FilePreview;
+
+impl FilePreview {
+    /// First and last `lines` lines of a file, read without loading it
+    /// whole. Invalid UTF-8 is shown as replacement characters so the
+    /// offending spot can be found.
+    pub fn head_and_tail(path: &Path, lines: usize) -> io::Result<String> {
+        let mut file = File::open(path)?;
+        let size = file.metadata()?.len();
+
+        let mut head = Vec::new();
+        (&mut file).take(PREVIEW_BYTES).read_to_end(&mut head)?;
+        let head = String::from_utf8_lossy(&head);
+        let head_lines: Vec<&str> = head.lines().take(lines).collect();
+
+        if size <= PREVIEW_BYTES {
+            let total = head.lines().count();
+            if total <= lines * 2 {
+                return Ok(head.into_owned());
+            }
+            let tail_lines: Vec<&str> = head.lines().skip(total - lines).collect();
+            return Ok(Self::join(&head_lines, &tail_lines));
+        }
+
+        let mut tail = Vec::new();
+        file.seek(SeekFrom::Start(
+            size.saturating_sub(PREVIEW_BYTES).max(PREVIEW_BYTES),
+        ))?;
+        file.read_to_end(&mut tail)?;
+        let tail = String::from_utf8_lossy(&tail);
+        let tail_lines: Vec<&str> = tail.lines().collect();
+        let tail_lines = &tail_lines[tail_lines.len().saturating_sub(lines)..];
+
+        Ok(Self::join(&head_lines, tail_lines))
+    }
+
+    fn join(head: &[&str], tail: &[&str]) -> String {
+        format!("{}\n⋯\n{}", head.join("\n"), tail.join("\n"))
+    }
+}
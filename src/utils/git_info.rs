@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+
+/// The commit a folder is checked out at, read with the `git` command.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GitInfo {
+    /// Name of the repository's top-level folder.
+    pub repo: String,
+    /// `None` for a detached HEAD.
+    pub branch: Option<String>,
+    pub commit: String,
+    /// Whether tracked or untracked files differ from the commit.
+    pub dirty: bool,
+    /// Paths with uncommitted changes, relative to the repository root. Not
+    /// stored with history records or the manifest, the flag is enough there.
+    #[serde(default, skip_serializing)]
+    pub changed_files: Vec<String>,
+}
+
+impl GitInfo {
+    /// `None` if the folder is not in a git repository or git is not installed.
+    pub fn read(folder_path: &Path) -> Option<Self> {
+        let toplevel = git(folder_path, &["rev-parse", "--show-toplevel"])?;
+        let commit = git(folder_path, &["rev-parse", "--short", "HEAD"])?;
+        let branch = Self::current_branch(folder_path);
+        // Porcelain lines are a two-letter status, a space and the path
+        let changed_files: Vec<String> = git(folder_path, &["status", "--porcelain"])
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.get(3..))
+            .map(str::to_string)
+            .collect();
+        let repo = Path::new(&toplevel)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or(toplevel);
+
+        Some(Self {
+            repo,
+            branch,
+            commit,
+            dirty: !changed_files.is_empty(),
+            changed_files,
+        })
+    }
+
+    /// The checked out branch alone, cheap enough to poll for a switch.
+    pub fn current_branch(folder_path: &Path) -> Option<String> {
+        git(folder_path, &["rev-parse", "--abbrev-ref", "HEAD"]).filter(|branch| branch != "HEAD")
+    }
+
+    /// E.g. `my-repo @ 1a2b3c4 (main, uncommitted changes)`.
+    pub fn label(&self) -> String {
+        let details: Vec<&str> = self
+            .branch
+            .as_deref()
+            .into_iter()
+            .chain(self.dirty.then_some("uncommitted changes"))
+            .collect();
+        if details.is_empty() {
+            return format!("{} @ {}", self.repo, self.commit);
+        }
+        format!("{} @ {} ({})", self.repo, self.commit, details.join(", "))
+    }
+}
+
+fn git(folder_path: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(folder_path)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .to_string(),
+    )
+}
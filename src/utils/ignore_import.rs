@@ -0,0 +1,66 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Ignore files other tools keep next to the code, and whether their
+/// patterns are relative to the folder root rather than matching at any
+/// depth.
+pub const IMPORTABLE_IGNORE_FILES: [(&str, bool); 3] = [
+    (".dockerignore", true),
+    (".npmignore", false),
+    (".eslintignore", false),
+];
+
+/// Patterns read from an ignore file, converted to `.claudekeep` syntax.
+pub struct ImportedRules {
+    pub patterns: Vec<String>,
+    /// Negated patterns (`!keep.me`) have no equivalent and are left out.
+    pub skipped: usize,
+}
+
+pub struct IgnoreImport;
+
+impl IgnoreImport {
+    pub fn read(path: &Path, root_relative: bool) -> io::Result<ImportedRules> {
+        let content = fs::read_to_string(path)?;
+        let mut patterns = Vec::new();
+        let mut skipped = 0;
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('!') {
+                skipped += 1;
+                continue;
+            }
+
+            let pattern = if root_relative && !line.starts_with('/') && !line.starts_with("**") {
+                format!("/{}", line.trim_start_matches("./"))
+            } else {
+                line.to_string()
+            };
+            patterns.push(pattern);
+        }
+
+        Ok(ImportedRules { patterns, skipped })
+    }
+
+    /// Appends the patterns that are not in `rules` yet, one per line. Returns
+    /// how many were added.
+    pub fn merge(rules: &mut String, patterns: &[String]) -> usize {
+        let mut added = 0;
+        for pattern in patterns {
+            if rules.lines().any(|existing| existing.trim() == pattern) {
+                continue;
+            }
+            if !rules.is_empty() && !rules.ends_with('\n') {
+                rules.push('\n');
+            }
+            rules.push_str(pattern);
+            added += 1;
+        }
+        added
+    }
+}
@@ -1,4 +1,13 @@
 pub mod claude_keep;
+pub mod claude_keep_template;
 pub mod color;
 pub mod curl_parser;
+pub mod file_preview;
 pub mod file_size;
+pub mod git_info;
+pub mod ignore_import;
+pub mod number_format;
+pub mod session_store;
+pub mod single_instance;
+pub mod temp_workspace;
+pub mod time_format;
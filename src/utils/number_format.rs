@@ -0,0 +1,17 @@
+pub struct NumberFormat;
+
+impl NumberFormat {
+    pub fn thousands(value: usize) -> String {
+        let digits = value.to_string();
+        let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+
+        for (idx, digit) in digits.chars().enumerate() {
+            if idx > 0 && (digits.len() - idx) % 3 == 0 {
+                formatted.push(',');
+            }
+            formatted.push(digit);
+        }
+
+        formatted
+    }
+}
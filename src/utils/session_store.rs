@@ -0,0 +1,263 @@
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+const KEYRING_SERVICE: &str = "claude-uploader";
+const KEYRING_USER: &str = "session-key";
+const SESSIONS_FILE_NAME: &str = "sessions.bin";
+/// Sessions encrypted with a passphrase, for systems without a keychain.
+const LOCKED_SESSIONS_FILE_NAME: &str = "sessions.locked";
+const NONCE_LEN: usize = 12;
+const SALT_LEN: usize = 16;
+
+/// A profile's pasted session, restored on the next launch.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StoredSession {
+    pub curl_text: String,
+    pub organization_override: Option<String>,
+    pub project_override: Option<String>,
+}
+
+/// Keeps pasted sessions between launches. They carry the account's
+/// cookies, so the file is encrypted, with a key held in the OS keychain
+/// or, where there is none, derived from a passphrase with Argon2.
+pub struct SessionStore {
+    cipher: ChaCha20Poly1305,
+    path: PathBuf,
+    /// Salt of the passphrase key, written ahead of the encrypted data.
+    salt: Option<[u8; SALT_LEN]>,
+    /// What was written last, to skip rewriting an unchanged file.
+    saved: Option<String>,
+}
+
+impl SessionStore {
+    /// Fetches the key from the keychain, creating it on first use.
+    pub fn open() -> Result<Self, String> {
+        let path = Self::data_dir()?.join(SESSIONS_FILE_NAME);
+        let entry =
+            keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER).map_err(|e| e.to_string())?;
+
+        let key = match entry.get_password() {
+            Ok(hex) => Self::parse_key(&hex).ok_or("malformed key in the keychain")?,
+            Err(keyring::Error::NoEntry) => {
+                let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+                entry
+                    .set_password(&Self::format_key(&key))
+                    .map_err(|e| e.to_string())?;
+                // Whatever was encrypted with a lost key can't be read anymore
+                let _ = fs::remove_file(&path);
+                key
+            }
+            Err(e) => return Err(e.to_string()),
+        };
+
+        Ok(Self {
+            cipher: ChaCha20Poly1305::new(&key),
+            path,
+            salt: None,
+            saved: None,
+        })
+    }
+
+    /// Whether a passphrase protected file is waiting to be unlocked.
+    pub fn has_locked_sessions() -> bool {
+        Self::data_dir()
+            .map(|dir| dir.join(LOCKED_SESSIONS_FILE_NAME).exists())
+            .unwrap_or(false)
+    }
+
+    /// Opens the passphrase protected file, or starts one protected by
+    /// `passphrase`. A wrong passphrase only shows once [`Self::load`] fails.
+    pub fn unlock(passphrase: &str) -> Result<Self, String> {
+        Self::unlock_at(
+            Self::data_dir()?.join(LOCKED_SESSIONS_FILE_NAME),
+            passphrase,
+        )
+    }
+
+    fn unlock_at(path: PathBuf, passphrase: &str) -> Result<Self, String> {
+        if passphrase.is_empty() {
+            return Err("Enter a passphrase".to_string());
+        }
+        // An existing file keeps its salt, so the passphrase derives its key again
+        let salt = match fs::read(&path) {
+            Ok(bytes) if bytes.len() >= SALT_LEN => {
+                let mut salt = [0u8; SALT_LEN];
+                salt.copy_from_slice(&bytes[..SALT_LEN]);
+                salt
+            }
+            _ => {
+                let mut salt = [0u8; SALT_LEN];
+                OsRng.fill_bytes(&mut salt);
+                salt
+            }
+        };
+
+        let mut key = Key::default();
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            cipher: ChaCha20Poly1305::new(&key),
+            path,
+            salt: Some(salt),
+            saved: None,
+        })
+    }
+
+    /// Sessions by profile name; none before the first save.
+    pub fn load(&mut self) -> Result<HashMap<String, StoredSession>, String> {
+        let bytes = match fs::read(&self.path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(e) => return Err(e.to_string()),
+        };
+        let data = match self.salt {
+            Some(_) => bytes.get(SALT_LEN..).unwrap_or_default(),
+            None => &bytes[..],
+        };
+        if data.len() < NONCE_LEN {
+            return Err("the sessions file is truncated".to_string());
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| match self.salt {
+                Some(_) => "Wrong passphrase".to_string(),
+                None => "the sessions file could not be decrypted".to_string(),
+            })?;
+
+        let json = String::from_utf8_lossy(&plaintext).to_string();
+        let sessions = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+        self.saved = Some(json);
+        Ok(sessions)
+    }
+
+    pub fn save(&mut self, sessions: &HashMap<String, StoredSession>) -> Result<(), String> {
+        let json = serde_json::to_string(sessions).map_err(|e| e.to_string())?;
+        if self.saved.as_ref() == Some(&json) {
+            return Ok(());
+        }
+        if sessions.is_empty() {
+            return self.forget();
+        }
+
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, json.as_bytes())
+            .map_err(|e| e.to_string())?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&self.path).map_err(|e| e.to_string())?;
+        file.write_all(self.salt.as_ref().map(|salt| &salt[..]).unwrap_or_default())
+            .and_then(|()| file.write_all(&nonce))
+            .and_then(|()| file.write_all(&ciphertext))
+            .map_err(|e| e.to_string())?;
+
+        self.saved = Some(json);
+        Ok(())
+    }
+
+    /// Removes every saved session from disk.
+    pub fn forget(&mut self) -> Result<(), String> {
+        match fs::remove_file(&self.path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.to_string()),
+        }
+        self.saved = Some("{}".to_string());
+        Ok(())
+    }
+
+    fn data_dir() -> Result<PathBuf, String> {
+        dirs::data_local_dir()
+            .map(|dir| dir.join("claude-uploader"))
+            .ok_or_else(|| "no data directory".to_string())
+    }
+
+    fn format_key(key: &Key) -> String {
+        key.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    fn parse_key(hex: &str) -> Option<Key> {
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+            .collect::<Option<Vec<u8>>>()?;
+        (bytes.len() == 32).then(|| *Key::from_slice(&bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn temp_path(dir: &TempDir) -> PathBuf {
+        dir.path().join("sessions")
+    }
+
+    fn sessions() -> HashMap<String, StoredSession> {
+        let session = StoredSession {
+            curl_text: "curl 'https://claude.ai/api/organizations/org/projects/p/docs'".to_string(),
+            organization_override: Some("org".to_string()),
+            project_override: None,
+        };
+        HashMap::from([("default".to_string(), session)])
+    }
+
+    #[test]
+    fn passphrase_sessions_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = temp_path(&dir);
+        let mut store = SessionStore::unlock_at(path.clone(), "correct horse").unwrap();
+        store.save(&sessions()).unwrap();
+
+        let mut reopened = SessionStore::unlock_at(path, "correct horse").unwrap();
+        assert_eq!(reopened.load().unwrap(), sessions());
+    }
+
+    #[test]
+    fn wrong_passphrase_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = temp_path(&dir);
+        let mut store = SessionStore::unlock_at(path.clone(), "correct horse").unwrap();
+        store.save(&sessions()).unwrap();
+
+        let mut reopened = SessionStore::unlock_at(path, "battery staple").unwrap();
+        assert_eq!(reopened.load(), Err("Wrong passphrase".to_string()));
+    }
+
+    #[test]
+    fn sessions_are_not_stored_in_plain_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = temp_path(&dir);
+        let mut store = SessionStore::unlock_at(path.clone(), "correct horse").unwrap();
+        store.save(&sessions()).unwrap();
+
+        let bytes = fs::read(&path).unwrap();
+        assert!(!String::from_utf8_lossy(&bytes).contains("claude.ai"));
+    }
+
+    #[test]
+    fn empty_passphrase_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(SessionStore::unlock_at(temp_path(&dir), "").is_err());
+    }
+}
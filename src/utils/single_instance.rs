@@ -0,0 +1,276 @@
+use std::collections::hash_map::RandomState;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+const LOCK_FILE_NAME: &str = "instance.lock";
+const TIMEOUT: Duration = Duration::from_millis(500);
+const ACK: &str = "ok";
+/// Tries at creating the lock file, each after a stale one was cleared.
+const CLAIM_ATTEMPTS: u32 = 3;
+/// How often a lock file still being written is read again.
+const SETTLE_INTERVAL: Duration = Duration::from_millis(50);
+
+/// What a later launch asks the running instance to do.
+#[derive(Debug, Clone)]
+pub enum HandOff {
+    /// Bring the window to the front.
+    Focus,
+    /// Bring the window to the front and open a folder in it.
+    OpenFolder(PathBuf),
+}
+
+impl HandOff {
+    fn to_line(&self) -> String {
+        match self {
+            Self::Focus => "focus".to_string(),
+            Self::OpenFolder(folder) => format!("open {}", folder.display()),
+        }
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        match line.split_once(' ') {
+            Some(("open", folder)) => Some(Self::OpenFolder(PathBuf::from(folder))),
+            None if line == "focus" => Some(Self::Focus),
+            _ => None,
+        }
+    }
+}
+
+pub enum Claim {
+    /// This process is the running instance now.
+    Primary(SingleInstance),
+    /// The instance already running accepted the request.
+    HandedOff,
+    /// No guard could be set up, e.g. without a writable data directory.
+    Unguarded,
+}
+
+/// Keeps a second window from running next to the first, where both could
+/// sync and delete docs of the same project. The running instance listens
+/// on a loopback port; a lock file holds that port and a token later
+/// launches present to hand over their request.
+pub struct SingleInstance {
+    listener: TcpListener,
+    token: String,
+}
+
+impl SingleInstance {
+    /// Hands `request` to the running instance, or becomes it. A lock file
+    /// left behind by a crashed instance is taken over, since nobody answers.
+    pub fn claim(request: &HandOff) -> Claim {
+        match Self::lock_path() {
+            Some(lock_path) => Self::claim_at(&lock_path, request),
+            None => Claim::Unguarded,
+        }
+    }
+
+    /// The lock file is created exclusively, so of two launches racing for
+    /// it only one becomes the running instance and the other hands off.
+    fn claim_at(lock_path: &Path, request: &HandOff) -> Claim {
+        for _ in 0..CLAIM_ATTEMPTS {
+            match Self::listen(lock_path) {
+                Ok(instance) => return Claim::Primary(instance),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(e) => {
+                    println!("Could not set up the single-instance guard: {}", e);
+                    return Claim::Unguarded;
+                }
+            }
+
+            let content = Self::read_settled_lock(lock_path);
+            if let Some((port, token)) = content.as_deref().and_then(Self::parse_lock) {
+                match Self::hand_off(port, &token, request) {
+                    Ok(()) => return Claim::HandedOff,
+                    Err(e) => println!("No running instance answered ({}), taking over", e),
+                }
+            }
+            Self::remove_stale_lock(lock_path, content.as_deref().unwrap_or_default());
+        }
+
+        println!("Could not claim the single-instance lock");
+        Claim::Unguarded
+    }
+
+    /// Receives the requests of later launches. `wake` is called after each
+    /// one, so an idle window handles it right away.
+    pub fn serve(self, wake: impl Fn() + Send + 'static) -> Receiver<HandOff> {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            for stream in self.listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                match Self::receive(stream, &self.token) {
+                    Ok(Some(request)) => {
+                        if sender.send(request).is_err() {
+                            break;
+                        }
+                        wake();
+                    }
+                    Ok(None) => println!("Ignored a malformed hand-off"),
+                    Err(e) => println!("Failed to receive a hand-off: {}", e),
+                }
+            }
+        });
+        receiver
+    }
+
+    fn lock_path() -> Option<PathBuf> {
+        dirs::data_local_dir().map(|dir| dir.join("claude-uploader").join(LOCK_FILE_NAME))
+    }
+
+    fn parse_lock(content: &str) -> Option<(u16, String)> {
+        let (port, token) = content.trim().split_once(' ')?;
+        Some((port.parse().ok()?, token.to_string()))
+    }
+
+    /// The lock file's content, waiting a moment for an instance that just
+    /// created it to write its port. `None` once the file is gone.
+    fn read_settled_lock(path: &Path) -> Option<String> {
+        let mut waited = Duration::ZERO;
+        loop {
+            let content = fs::read_to_string(path).ok()?;
+            if Self::parse_lock(&content).is_some() || waited >= TIMEOUT {
+                return Some(content);
+            }
+            std::thread::sleep(SETTLE_INTERVAL);
+            waited += SETTLE_INTERVAL;
+        }
+    }
+
+    /// Clears a lock nobody answers for. It is moved aside first, and put
+    /// back if another launch replaced it with its own in the meantime.
+    fn remove_stale_lock(path: &Path, stale: &str) {
+        let aside = path.with_extension(format!("stale-{}", std::process::id()));
+        if fs::rename(path, &aside).is_err() {
+            return;
+        }
+        if fs::read_to_string(&aside).ok().as_deref() != Some(stale) {
+            let _ = fs::hard_link(&aside, path);
+        }
+        let _ = fs::remove_file(&aside);
+    }
+
+    fn listen(lock_path: &Path) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let port = listener.local_addr()?.port();
+        let token = format!("{:016x}", RandomState::new().build_hasher().finish());
+
+        if let Some(dir) = lock_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        write!(options.open(lock_path)?, "{} {}", port, token)?;
+
+        Ok(Self { listener, token })
+    }
+
+    fn hand_off(port: u16, token: &str, request: &HandOff) -> io::Result<()> {
+        let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+        let mut stream = TcpStream::connect_timeout(&address, TIMEOUT)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        writeln!(stream, "{} {}", token, request.to_line())?;
+
+        // Whatever else listens on a stale port won't acknowledge
+        let mut reply = String::new();
+        BufReader::new(&stream).read_line(&mut reply)?;
+        if reply.trim_end() == ACK {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unexpected reply",
+            ))
+        }
+    }
+
+    fn receive(stream: TcpStream, token: &str) -> io::Result<Option<HandOff>> {
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line)?;
+
+        let request = line
+            .trim_end()
+            .split_once(' ')
+            .filter(|(sent_token, _)| *sent_token == token)
+            .and_then(|(_, request)| HandOff::parse(request));
+        if request.is_some() {
+            writeln!(&stream, "{}", ACK)?;
+        }
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn lock_path(dir: &TempDir) -> PathBuf {
+        dir.path().join("instance.lock")
+    }
+
+    #[test]
+    fn later_launches_hand_off_to_the_running_instance() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = lock_path(&dir);
+        let Claim::Primary(instance) = SingleInstance::claim_at(&path, &HandOff::Focus) else {
+            panic!("the first launch should become the running instance");
+        };
+        let requests = instance.serve(|| {});
+
+        let folder = PathBuf::from("/projects/site");
+        let request = HandOff::OpenFolder(folder.clone());
+        assert!(matches!(
+            SingleInstance::claim_at(&path, &request),
+            Claim::HandedOff
+        ));
+        assert!(matches!(
+            requests.recv_timeout(TIMEOUT),
+            Ok(HandOff::OpenFolder(received)) if received == folder
+        ));
+    }
+
+    #[test]
+    fn stale_locks_are_taken_over() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = lock_path(&dir);
+        // Nothing listens here anymore, like after a crash
+        let port = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .and_then(|listener| listener.local_addr())
+            .unwrap()
+            .port();
+        fs::write(&path, format!("{} 0123456789abcdef", port)).unwrap();
+
+        assert!(matches!(
+            SingleInstance::claim_at(&path, &HandOff::Focus),
+            Claim::Primary(_)
+        ));
+        assert_ne!(
+            SingleInstance::parse_lock(&fs::read_to_string(&path).unwrap()),
+            Some((port, "0123456789abcdef".to_string()))
+        );
+    }
+
+    #[test]
+    fn an_existing_lock_is_never_overwritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = lock_path(&dir);
+        fs::write(&path, "").unwrap();
+        assert_eq!(
+            SingleInstance::listen(&path).err().map(|e| e.kind()),
+            Some(io::ErrorKind::AlreadyExists)
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+const DIR_PREFIX: &str = "claude-uploader-";
+
+/// Where the owning process can't be checked, leftovers this old are removed.
+#[cfg(not(target_os = "linux"))]
+const STALE_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Private scratch directory for intermediate files, readable only by the
+/// current user and removed when dropped. Workspaces left behind by a
+/// crashed process are removed the next time one is created.
+#[derive(Debug)]
+pub struct TempWorkspace {
+    dir: PathBuf,
+    next: AtomicUsize,
+}
+
+impl TempWorkspace {
+    pub fn create() -> io::Result<Self> {
+        let root = std::env::temp_dir();
+        Self::remove_stale(&root);
+
+        let dir = root.join(format!("{}{}", DIR_PREFIX, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut builder = fs::DirBuilder::new();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        builder.create(&dir)?;
+
+        Ok(Self {
+            dir,
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    /// A fresh path in the workspace ending in `name`; never handed out twice.
+    pub fn file_path(&self, name: &str) -> PathBuf {
+        let index = self.next.fetch_add(1, Ordering::Relaxed);
+        self.dir.join(format!("{:04}-{}", index, name))
+    }
+
+    /// Writes `content` into the workspace first and only then moves it to
+    /// `target`, so a failed write never leaves a partial file behind.
+    pub fn write_then_move(&self, target: &Path, content: &str) -> io::Result<()> {
+        let name = target
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let temp = self.file_path(&name);
+        fs::write(&temp, content)?;
+
+        // Renaming fails across file systems, copy there instead
+        let result = fs::rename(&temp, target).or_else(|_| fs::copy(&temp, target).map(|_| ()));
+        let _ = fs::remove_file(&temp);
+        result
+    }
+
+    fn remove_stale(root: &Path) {
+        let Ok(entries) = fs::read_dir(root) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Some(pid) = name
+                .strip_prefix(DIR_PREFIX)
+                .and_then(|pid| pid.parse::<u32>().ok())
+            else {
+                continue;
+            };
+            if pid != std::process::id() && is_stale(&entry.path(), pid) {
+                println!("Removing leftover workspace {}", entry.path().display());
+                let _ = fs::remove_dir_all(entry.path());
+            }
+        }
+    }
+}
+
+impl Drop for TempWorkspace {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_dir_all(&self.dir) {
+            println!("Failed to remove workspace {}: {}", self.dir.display(), e);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn is_stale(_dir: &Path, pid: u32) -> bool {
+    !Path::new("/proc").join(pid.to_string()).exists()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_stale(dir: &Path, _pid: u32) -> bool {
+    fs::metadata(dir)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age > STALE_AGE)
+}
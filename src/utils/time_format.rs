@@ -0,0 +1,72 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub struct TimeFormat;
+
+impl TimeFormat {
+    pub fn ago(time: SystemTime) -> String {
+        let seconds = match SystemTime::now().duration_since(time) {
+            Ok(elapsed) => elapsed.as_secs(),
+            Err(_) => return "just now".to_string(),
+        };
+
+        match seconds {
+            0..=59 => "just now".to_string(),
+            60..=3_599 => format!("{} min ago", seconds / 60),
+            3_600..=86_399 => format!("{} h ago", seconds / 3_600),
+            86_400..=2_591_999 => format!("{} days ago", seconds / 86_400),
+            2_592_000..=31_535_999 => format!("{} months ago", seconds / 2_592_000),
+            _ => format!("{} years ago", seconds / 31_536_000),
+        }
+    }
+
+    /// Calendar date in UTC, e.g. `2024-03-09`.
+    pub fn date(time: SystemTime) -> String {
+        let days = time
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs() / 86_400)
+            .unwrap_or(0) as i64;
+
+        // Days since 1970-01-01 to a proleptic Gregorian date
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 {
+            month_index + 3
+        } else {
+            month_index - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+
+    /// Date and time of day in UTC, e.g. `2024-03-09 14:05 UTC`.
+    pub fn timestamp(time: SystemTime) -> String {
+        let seconds = time
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+        let seconds_of_day = seconds % 86_400;
+        format!(
+            "{} {:02}:{:02} UTC",
+            Self::date(time),
+            seconds_of_day / 3_600,
+            (seconds_of_day % 3_600) / 60
+        )
+    }
+
+    pub fn duration(duration: Duration) -> String {
+        let seconds = duration.as_secs();
+        match seconds {
+            0 => format!("{} ms", duration.as_millis()),
+            1..=59 => format!("{:.1} s", duration.as_secs_f64()),
+            60..=3_599 => format!("{} min {:02} s", seconds / 60, seconds % 60),
+            _ => format!("{} h {:02} min", seconds / 3_600, (seconds % 3_600) / 60),
+        }
+    }
+}